@@ -0,0 +1,37 @@
+// compiles the gsettings schema, so that the app also works when it's not installed with meson
+use std::{env, fs, path::PathBuf, process::Command};
+
+const SCHEMA: &str = "data/io.github.lemmygtk.lemoa.gschema.xml.in";
+
+fn app_id() -> String {
+    let config = fs::read_to_string("src/config.rs").expect("Could not read config.rs");
+    config
+        .lines()
+        .find_map(|line| line.strip_prefix("pub const APP_ID: &str = "))
+        .map(|value| value.trim_end_matches(';').trim_matches('"').to_string())
+        .expect("APP_ID missing in config.rs")
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", SCHEMA);
+    println!("cargo:rerun-if-changed=src/config.rs");
+
+    let app_id = app_id();
+    let schema_dir = PathBuf::from(env::var("OUT_DIR").unwrap()).join("schemas");
+    fs::create_dir_all(&schema_dir).unwrap();
+    let schema = fs::read_to_string(SCHEMA)
+        .unwrap()
+        .replace("@APP_ID@", &app_id);
+    fs::write(schema_dir.join(format!("{}.gschema.xml", app_id)), schema).unwrap();
+
+    let compiled = Command::new("glib-compile-schemas")
+        .arg(&schema_dir)
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false);
+    if !compiled {
+        println!("cargo:warning=Could not compile the gsettings schema, is glib-compile-schemas installed?");
+        // the app then relies on the schema being installed system-wide
+        fs::write(schema_dir.join("gschemas.compiled"), []).unwrap();
+    }
+}
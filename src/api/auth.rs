@@ -1,15 +1,197 @@
-use lemmy_api_common::{person::Login, sensitive::Sensitive};
+use lemmy_api_common::{
+    person::{
+        ChangePassword, GenerateTotpSecretResponse, GetCaptchaResponse, Login, LoginResponse,
+        PasswordReset, Register, UpdateTotp, UpdateTotpResponse,
+    },
+    sensitive::Sensitive,
+    site::GetSiteResponse,
+    SuccessResponse,
+};
+use reqwest::blocking::RequestBuilder;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt;
+
+use crate::settings::Account;
+
+use super::CLIENT;
+
+#[derive(Debug)]
+pub enum AuthError {
+    Request(reqwest::Error),
+    // the error code lemmy responded with, e.g. `username_already_exists`
+    Server(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Request(err) => write!(f, "{}", err),
+            AuthError::Server(code) => {
+                let message = match code.as_str() {
+                    "incorrect_login" => "Wrong credentials!",
+                    "registration_closed" => "The instance doesn't accept new registrations",
+                    "captcha_incorrect" => "The captcha answer is wrong",
+                    "passwords_do_not_match" => "The passwords don't match",
+                    "invalid_password" => "The password must be between 10 and 60 characters long",
+                    "username_already_exists" | "user_already_exists" => {
+                        "The username is already taken"
+                    }
+                    "email_already_exists" => "The email address is already in use",
+                    "email_required" => "The instance requires an email address",
+                    "registration_application_answer_required" => {
+                        "The instance requires an answer to its application question"
+                    }
+                    "invalid_name" => "The username contains invalid characters",
+                    "email_not_verified" => "The email address hasn't been verified yet",
+                    "registration_application_is_pending" => {
+                        "The registration application hasn't been approved yet"
+                    }
+                    "registration_denied" => "The registration application was denied",
+                    "missing_totp_token" => {
+                        "The instance requires a two-factor authentication code"
+                    }
+                    "incorrect_totp_token" => "The two-factor authentication code is wrong",
+                    "no_email_setup" => "The instance can't send emails",
+                    "not_logged_in" => "You need to be logged in",
+                    _ => return write!(f, "The server returned an error: {}", code),
+                };
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for AuthError {
+    fn from(err: reqwest::Error) -> Self {
+        AuthError::Request(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+// keeps the error lemmy responds with instead of failing to parse it like the other requests
+fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, AuthError> {
+    let response = request.send()?;
+    if response.status().is_success() {
+        return Ok(response.json()?);
+    }
+    match response.json::<ErrorResponse>() {
+        Ok(res) => Err(AuthError::Server(res.error)),
+        Err(err) => Err(AuthError::Request(err)),
+    }
+}
+
+// the requests that don't need to be logged in don't send the token, it might be expired
+fn post<T, Params>(path: &str, params: &Params) -> Result<T, AuthError>
+where
+    T: DeserializeOwned,
+    Params: Serialize + std::fmt::Debug,
+{
+    send(CLIENT.post(super::get_url(path)).json(&params))
+}
 
 pub fn login(
     username_or_email: String,
     password: String,
     totp_token: Option<String>,
-) -> std::result::Result<lemmy_api_common::person::LoginResponse, reqwest::Error> {
+) -> std::result::Result<LoginResponse, AuthError> {
     let params = Login {
         username_or_email: Sensitive::new(username_or_email),
         password: Sensitive::new(password),
         totp_2fa_token: totp_token,
     };
 
-    super::post("/user/login", &params)
+    post("/user/login", &params)
+}
+
+pub fn get_captcha() -> std::result::Result<GetCaptchaResponse, reqwest::Error> {
+    super::get("/user/get_captcha", &())
+}
+
+pub fn register(params: Register) -> std::result::Result<LoginResponse, AuthError> {
+    post("/user/register", &params)
+}
+
+pub fn request_password_reset(email: String) -> std::result::Result<SuccessResponse, AuthError> {
+    let params = PasswordReset {
+        email: Sensitive::new(email),
+    };
+    post("/user/password_reset", &params)
+}
+
+// all other sessions are logged out, the response contains a new token for this one
+pub fn change_password(
+    old_password: String,
+    new_password: String,
+    new_password_verify: String,
+) -> std::result::Result<LoginResponse, AuthError> {
+    let params = ChangePassword {
+        old_password: Sensitive::new(old_password),
+        new_password: Sensitive::new(new_password),
+        new_password_verify: Sensitive::new(new_password_verify),
+    };
+    send(
+        CLIENT
+            .put(super::get_url("/user/change_password"))
+            .headers(super::get_auth_header())
+            .json(&params),
+    )
+}
+
+pub fn generate_totp_secret() -> std::result::Result<GenerateTotpSecretResponse, AuthError> {
+    send(
+        CLIENT
+            .post(super::get_url("/user/totp/generate"))
+            .headers(super::get_auth_header()),
+    )
+}
+
+// enabling requires a code generated from the new secret, disabling one from the current secret
+pub fn update_totp(
+    totp_token: String,
+    enabled: bool,
+) -> std::result::Result<UpdateTotpResponse, AuthError> {
+    let params = UpdateTotp {
+        totp_token,
+        enabled,
+    };
+    send(
+        CLIENT
+            .post(super::get_url("/user/totp/update"))
+            .headers(super::get_auth_header())
+            .json(&params),
+    )
+}
+
+// invalidates the token on the server
+pub fn logout(account: &Account) -> std::result::Result<SuccessResponse, AuthError> {
+    send(
+        CLIENT
+            .post(super::get_account_url(account, "/user/logout"))
+            .headers(super::get_account_auth_header(account)),
+    )
+}
+
+// returns whether the token of the account is still accepted by the server, errors if that's unknown
+pub fn validate_session(account: &Account) -> std::result::Result<bool, AuthError> {
+    let response = send::<SuccessResponse>(
+        CLIENT
+            .get(super::get_account_url(account, "/user/validate_auth"))
+            .headers(super::get_account_auth_header(account)),
+    );
+    match response {
+        Ok(_) => Ok(true),
+        Err(AuthError::Server(code)) if code == "not_logged_in" || code == "incorrect_login" => {
+            Ok(false)
+        }
+        // instances before 0.19 don't know the endpoint, but only return the user if the token is valid
+        Err(AuthError::Request(err)) if err.is_decode() => {
+            let site: GetSiteResponse = super::get_as(account, "/site", &())?;
+            Ok(site.my_user.is_some())
+        }
+        Err(err) => Err(err),
+    }
 }
@@ -2,49 +2,143 @@ use crate::settings;
 use rand::distributions::{Alphanumeric, DistString};
 use reqwest::blocking::multipart::Part;
 use serde::Deserialize;
-use std::fs::File;
+use std::fmt;
 use std::io::Read;
+use std::path::Path;
 
 use super::CLIENT;
 
 #[derive(Deserialize)]
 pub struct UploadImageResponse {
-    #[allow(dead_code)]
     msg: String,
+    #[serde(default)]
     files: Vec<UploadImageFile>,
 }
 
 #[derive(Deserialize)]
 struct UploadImageFile {
     pub file: String,
-    #[allow(dead_code)]
     pub delete_token: String,
 }
 
-pub fn upload_image(image: std::path::PathBuf) -> Result<String, reqwest::Error> {
-    let mime_type = mime_guess::from_path(image.clone()).first();
-    let file_name = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
+#[derive(Debug, Clone)]
+pub struct UploadedImage {
+    pub url: String,
+    pub file: String,
+    pub delete_token: String,
+}
+
+#[derive(Debug)]
+pub enum UploadError {
+    NotLoggedIn,
+    UnsupportedType,
+    Io(std::io::Error),
+    Request(reqwest::Error),
+    Server(String),
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::NotLoggedIn => write!(f, "You need to be logged in to upload images"),
+            UploadError::UnsupportedType => write!(f, "The file is not a supported image"),
+            UploadError::Io(err) => write!(f, "Could not read the image: {}", err),
+            UploadError::Request(err) => write!(f, "{}", err),
+            UploadError::Server(msg) => write!(f, "The server rejected the image: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for UploadError {
+    fn from(err: std::io::Error) -> Self {
+        UploadError::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for UploadError {
+    fn from(err: reqwest::Error) -> Self {
+        UploadError::Request(err)
+    }
+}
 
-    let mut file = File::open(image).unwrap();
-    let mut data = Vec::new();
-    file.read_to_end(&mut data).unwrap();
+// reports how much of the image has already been sent to the server
+struct ProgressReader<R, F> {
+    inner: R,
+    read: u64,
+    length: u64,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(f64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.read += count as u64;
+        (self.on_progress)(self.read as f64 / self.length.max(1) as f64);
+        Ok(count)
+    }
+}
+
+pub fn upload_image_file(
+    image: &Path,
+    on_progress: impl FnMut(f64) + Send + 'static,
+) -> Result<UploadedImage, UploadError> {
+    let mime_type = mime_guess::from_path(image)
+        .first()
+        .filter(|mime| mime.type_() == mime_guess::mime::IMAGE)
+        .ok_or(UploadError::UnsupportedType)?;
+    let data = std::fs::read(image)?;
+    upload_image(data, mime_type.essence_str(), on_progress)
+}
+
+pub fn upload_image(
+    data: Vec<u8>,
+    mime_type: &str,
+    on_progress: impl FnMut(f64) + Send + 'static,
+) -> Result<UploadedImage, UploadError> {
+    let account = settings::get_current_account();
+    let jwt = account.jwt.ok_or(UploadError::NotLoggedIn)?;
+    let file_name = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
 
-    let part = Part::bytes(data)
+    let length = data.len() as u64;
+    let reader = ProgressReader {
+        inner: std::io::Cursor::new(data),
+        read: 0,
+        length,
+        on_progress,
+    };
+    let part = Part::reader_with_length(reader, length)
         .file_name(file_name)
-        .mime_str(mime_type.unwrap().essence_str())?;
+        .mime_str(mime_type)?;
     let form = reqwest::blocking::multipart::Form::new().part("images[]", part);
-    let account = settings::get_current_account();
     let base_url = account.instance_url;
     let path = format!("{}/pictrs/image", base_url);
     let res: UploadImageResponse = CLIENT
         .post(path)
-        .header(
-            "cookie",
-            format!("jwt={}", account.jwt.unwrap().into_inner()),
-        )
+        .header("cookie", format!("jwt={}", jwt.into_inner()))
         .multipart(form)
         .send()?
         .json()?;
 
-    Ok(format!("{}/pictrs/image/{}", base_url, res.files[0].file))
+    let Some(file) = res.files.into_iter().next() else {
+        return Err(UploadError::Server(res.msg));
+    };
+    Ok(UploadedImage {
+        url: format!("{}/pictrs/image/{}", base_url, file.file),
+        file: file.file,
+        delete_token: file.delete_token,
+    })
+}
+
+pub fn delete_image(file: &str, delete_token: &str) -> Result<(), reqwest::Error> {
+    let account = settings::get_current_account();
+    let path = format!(
+        "{}/pictrs/image/delete/{}/{}",
+        account.instance_url, delete_token, file
+    );
+    let mut request = CLIENT.get(path);
+    if let Some(jwt) = account.jwt {
+        request = request.header("cookie", format!("jwt={}", jwt.into_inner()));
+    }
+    request.send()?.error_for_status()?;
+    Ok(())
 }
@@ -1,4 +1,10 @@
-use lemmy_api_common::site::{GetFederatedInstancesResponse, InstanceWithFederationState};
+use lemmy_api_common::{
+    lemmy_db_schema::newtypes::InstanceId,
+    site::{
+        BlockInstance, BlockInstanceResponse, GetFederatedInstancesResponse,
+        InstanceWithFederationState,
+    },
+};
 
 pub fn fetch_instances(
     query_filter: &str,
@@ -27,3 +33,12 @@ pub fn fetch_instances(
         None => Ok(vec![]),
     }
 }
+
+pub fn block_instance(
+    instance_id: InstanceId,
+    block: bool,
+) -> std::result::Result<BlockInstanceResponse, reqwest::Error> {
+    let params = BlockInstance { instance_id, block };
+
+    super::post("/site/block", &params)
+}
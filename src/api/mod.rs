@@ -2,7 +2,7 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     config,
-    settings::{self, get_current_account},
+    settings::{self, get_current_account, Account},
 };
 
 pub mod auth;
@@ -32,18 +32,26 @@ pub static CLIENT: Lazy<Client> = Lazy::new(|| {
         .expect("Failed to create reqwest http client!")
 });
 
-fn get_api_url() -> String {
-    format!("{}/api/{}", get_current_account().instance_url, API_VERSION)
+fn get_api_url(account: &Account) -> String {
+    format!("{}/api/{}", account.instance_url, API_VERSION)
 }
 
 fn get_url(path: &str) -> String {
-    format!("{}{}", get_api_url(), path)
+    get_account_url(&get_current_account(), path)
+}
+
+fn get_account_url(account: &Account, path: &str) -> String {
+    format!("{}{}", get_api_url(account), path)
 }
 
 fn get_auth_header() -> HeaderMap<HeaderValue> {
+    get_account_auth_header(&settings::get_current_account())
+}
+
+fn get_account_auth_header(account: &Account) -> HeaderMap<HeaderValue> {
     let mut headers = HeaderMap::new();
 
-    if let Some(jwt) = settings::get_current_account().jwt {
+    if let Some(jwt) = account.jwt.clone() {
         let auth_string = "Bearer ".to_string() + &jwt.into_inner();
         headers.insert(
             "Authorization",
@@ -67,6 +75,20 @@ where
         .json()
 }
 
+// like get, but for any of the accounts instead of only the current one
+fn get_as<T, Params>(account: &Account, path: &str, params: &Params) -> Result<T, reqwest::Error>
+where
+    T: DeserializeOwned,
+    Params: Serialize + std::fmt::Debug,
+{
+    CLIENT
+        .get(get_account_url(account, path))
+        .headers(get_account_auth_header(account))
+        .query(&params)
+        .send()?
+        .json()
+}
+
 fn post<T, Params>(path: &str, params: &Params) -> Result<T, reqwest::Error>
 where
     T: DeserializeOwned,
@@ -2,7 +2,7 @@ use itertools::Itertools;
 use lemmy_api_common::{
     comment::{GetComments, GetCommentsResponse},
     lemmy_db_schema::{
-        newtypes::{CommunityId, PostId},
+        newtypes::{CommentId, CommunityId, LanguageId, PostId},
         CommentSortType, ListingType,
     },
     lemmy_db_views::structs::CommentView,
@@ -11,8 +11,32 @@ use lemmy_api_common::{
         GetPostResponse, MarkPostAsRead, PostReportResponse, PostResponse, SavePost,
     },
 };
+use serde::Serialize;
 use std::result::Result;
 
+#[derive(Debug, Clone, Default)]
+pub struct PostDetails {
+    pub name: String,
+    pub body: String,
+    pub url: Option<reqwest::Url>,
+    pub nsfw: bool,
+    pub language_id: Option<LanguageId>,
+    pub custom_thumbnail: Option<reqwest::Url>,
+    pub alt_text: Option<String>,
+}
+
+// custom thumbnails and alt texts are only supported by newer Lemmy versions,
+// hence they're not part of the request types of lemmy_api_common yet
+#[derive(Debug, Serialize)]
+struct WithThumbnail<T: Serialize> {
+    #[serde(flatten)]
+    params: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_thumbnail: Option<reqwest::Url>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alt_text: Option<String>,
+}
+
 pub fn get_post(id: PostId) -> Result<GetPostResponse, reqwest::Error> {
     let params = GetPost {
         id: Some(id),
@@ -22,10 +46,16 @@ pub fn get_post(id: PostId) -> Result<GetPostResponse, reqwest::Error> {
     super::get("/post", &params)
 }
 
-pub fn get_comments(post_id: PostId) -> Result<Vec<CommentView>, reqwest::Error> {
+// only the thread below the parent is fetched if one is given
+pub fn get_comments(
+    post_id: PostId,
+    sort: CommentSortType,
+    parent_id: Option<CommentId>,
+) -> Result<Vec<CommentView>, reqwest::Error> {
     let params = GetComments {
         post_id: Some(post_id),
-        sort: Some(CommentSortType::Hot),
+        parent_id,
+        sort: Some(sort),
         type_: Some(ListingType::All),
         max_depth: Some(8),
         ..Default::default()
@@ -57,33 +87,37 @@ pub fn default_post() -> GetPostResponse {
 }
 
 pub fn create_post(
-    name: String,
-    body: String,
-    url: Option<reqwest::Url>,
-    community_id: i32,
+    details: PostDetails,
+    community_id: CommunityId,
 ) -> Result<PostResponse, reqwest::Error> {
-    let params = CreatePost {
-        name,
-        body: Some(body),
-        url,
-        community_id: CommunityId(community_id),
-        ..Default::default()
+    let params = WithThumbnail {
+        params: CreatePost {
+            name: details.name,
+            body: Some(details.body),
+            url: details.url,
+            community_id,
+            nsfw: Some(details.nsfw),
+            language_id: details.language_id,
+            ..Default::default()
+        },
+        custom_thumbnail: details.custom_thumbnail,
+        alt_text: details.alt_text,
     };
     super::post("/post", &params)
 }
 
-pub fn edit_post(
-    name: String,
-    url: Option<reqwest::Url>,
-    body: String,
-    post_id: i32,
-) -> Result<PostResponse, reqwest::Error> {
-    let params = EditPost {
-        name: Some(name),
-        body: Some(body),
-        url,
-        post_id: PostId(post_id),
-        ..Default::default()
+pub fn edit_post(details: PostDetails, post_id: PostId) -> Result<PostResponse, reqwest::Error> {
+    let params = WithThumbnail {
+        params: EditPost {
+            name: Some(details.name),
+            body: Some(details.body),
+            url: details.url,
+            post_id,
+            nsfw: Some(details.nsfw),
+            language_id: details.language_id,
+        },
+        custom_thumbnail: details.custom_thumbnail,
+        alt_text: details.alt_text,
     };
     super::put("/post", &params)
 }
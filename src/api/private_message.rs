@@ -1,8 +1,10 @@
+use crate::settings::Account;
 use lemmy_api_common::{
     lemmy_db_schema::newtypes::{PersonId, PrivateMessageId},
     private_message::{
-        CreatePrivateMessage, EditPrivateMessage, GetPrivateMessages, PrivateMessageResponse,
-        PrivateMessagesResponse,
+        CreatePrivateMessage, CreatePrivateMessageReport, DeletePrivateMessage, EditPrivateMessage,
+        GetPrivateMessages, MarkPrivateMessageAsRead, PrivateMessageReportResponse,
+        PrivateMessageResponse, PrivateMessagesResponse,
     },
 };
 
@@ -32,10 +34,55 @@ pub fn list_private_messages(
     unread_only: bool,
     page: i64,
 ) -> std::result::Result<PrivateMessagesResponse, reqwest::Error> {
+    // messages are grouped into conversations, which requires more than the default of ten
     let params = GetPrivateMessages {
         unread_only: Some(unread_only),
         page: Some(page),
+        limit: Some(50),
         ..Default::default()
     };
     super::get("/private_message/list", &params)
 }
+
+pub fn delete_private_message(
+    private_message_id: PrivateMessageId,
+    deleted: bool,
+) -> std::result::Result<PrivateMessageResponse, reqwest::Error> {
+    let params = DeletePrivateMessage {
+        private_message_id,
+        deleted,
+    };
+    super::post("/private_message/delete", &params)
+}
+
+pub fn report_private_message(
+    private_message_id: PrivateMessageId,
+    reason: String,
+) -> std::result::Result<PrivateMessageReportResponse, reqwest::Error> {
+    let params = CreatePrivateMessageReport {
+        private_message_id,
+        reason,
+    };
+    super::post("/private_message/report", &params)
+}
+
+pub fn mark_private_message_as_read(
+    private_message_id: PrivateMessageId,
+    read: bool,
+) -> std::result::Result<PrivateMessageResponse, reqwest::Error> {
+    let params = MarkPrivateMessageAsRead {
+        private_message_id,
+        read,
+    };
+    super::post("/private_message/mark_as_read", &params)
+}
+
+pub fn get_unread_private_messages(
+    account: &Account,
+) -> std::result::Result<PrivateMessagesResponse, reqwest::Error> {
+    let params = GetPrivateMessages {
+        unread_only: Some(true),
+        ..Default::default()
+    };
+    super::get_as(account, "/private_message/list", &params)
+}
@@ -1,6 +1,6 @@
 use lemmy_api_common::{
     lemmy_db_schema::{SearchType, SortType},
-    site::{Search, SearchResponse},
+    site::{ResolveObject, ResolveObjectResponse, Search, SearchResponse},
 };
 
 pub fn fetch_search(
@@ -18,3 +18,10 @@ pub fn fetch_search(
 
     super::get("/search", &params)
 }
+
+// looks up a post, comment, community or person by its url or a shortened identifier
+// like !community@instance, federating it to the current instance if needed
+pub fn resolve_object(query: String) -> std::result::Result<ResolveObjectResponse, reqwest::Error> {
+    let params = ResolveObject { q: query };
+    super::get("/resolve_object", &params)
+}
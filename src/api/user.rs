@@ -1,9 +1,16 @@
+use crate::settings::Account;
 use lemmy_api_common::{
-    lemmy_db_schema::{newtypes::PersonId, CommentSortType},
+    lemmy_db_schema::{
+        newtypes::{CommentReplyId, PersonId, PersonMentionId},
+        CommentSortType,
+    },
     person::{
-        BlockPerson, BlockPersonResponse, GetPersonDetails, GetPersonDetailsResponse,
-        GetPersonMentions, GetPersonMentionsResponse, GetReplies, GetRepliesResponse,
+        BlockPerson, BlockPersonResponse, CommentReplyResponse, GetPersonDetails,
+        GetPersonDetailsResponse, GetPersonMentions, GetPersonMentionsResponse, GetReplies,
+        GetRepliesResponse, GetUnreadCountResponse, MarkCommentReplyAsRead,
+        MarkPersonMentionAsRead, PersonMentionResponse, SaveUserSettings,
     },
+    SuccessResponse,
 };
 
 pub fn get_user(
@@ -30,6 +37,12 @@ pub fn block_user(
     super::post("/user/block", &params)
 }
 
+pub fn save_user_settings(
+    params: SaveUserSettings,
+) -> std::result::Result<SuccessResponse, reqwest::Error> {
+    super::put("/user/save_user_settings", &params)
+}
+
 pub fn default_person() -> GetPersonDetailsResponse {
     serde_json::from_str(include_str!("../examples/person.json")).unwrap()
 }
@@ -63,3 +76,52 @@ pub fn get_replies(
 pub fn mark_all_as_read() -> std::result::Result<GetRepliesResponse, reqwest::Error> {
     super::post("/user/mark_all_as_read", &())
 }
+
+pub fn get_unread_count() -> std::result::Result<GetUnreadCountResponse, reqwest::Error> {
+    super::get("/user/unread_count", &())
+}
+
+pub fn mark_reply_as_read(
+    comment_reply_id: CommentReplyId,
+    read: bool,
+) -> std::result::Result<CommentReplyResponse, reqwest::Error> {
+    let params = MarkCommentReplyAsRead {
+        comment_reply_id,
+        read,
+    };
+    super::post("/comment/mark_as_read", &params)
+}
+
+pub fn mark_mention_as_read(
+    person_mention_id: PersonMentionId,
+    read: bool,
+) -> std::result::Result<PersonMentionResponse, reqwest::Error> {
+    let params = MarkPersonMentionAsRead {
+        person_mention_id,
+        read,
+    };
+    super::post("/user/mention/mark_as_read", &params)
+}
+
+// the newest unread replies of any account, used for the notifications
+pub fn get_unread_replies(
+    account: &Account,
+) -> std::result::Result<GetRepliesResponse, reqwest::Error> {
+    let params = GetReplies {
+        unread_only: Some(true),
+        sort: Some(CommentSortType::New),
+        ..Default::default()
+    };
+    super::get_as(account, "/user/replies", &params)
+}
+
+pub fn get_unread_mentions(
+    account: &Account,
+) -> std::result::Result<GetPersonMentionsResponse, reqwest::Error> {
+    let params = GetPersonMentions {
+        unread_only: Some(true),
+        sort: Some(CommentSortType::New),
+        ..Default::default()
+    };
+    super::get_as(account, "/user/mention", &params)
+}
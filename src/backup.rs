@@ -0,0 +1,378 @@
+// exports all local data into a single file and merges such a file into an existing installation
+// that way a setup can be moved to another machine
+use crate::drafts::{self, Draft};
+use crate::gsettings;
+use crate::gtk::{gio::prelude::*, glib};
+use crate::secrets;
+use crate::settings::{self, Account};
+use crate::uploads::{self, Upload};
+use lemmy_api_common::sensitive::Sensitive;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, io::Write, path::Path};
+
+// increased whenever the format of the backup changes
+const BACKUP_VERSION: u32 = 1;
+
+// the window state only makes sense on the machine it has been saved on
+const EXCLUDED_KEYS: [&str; 3] = ["window-width", "window-height", "window-maximized"];
+
+#[derive(Debug)]
+pub enum BackupError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    // the tokens can't be read until the encrypted file has been unlocked
+    SecretsLocked,
+}
+
+impl fmt::Display for BackupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupError::Io(err) => write!(f, "Could not access the backup file: {}", err),
+            BackupError::Json(err) => write!(f, "The backup file is invalid: {}", err),
+            BackupError::UnsupportedVersion(version) => write!(
+                f,
+                "The backup has been created by a newer version of Lemoa (format {})",
+                version
+            ),
+            BackupError::SecretsLocked => write!(
+                f,
+                "The login tokens can't be exported while the encrypted token file is locked. Unlock it or export without tokens."
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for BackupError {
+    fn from(err: std::io::Error) -> Self {
+        BackupError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for BackupError {
+    fn from(err: serde_json::Error) -> Self {
+        BackupError::Json(err)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct BackupAccount {
+    #[serde(flatten)]
+    account: Account,
+    // only included if the user chose to, anyone with the file can use the account otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<Sensitive<String>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Backup {
+    version: u32,
+    created: chrono::DateTime<chrono::Utc>,
+    accounts: Vec<BackupAccount>,
+    // the gsettings keys with their values in the text format of GVariant
+    preferences: BTreeMap<String, String>,
+    drafts: Vec<Draft>,
+    uploads: Vec<Upload>,
+}
+
+// how to handle data that exists both locally and in the backup, but differs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    KeepExisting,
+    UseImported,
+}
+
+// what an import changes, shown to the user before anything is merged
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub new_accounts: usize,
+    // the labels of the accounts that exist locally too
+    pub conflicting_accounts: Vec<String>,
+    pub new_drafts: usize,
+    pub conflicting_drafts: usize,
+    pub new_preferences: usize,
+    pub conflicting_preferences: usize,
+    pub new_uploads: usize,
+}
+
+impl ImportSummary {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicting_accounts.is_empty()
+            || self.conflicting_drafts > 0
+            || self.conflicting_preferences > 0
+    }
+
+    pub fn description(&self) -> String {
+        let mut lines = vec![format!(
+            "{} new accounts, {} new drafts, {} uploads and {} preferences will be added.",
+            self.new_accounts, self.new_drafts, self.new_uploads, self.new_preferences
+        )];
+        if !self.conflicting_accounts.is_empty() {
+            lines.push(format!(
+                "These accounts exist already: {}.",
+                self.conflicting_accounts.join(", ")
+            ));
+        }
+        if self.conflicting_drafts > 0 {
+            lines.push(format!(
+                "{} drafts differ from the existing ones.",
+                self.conflicting_drafts
+            ));
+        }
+        if self.conflicting_preferences > 0 {
+            lines.push(format!(
+                "{} preferences differ from the existing ones.",
+                self.conflicting_preferences
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+// accounts are the same if they belong to the same user of the same instance
+fn is_same_account(a: &Account, b: &Account) -> bool {
+    a.instance_url == b.instance_url && a.name == b.name
+}
+
+fn is_same_draft(a: &Draft, b: &Draft) -> bool {
+    a.target == b.target && a.instance_url == b.instance_url
+}
+
+fn has_same_content(a: &Draft, b: &Draft) -> bool {
+    a.name == b.name && a.body == b.body && a.url == b.url
+}
+
+// the keys of the backup that can be applied, together with their parsed value
+fn parse_preferences(preferences: &BTreeMap<String, String>) -> Vec<(String, glib::Variant)> {
+    let settings = gsettings::settings();
+    let Some(schema) = settings.settings_schema() else {
+        return vec![];
+    };
+    preferences
+        .iter()
+        .filter(|(key, _)| !EXCLUDED_KEYS.contains(&key.as_str()))
+        // keys of other versions of lemoa are skipped
+        .filter(|(key, _)| schema.has_key(key))
+        .filter_map(|(key, value)| {
+            let key_type = schema.key(key).value_type();
+            match glib::Variant::parse(Some(&key_type), value) {
+                Ok(value) if schema.key(key).range_check(&value) => Some((key.clone(), value)),
+                _ => {
+                    println!("Skipping invalid value of {} in the backup: {}", key, value);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// the backup may contain login tokens, so only the user can read it
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    #[cfg(unix)]
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // the mode only applies to new files, but an existing one might be overwritten
+    #[cfg(unix)]
+    file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    file.write_all(data)
+}
+
+pub fn export(path: &Path, include_tokens: bool) -> Result<(), BackupError> {
+    let prefs = settings::get_prefs();
+    // the tokens would be missing otherwise, which logs the accounts out after an import
+    let tokens_missing = prefs
+        .accounts
+        .iter()
+        .any(|account| account.jwt.is_none() && account.token_ref.is_some());
+    if include_tokens && tokens_missing && secrets::is_locked() {
+        return Err(BackupError::SecretsLocked);
+    }
+    let accounts = prefs
+        .accounts
+        .into_iter()
+        .filter(|account| !account.instance_url.is_empty())
+        .map(|mut account| {
+            let token = account.jwt.take().filter(|_| include_tokens);
+            // the reference only points into the secret store of this machine
+            account.token_ref = None;
+            // without the token, the account needs to log in again after it has been imported
+            if token.is_none() && !account.name.is_empty() {
+                account.session_expired = true;
+            }
+            BackupAccount { account, token }
+        })
+        .collect();
+
+    let settings = gsettings::settings();
+    let preferences = settings
+        .settings_schema()
+        .map(|schema| schema.list_keys())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|key| !EXCLUDED_KEYS.contains(&key.as_str()))
+        .map(|key| {
+            (
+                key.to_string(),
+                settings.value(&key).print(false).to_string(),
+            )
+        })
+        .collect();
+
+    let backup = Backup {
+        version: BACKUP_VERSION,
+        created: chrono::Utc::now(),
+        accounts,
+        preferences,
+        drafts: drafts::get_all_drafts(),
+        uploads: uploads::get_all_uploads(),
+    };
+    write_private(path, &serde_json::to_vec_pretty(&backup)?)?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Backup, BackupError> {
+    let backup: Backup = serde_json::from_slice(&std::fs::read(path)?)?;
+    if backup.version > BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(backup.version));
+    }
+    Ok(backup)
+}
+
+impl Backup {
+    pub fn summary(&self) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+
+        let accounts = settings::get_prefs().accounts;
+        for imported in &self.accounts {
+            match accounts
+                .iter()
+                .find(|account| is_same_account(account, &imported.account))
+            {
+                Some(_) => summary.conflicting_accounts.push(imported.account.label()),
+                None => summary.new_accounts += 1,
+            }
+        }
+
+        let drafts = drafts::get_all_drafts();
+        for imported in &self.drafts {
+            match drafts.iter().find(|draft| is_same_draft(draft, imported)) {
+                Some(draft) if !has_same_content(draft, imported) => {
+                    summary.conflicting_drafts += 1
+                }
+                Some(_) => {}
+                None => summary.new_drafts += 1,
+            }
+        }
+
+        // preferences that haven't been changed locally yet are taken over without asking
+        let settings = gsettings::settings();
+        for (key, value) in parse_preferences(&self.preferences) {
+            match settings.user_value(&key) {
+                Some(existing) if existing != value => summary.conflicting_preferences += 1,
+                Some(_) => {}
+                None if settings.value(&key) != value => summary.new_preferences += 1,
+                None => {}
+            }
+        }
+
+        let uploads = uploads::get_all_uploads();
+        summary.new_uploads = self
+            .uploads
+            .iter()
+            .filter(|imported| !uploads.iter().any(|upload| upload.url == imported.url))
+            .count();
+
+        summary
+    }
+
+    pub fn import(self, resolution: ConflictResolution) {
+        let use_imported = resolution == ConflictResolution::UseImported;
+
+        let mut prefs = settings::get_prefs();
+        let current_account = prefs
+            .accounts
+            .get(prefs.current_account_index as usize)
+            .cloned();
+        let imports_accounts = !self.accounts.is_empty();
+        for BackupAccount { mut account, token } in self.accounts {
+            if account.instance_url.is_empty() {
+                continue;
+            }
+            account.token_ref = None;
+            if token.is_some() {
+                account.jwt = token;
+                account.session_expired = false;
+            }
+            match prefs
+                .accounts
+                .iter_mut()
+                .find(|existing| is_same_account(existing, &account))
+            {
+                Some(existing) if use_imported => {
+                    // a backup without tokens shouldn't log out the existing account
+                    if account.jwt.is_none() {
+                        account.jwt = existing.jwt.clone();
+                        account.session_expired = existing.session_expired;
+                    }
+                    // keeps the reference, so that the old token is replaced in the secret store
+                    account.token_ref = existing.token_ref.clone();
+                    *existing = account;
+                }
+                Some(_) => {}
+                None => prefs.accounts.push(account),
+            }
+        }
+        // the empty account of a new installation is replaced by the imported ones
+        if imports_accounts {
+            prefs
+                .accounts
+                .retain(|account| !account.instance_url.is_empty());
+        }
+        prefs.current_account_index = current_account
+            .and_then(|current| {
+                prefs
+                    .accounts
+                    .iter()
+                    .position(|account| is_same_account(account, &current))
+            })
+            .unwrap_or(0) as u32;
+        if prefs.accounts.is_empty() {
+            prefs.accounts.push(Account::default());
+        }
+        settings::save_prefs(&prefs);
+
+        let settings = gsettings::settings();
+        for (key, value) in parse_preferences(&self.preferences) {
+            if settings.user_value(&key).is_none() || use_imported {
+                if let Err(err) = settings.set_value(&key, &value) {
+                    println!("{}", err);
+                }
+            }
+        }
+
+        let mut drafts = drafts::get_all_drafts();
+        for imported in self.drafts {
+            match drafts
+                .iter_mut()
+                .find(|draft| is_same_draft(draft, &imported))
+            {
+                Some(draft) if use_imported => *draft = imported,
+                Some(_) => {}
+                None => drafts.push(imported),
+            }
+        }
+        drafts::save_drafts(&drafts);
+
+        let mut uploads = uploads::get_all_uploads();
+        for imported in self.uploads {
+            if !uploads.iter().any(|upload| upload.url == imported.url) {
+                uploads.push(imported);
+            }
+        }
+        uploads::save_uploads(&uploads);
+    }
+}
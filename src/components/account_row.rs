@@ -1,7 +1,10 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
-use crate::settings::{self, Account};
+use crate::{
+    api,
+    settings::{self, Account},
+};
 
 use super::accounts_page::AccountsPageInput;
 
@@ -42,6 +45,13 @@ impl FactoryComponent for AccountRow {
                         }
                     },
                 },
+                gtk::Label {
+                    set_label: "Session expired",
+                    add_css_class: "error",
+                    set_margin_start: 10,
+                    #[watch]
+                    set_visible: self.account.session_expired,
+                },
                 gtk::Box {
                     set_hexpand: true,
                 },
@@ -75,18 +85,19 @@ impl FactoryComponent for AccountRow {
 
     fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
         match message {
-            AccountRowInput::Select => {
-                settings::update_account_index(self.index);
-                let message = if self.account.instance_url.is_empty() {
-                    crate::AppMsg::ChooseInstance
-                } else {
-                    crate::AppMsg::OpenPosts
-                };
-                sender
-                    .output_sender()
-                    .emit(AccountsPageInput::Forward(message))
-            }
+            AccountRowInput::Select => sender.output_sender().emit(AccountsPageInput::Forward(
+                crate::AppMsg::SwitchAccount(self.index),
+            )),
             AccountRowInput::Logout => {
+                if self.account.jwt.is_some() {
+                    let account = self.account.clone();
+                    std::thread::spawn(move || {
+                        if let Err(err) = api::auth::logout(&account) {
+                            println!("{}", err);
+                        }
+                    });
+                }
+                self.account.session_expired = false;
                 self.account.name = "".to_string();
                 self.account.id = 0;
                 self.account.jwt = None;
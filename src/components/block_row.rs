@@ -0,0 +1,126 @@
+use gtk::prelude::*;
+use lemmy_api_common::lemmy_db_schema::newtypes::{CommunityId, DbUrl, InstanceId, PersonId};
+use relm4::prelude::*;
+use relm4_components::web_image::WebImage;
+
+use crate::util::get_web_image_msg;
+
+use super::blocks_page::BlocksPageInput;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockTarget {
+    Person(PersonId),
+    Community(CommunityId),
+    Instance(InstanceId),
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub target: BlockTarget,
+    pub name: String,
+    // the full address, e.g. the actor id of a person
+    pub address: String,
+    pub icon: Option<DbUrl>,
+}
+
+pub struct BlockRow {
+    block: Block,
+    icon: Controller<WebImage>,
+}
+
+impl BlockRow {
+    pub fn target(&self) -> BlockTarget {
+        self.block.target
+    }
+}
+
+#[derive(Debug)]
+pub enum BlockRowInput {
+    Open,
+    Unblock,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for BlockRow {
+    type Init = Block;
+    type Input = BlockRowInput;
+    type Output = BlocksPageInput;
+    type ParentInput = BlocksPageInput;
+    type ParentWidget = gtk::Box;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Horizontal,
+            set_spacing: 10,
+            set_margin_top: 5,
+            set_margin_bottom: 5,
+
+            #[local_ref]
+            icon -> gtk::Box {
+                set_size_request: (35, 35),
+                set_hexpand: false,
+                set_visible: self.block.icon.is_some(),
+            },
+            gtk::Button {
+                set_hexpand: true,
+                add_css_class: "flat",
+                set_sensitive: !matches!(self.block.target, BlockTarget::Instance(_)),
+                connect_clicked => BlockRowInput::Open,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+
+                    gtk::Label {
+                        set_label: &self.block.name,
+                        set_halign: gtk::Align::Start,
+                    },
+                    gtk::Label {
+                        set_label: &self.block.address,
+                        set_halign: gtk::Align::Start,
+                        add_css_class: "dim-label",
+                        set_ellipsize: gtk::pango::EllipsizeMode::Middle,
+                    },
+                },
+            },
+            gtk::Button {
+                set_label: "Unblock",
+                set_valign: gtk::Align::Center,
+                connect_clicked => BlockRowInput::Unblock,
+            },
+        }
+    }
+
+    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
+        let icon = WebImage::builder().launch("".to_string()).detach();
+        icon.emit(get_web_image_msg(init.icon.clone()));
+        Self { block: init, icon }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: &Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let icon = self.icon.widget();
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn forward_to_parent(output: Self::Output) -> Option<Self::ParentInput> {
+        Some(output)
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        match message {
+            BlockRowInput::Open => sender
+                .output_sender()
+                .emit(BlocksPageInput::Open(self.block.target)),
+            BlockRowInput::Unblock => sender
+                .output_sender()
+                .emit(BlocksPageInput::Unblock(self.block.target)),
+        }
+    }
+}
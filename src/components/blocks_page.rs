@@ -0,0 +1,235 @@
+use gtk::prelude::*;
+use lemmy_api_common::site::MyUserInfo;
+use relm4::{factory::FactoryVecDeque, prelude::*};
+
+use crate::api;
+
+use super::block_row::{Block, BlockRow, BlockTarget};
+
+pub struct BlocksPage {
+    loading: bool,
+    persons: FactoryVecDeque<BlockRow>,
+    communities: FactoryVecDeque<BlockRow>,
+    instances: FactoryVecDeque<BlockRow>,
+}
+
+#[derive(Debug)]
+pub enum BlocksPageInput {
+    Update,
+    DoneUpdate(Box<MyUserInfo>),
+    Open(BlockTarget),
+    Unblock(BlockTarget),
+    Unblocked(BlockTarget),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for BlocksPage {
+    type Init = ();
+    type Input = BlocksPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Label {
+                set_label: "Blocked",
+                add_css_class: "font-very-bold",
+                set_margin_top: 10,
+            },
+
+            gtk::Spinner {
+                set_spinning: true,
+                set_margin_top: 10,
+                #[watch]
+                set_visible: model.loading,
+            },
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+                #[watch]
+                set_visible: !model.loading,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_all: 10,
+                    set_spacing: 5,
+
+                    gtk::Label {
+                        set_label: "Users",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                    },
+                    gtk::Label {
+                        set_label: "No users are blocked",
+                        set_halign: gtk::Align::Start,
+                        add_css_class: "dim-label",
+                        #[watch]
+                        set_visible: model.persons.is_empty(),
+                    },
+                    #[local_ref]
+                    persons_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+
+                    gtk::Label {
+                        set_label: "Communities",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 15,
+                    },
+                    gtk::Label {
+                        set_label: "No communities are blocked",
+                        set_halign: gtk::Align::Start,
+                        add_css_class: "dim-label",
+                        #[watch]
+                        set_visible: model.communities.is_empty(),
+                    },
+                    #[local_ref]
+                    communities_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+
+                    gtk::Label {
+                        set_label: "Instances",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 15,
+                    },
+                    gtk::Label {
+                        set_label: "No instances are blocked",
+                        set_halign: gtk::Align::Start,
+                        add_css_class: "dim-label",
+                        #[watch]
+                        set_visible: model.instances.is_empty(),
+                    },
+                    #[local_ref]
+                    instances_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let persons = FactoryVecDeque::new(gtk::Box::default(), sender.input_sender());
+        let communities = FactoryVecDeque::new(gtk::Box::default(), sender.input_sender());
+        let instances = FactoryVecDeque::new(gtk::Box::default(), sender.input_sender());
+        let model = Self {
+            loading: false,
+            persons,
+            communities,
+            instances,
+        };
+        let persons_box = model.persons.widget();
+        let communities_box = model.communities.widget();
+        let instances_box = model.instances.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            BlocksPageInput::Update => {
+                self.loading = true;
+                std::thread::spawn(move || match api::site::fetch_site() {
+                    Ok(site) => match site.my_user {
+                        Some(my_user) => {
+                            sender.input(BlocksPageInput::DoneUpdate(Box::new(my_user)))
+                        }
+                        None => sender.output_sender().emit(crate::AppMsg::ShowMessage(
+                            "You need to be logged in to see your blocks".to_string(),
+                        )),
+                    },
+                    Err(err) => sender
+                        .output_sender()
+                        .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                });
+            }
+            BlocksPageInput::DoneUpdate(my_user) => {
+                self.loading = false;
+                let mut persons = self.persons.guard();
+                persons.clear();
+                for block in my_user.person_blocks {
+                    persons.push_back(Block {
+                        target: BlockTarget::Person(block.target.id),
+                        name: block.target.display_name.unwrap_or(block.target.name),
+                        address: block.target.actor_id.to_string(),
+                        icon: block.target.avatar,
+                    });
+                }
+                let mut communities = self.communities.guard();
+                communities.clear();
+                for block in my_user.community_blocks {
+                    communities.push_back(Block {
+                        target: BlockTarget::Community(block.community.id),
+                        name: block.community.title,
+                        address: block.community.actor_id.to_string(),
+                        icon: block.community.icon,
+                    });
+                }
+                let mut instances = self.instances.guard();
+                instances.clear();
+                for block in my_user.instance_blocks {
+                    instances.push_back(Block {
+                        target: BlockTarget::Instance(block.instance.id),
+                        name: block
+                            .site
+                            .as_ref()
+                            .map(|site| site.name.clone())
+                            .unwrap_or(block.instance.domain.clone()),
+                        address: block.instance.domain,
+                        icon: block.site.and_then(|site| site.icon),
+                    });
+                }
+            }
+            BlocksPageInput::Open(target) => {
+                let message = match target {
+                    BlockTarget::Person(person_id) => crate::AppMsg::OpenPerson(person_id),
+                    BlockTarget::Community(community_id) => {
+                        crate::AppMsg::OpenCommunity(community_id)
+                    }
+                    BlockTarget::Instance(_) => return,
+                };
+                sender.output_sender().emit(message);
+            }
+            BlocksPageInput::Unblock(target) => {
+                std::thread::spawn(move || {
+                    let result = match target {
+                        BlockTarget::Person(person_id) => {
+                            api::user::block_user(person_id, false).map(|_| ())
+                        }
+                        BlockTarget::Community(community_id) => {
+                            api::community::block_community(community_id, false).map(|_| ())
+                        }
+                        BlockTarget::Instance(instance_id) => {
+                            api::instances::block_instance(instance_id, false).map(|_| ())
+                        }
+                    };
+                    match result {
+                        Ok(_) => sender.input(BlocksPageInput::Unblocked(target)),
+                        Err(err) => sender
+                            .output_sender()
+                            .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                    }
+                });
+            }
+            BlocksPageInput::Unblocked(target) => {
+                let rows = match target {
+                    BlockTarget::Person(_) => &mut self.persons,
+                    BlockTarget::Community(_) => &mut self.communities,
+                    BlockTarget::Instance(_) => &mut self.instances,
+                };
+                let index = rows.iter().position(|row| row.target() == target);
+                if let Some(index) = index {
+                    rows.guard().remove(index);
+                }
+            }
+        }
+    }
+}
@@ -11,17 +11,22 @@ use crate::dialogs::editor::EditorOutput;
 use crate::dialogs::editor::EditorType;
 use crate::dialogs::report_dialog::ReportDialog;
 use crate::dialogs::report_dialog::ReportDialogInput;
+use crate::drafts;
+use crate::drafts::DraftTarget;
 use crate::settings;
 use crate::util;
 use crate::util::get_web_image_url;
-use crate::util::markdown_to_pango_markup;
 
+use super::markdown_view::MarkdownView;
+use super::markdown_view::MarkdownViewInput;
+use super::voting_row::VotingRowInput;
 use super::voting_row::VotingRowModel;
 use super::voting_row::VotingStats;
 
 pub struct CommentRow {
     pub comment: CommentView,
     avatar: Controller<WebImage>,
+    content: Controller<MarkdownView>,
     voting_row: Controller<VotingRowModel>,
     comment_editor_dialog: Controller<EditorDialog>,
     report_comment_dialog: Controller<ReportDialog>,
@@ -39,6 +44,24 @@ pub enum CommentRowMsg {
     ShowReportDialog,
 }
 
+impl CommentRow {
+    fn draft_target(&self, is_new: bool) -> DraftTarget {
+        let post_id = self.comment.comment.post_id;
+        let comment_id = self.comment.comment.id;
+        if is_new {
+            DraftTarget::Comment {
+                post_id,
+                parent_id: Some(comment_id),
+            }
+        } else {
+            DraftTarget::EditComment {
+                post_id,
+                comment_id,
+            }
+        }
+    }
+}
+
 #[relm4::factory(pub)]
 impl FactoryComponent for CommentRow {
     type Init = CommentView;
@@ -80,13 +103,8 @@ impl FactoryComponent for CommentRow {
                 }
             },
 
-            gtk::Label {
-                #[watch]
-               set_markup: &markdown_to_pango_markup(self.comment.comment.content.clone()),
-               set_halign: gtk::Align::Start,
-               set_wrap: true,
-               set_use_markup: true,
-            },
+            #[local_ref]
+            content -> gtk::Box {},
 
             gtk::Box {
                 set_orientation: gtk::Orientation::Horizontal,
@@ -140,6 +158,9 @@ impl FactoryComponent for CommentRow {
         let avatar = WebImage::builder()
             .launch(get_web_image_url(value.creator.avatar.clone()))
             .detach();
+        let content = MarkdownView::builder()
+            .launch(value.comment.content.clone())
+            .forward(sender.output_sender(), |msg| msg);
         let voting_row = VotingRowModel::builder()
             .launch(VotingStats::from_comment(
                 value.counts.clone(),
@@ -154,12 +175,13 @@ impl FactoryComponent for CommentRow {
             },
         );
         let report_comment_dialog = ReportDialog::builder()
-            .launch((None, Some(value.comment.id)))
+            .launch((None, Some(value.comment.id), None))
             .detach();
 
         Self {
             comment: value,
             avatar,
+            content,
             voting_row,
             comment_editor_dialog,
             report_comment_dialog,
@@ -175,6 +197,7 @@ impl FactoryComponent for CommentRow {
     ) -> Self::Widgets {
         let community_image = self.avatar.widget();
         let voting_row = self.voting_row.widget();
+        let content = self.content.widget();
         let widgets = view_output!();
         widgets
     }
@@ -196,24 +219,36 @@ impl FactoryComponent for CommentRow {
                     EditorData::default()
                 } else {
                     EditorData {
-                        name: String::from(""),
                         body: self.comment.comment.content.clone(),
-                        url: None,
+                        ..Default::default()
                     }
                 };
+                let target = self.draft_target(is_new);
                 let sender = self.comment_editor_dialog.sender();
-                sender.emit(DialogMsg::UpdateData(data));
+                sender.emit(DialogMsg::UpdateData(Box::new(data)));
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(target)));
                 sender.emit(DialogMsg::UpdateType(EditorType::Comment, is_new));
                 sender.emit(DialogMsg::Show);
             }
             CommentRowMsg::UpdateComment(comment) => {
+                self.voting_row
+                    .emit(VotingRowInput::UpdateStats(VotingStats::from_comment(
+                        comment.counts.clone(),
+                        comment.my_vote,
+                    )));
+                self.content
+                    .emit(MarkdownViewInput::Update(comment.comment.content.clone()));
                 self.comment = comment;
             }
             CommentRowMsg::EditCommentRequest(data) => {
                 let id = self.comment.comment.id;
+                let target = self.draft_target(false);
                 std::thread::spawn(move || {
                     let message = match api::comment::edit_comment(data.body, id) {
-                        Ok(comment) => Some(CommentRowMsg::UpdateComment(comment.comment_view)),
+                        Ok(comment) => {
+                            drafts::remove_draft(&target);
+                            Some(CommentRowMsg::UpdateComment(comment.comment_view))
+                        }
                         Err(err) => {
                             println!("{}", err);
                             None
@@ -227,9 +262,11 @@ impl FactoryComponent for CommentRow {
             CommentRowMsg::CreateCommentRequest(data) => {
                 let post_id = self.comment.comment.post_id;
                 let parent_id = self.comment.comment.id;
+                let target = self.draft_target(true);
                 std::thread::spawn(move || {
                     match api::comment::create_comment(post_id, data.body, Some(parent_id)) {
                         Ok(_comment) => {
+                            drafts::remove_draft(&target);
                             // TODO sender.output_sender().emit(PostPageInput::CreatedComment(comment.comment_view));
                         }
                         Err(err) => {
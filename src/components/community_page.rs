@@ -1,5 +1,6 @@
 use crate::{
     dialogs::editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType},
+    drafts::{self, DraftTarget},
     util::markdown_to_pango_markup,
 };
 use gtk::prelude::*;
@@ -11,7 +12,7 @@ use lemmy_api_common::{
 use relm4::{factory::FactoryVecDeque, prelude::*};
 use relm4_components::web_image::WebImage;
 
-use crate::{api, settings, util::get_web_image_msg};
+use crate::{api, gsettings, settings, util::get_web_image_msg};
 
 use super::{
     post_row::PostRow,
@@ -42,6 +43,7 @@ pub enum CommunityInput {
     UpdateOrder(SortType),
     ToggleBlocked,
     UpdateBlocked(bool),
+    BlockInstance,
     None,
 }
 
@@ -55,7 +57,7 @@ impl SimpleComponent for CommunityPage {
         gtk::ScrolledWindow {
             set_vexpand: false,
             connect_edge_reached[sender] => move |_, pos| {
-                if pos == gtk::PositionType::Bottom && settings::get_prefs().infinite_scroll {
+                if pos == gtk::PositionType::Bottom && gsettings::infinite_scroll() {
                     sender.input(CommunityInput::FetchPosts);
                 }
             },
@@ -126,6 +128,12 @@ impl SimpleComponent for CommunityPage {
                         set_visible: model.info.blocked,
                         connect_clicked => CommunityInput::ToggleBlocked,
                     },
+                    gtk::Button {
+                        set_label: "Block instance",
+                        #[watch]
+                        set_visible: settings::get_current_account().jwt.is_some() && !model.info.community.local,
+                        connect_clicked => CommunityInput::BlockInstance,
+                    },
                     gtk::Button {
                         set_label: "Create post",
                         connect_clicked => CommunityInput::OpenCreatePostDialog,
@@ -163,12 +171,11 @@ impl SimpleComponent for CommunityPage {
     ) -> relm4::ComponentParts<Self> {
         let avatar = WebImage::builder().launch("".to_string()).detach();
         let posts = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
-        let sort_dropdown =
-            SortDropdown::builder()
-                .launch(())
-                .forward(sender.input_sender(), |msg| match msg {
-                    SortDropdownOutput::New(sort_order) => CommunityInput::UpdateOrder(sort_order),
-                });
+        let sort_dropdown = SortDropdown::builder()
+            .launch(gsettings::default_sort())
+            .forward(sender.input_sender(), |msg| match msg {
+                SortDropdownOutput::New(sort_order) => CommunityInput::UpdateOrder(sort_order),
+            });
 
         let dialog = EditorDialog::builder()
             .transient_for(root)
@@ -184,7 +191,7 @@ impl SimpleComponent for CommunityPage {
             sort_dropdown,
             posts,
             create_post_dialog: dialog,
-            current_sort_type: SortType::Hot,
+            current_sort_type: gsettings::default_sort(),
             current_posts_page: 0,
         };
         let avatar = model.avatar.widget();
@@ -227,17 +234,31 @@ impl SimpleComponent for CommunityPage {
                 }
             }
             CommunityInput::OpenCreatePostDialog => {
+                let target = DraftTarget::NewPost(self.info.community.id);
+                let community = &self.info.community;
+                let name = format!(
+                    "{}@{}",
+                    community.name,
+                    community.actor_id.host_str().unwrap_or_default()
+                );
                 let sender = self.create_post_dialog.sender();
+                sender.emit(DialogMsg::UpdateCommunity(Some((community.id, name))));
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(target)));
                 sender.emit(DialogMsg::Show);
             }
             CommunityInput::CreatedPost(post) => {
                 self.posts.guard().push_front(post);
             }
             CommunityInput::CreatePostRequest(post) => {
-                let id = self.info.community.id.0;
+                let id = self.info.community.id;
                 std::thread::spawn(move || {
-                    let message = match api::post::create_post(post.name, post.body, post.url, id) {
-                        Ok(post) => Some(CommunityInput::CreatedPost(post.post_view)),
+                    let message = match api::post::create_post(post.post_details(), id) {
+                        Ok(post) => {
+                            drafts::remove_draft(&DraftTarget::NewPost(
+                                post.post_view.community.id,
+                            ));
+                            Some(CommunityInput::CreatedPost(post.post_view))
+                        }
                         Err(err) => {
                             println!("{}", err);
                             None
@@ -288,6 +309,13 @@ impl SimpleComponent for CommunityPage {
                 });
             }
             CommunityInput::UpdateBlocked(blocked) => self.info.blocked = blocked,
+            CommunityInput::BlockInstance => {
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::BlockInstance(Box::new(
+                        self.info.community.clone(),
+                    )));
+            }
             CommunityInput::None => {}
         }
     }
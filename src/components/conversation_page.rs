@@ -0,0 +1,350 @@
+use gtk::prelude::*;
+use lemmy_api_common::{
+    lemmy_db_schema::{newtypes::PersonId, source::person::Person},
+    lemmy_db_views::structs::PrivateMessageView,
+};
+use relm4::{factory::FactoryVecDeque, prelude::*};
+use relm4_components::web_image::WebImage;
+use std::{cell::Cell, rc::Rc};
+
+use crate::{api, settings, util::get_web_image_msg};
+
+use super::private_message_row::PrivateMessageRow;
+
+// the api can't filter by conversation, so several pages of messages are searched at once
+const MIN_MESSAGES: usize = 20;
+const MAX_PAGES_PER_FETCH: i64 = 5;
+
+pub struct ConversationPage {
+    participant: Option<Person>,
+    avatar: Controller<WebImage>,
+    messages: FactoryVecDeque<PrivateMessageRow>,
+    reply_buffer: gtk::TextBuffer,
+    page: i64,
+    exhausted: bool,
+    loading: bool,
+    // a reply is being sent, it can't be sent again in the meantime
+    sending: bool,
+    error: Option<String>,
+    // whether to keep the newest messages in view when the content grows
+    stick_to_bottom: Rc<Cell<bool>>,
+}
+
+#[derive(Debug)]
+pub enum ConversationPageInput {
+    Open(Box<Person>),
+    FetchMessages,
+    UpdateMessages(PersonId, Vec<PrivateMessageView>, i64, bool),
+    SendReply,
+    MessageSent(Box<PrivateMessageView>),
+    SendFailed(String),
+    OpenPerson,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for ConversationPage {
+    type Init = ();
+    type Input = ConversationPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+                set_margin_all: 10,
+
+                #[local_ref]
+                avatar -> gtk::Box {
+                    set_hexpand: false,
+                    #[watch]
+                    set_visible: model.participant.as_ref()
+                        .is_some_and(|participant| participant.avatar.is_some()),
+                },
+                gtk::Button {
+                    #[watch]
+                    set_label: &model.participant.as_ref()
+                        .map(|participant| participant.name.clone())
+                        .unwrap_or_default(),
+                    connect_clicked => ConversationPageInput::OpenPerson,
+                },
+            },
+
+            gtk::Separator {},
+
+            #[name(scrolled_window)]
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+                set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+
+                    gtk::Button {
+                        set_label: "Load older messages",
+                        set_halign: gtk::Align::Center,
+                        set_margin_top: 10,
+                        #[watch]
+                        set_visible: !model.exhausted,
+                        #[watch]
+                        set_sensitive: !model.loading,
+                        connect_clicked => ConversationPageInput::FetchMessages,
+                    },
+
+                    #[local_ref]
+                    messages -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                },
+            },
+
+            gtk::Separator {},
+
+            gtk::Label {
+                add_css_class: "error",
+                set_wrap: true,
+                set_margin_top: 10,
+                #[watch]
+                set_visible: model.error.is_some(),
+                #[watch]
+                set_label: model.error.as_deref().unwrap_or_default(),
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+                set_margin_all: 10,
+
+                gtk::ScrolledWindow {
+                    set_hexpand: true,
+                    set_min_content_height: 60,
+                    set_max_content_height: 200,
+                    set_propagate_natural_height: true,
+
+                    gtk::TextView {
+                        set_buffer: Some(&model.reply_buffer),
+                        set_wrap_mode: gtk::WrapMode::WordChar,
+                        set_top_margin: 5,
+                        set_bottom_margin: 5,
+                        set_left_margin: 5,
+                        set_right_margin: 5,
+                        add_css_class: "card",
+                        // ctrl+enter sends the message, enter alone starts a new line
+                        add_controller = gtk::EventControllerKey {
+                            connect_key_pressed[sender] => move |_, key, _, modifiers| {
+                                let send = modifiers.contains(gtk::gdk::ModifierType::CONTROL_MASK)
+                                    && matches!(key, gtk::gdk::Key::Return | gtk::gdk::Key::KP_Enter);
+                                if send {
+                                    sender.input(ConversationPageInput::SendReply);
+                                }
+                                gtk::Inhibit(send)
+                            }
+                        },
+                    },
+                },
+
+                gtk::Button {
+                    set_label: "Send",
+                    set_valign: gtk::Align::End,
+                    set_tooltip_text: Some("Send (Ctrl+Enter)"),
+                    add_css_class: "suggested-action",
+                    #[watch]
+                    set_sensitive: !model.sending,
+                    connect_clicked => ConversationPageInput::SendReply,
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let avatar = WebImage::builder().launch("".to_string()).detach();
+        let messages = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
+        let model = Self {
+            participant: None,
+            avatar,
+            messages,
+            reply_buffer: gtk::TextBuffer::default(),
+            page: 1,
+            exhausted: false,
+            loading: false,
+            sending: false,
+            error: None,
+            stick_to_bottom: Rc::new(Cell::new(true)),
+        };
+        let avatar = model.avatar.widget();
+        let messages = model.messages.widget();
+        let widgets = view_output!();
+
+        let stick_to_bottom = model.stick_to_bottom.clone();
+        widgets
+            .scrolled_window
+            .vadjustment()
+            .connect_upper_notify(move |adjustment| {
+                if stick_to_bottom.get() {
+                    adjustment.set_value(adjustment.upper() - adjustment.page_size());
+                }
+            });
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            ConversationPageInput::Open(participant) => {
+                self.avatar
+                    .emit(get_web_image_msg(participant.avatar.clone()));
+                self.participant = Some(*participant);
+                self.messages.guard().clear();
+                self.page = 1;
+                self.exhausted = false;
+                self.loading = false;
+                self.error = None;
+                self.stick_to_bottom.set(true);
+                sender.input(ConversationPageInput::FetchMessages);
+            }
+            ConversationPageInput::FetchMessages => {
+                let Some(participant) = &self.participant else {
+                    return;
+                };
+                if self.loading || self.exhausted {
+                    return;
+                }
+                self.loading = true;
+                // older messages are added above, so the view shouldn't jump to the end
+                self.stick_to_bottom.set(self.page == 1);
+                let participant_id = participant.id;
+                let mut page = self.page;
+                std::thread::spawn(move || {
+                    let mut messages = vec![];
+                    let mut exhausted = false;
+                    for _ in 0..MAX_PAGES_PER_FETCH {
+                        let response =
+                            match api::private_message::list_private_messages(false, page) {
+                                Ok(response) => response.private_messages,
+                                Err(err) => {
+                                    println!("{}", err);
+                                    break;
+                                }
+                            };
+                        if response.is_empty() {
+                            exhausted = true;
+                            break;
+                        }
+                        page += 1;
+                        messages.extend(response.into_iter().filter(|message| {
+                            message.creator.id == participant_id
+                                || message.recipient.id == participant_id
+                        }));
+                        if messages.len() >= MIN_MESSAGES {
+                            break;
+                        }
+                    }
+                    sender.input(ConversationPageInput::UpdateMessages(
+                        participant_id,
+                        messages,
+                        page,
+                        exhausted,
+                    ));
+                });
+            }
+            ConversationPageInput::UpdateMessages(
+                participant_id,
+                mut messages,
+                page,
+                exhausted,
+            ) => {
+                // another conversation might have been opened in the meantime
+                if self.participant.as_ref().map(|participant| participant.id)
+                    != Some(participant_id)
+                {
+                    return;
+                }
+                self.loading = false;
+                self.page = page;
+                self.exhausted = exhausted;
+
+                // the received messages count as read once the conversation has been opened
+                let my_id = settings::get_current_account().id;
+                let unread_ids: Vec<_> = messages
+                    .iter_mut()
+                    .filter(|message| {
+                        message.recipient.id.0 == my_id && !message.private_message.read
+                    })
+                    .map(|message| {
+                        message.private_message.read = true;
+                        message.private_message.id
+                    })
+                    .collect();
+                if !unread_ids.is_empty() {
+                    std::thread::spawn(move || {
+                        for message_id in unread_ids {
+                            if let Err(err) =
+                                api::private_message::mark_private_message_as_read(message_id, true)
+                            {
+                                println!("{}", err);
+                            }
+                        }
+                        sender.output_sender().emit(crate::AppMsg::FetchUnreadCount);
+                    });
+                }
+
+                // the messages are sorted from new to old, but displayed chronologically
+                let mut guard = self.messages.guard();
+                for message in messages {
+                    guard.push_front(message);
+                }
+            }
+            ConversationPageInput::SendReply => {
+                let Some(participant) = &self.participant else {
+                    return;
+                };
+                if self.sending {
+                    return;
+                }
+                let (start, end) = self.reply_buffer.bounds();
+                let content = self.reply_buffer.text(&start, &end, true).to_string();
+                if content.trim().is_empty() {
+                    return;
+                }
+                let recipient_id = participant.id;
+                self.sending = true;
+                self.error = None;
+                std::thread::spawn(move || {
+                    match api::private_message::create_private_message(content, recipient_id) {
+                        Ok(response) => sender.input(ConversationPageInput::MessageSent(Box::new(
+                            response.private_message_view,
+                        ))),
+                        Err(err) => sender.input(ConversationPageInput::SendFailed(format!(
+                            "Could not send the message: {}",
+                            err
+                        ))),
+                    }
+                });
+            }
+            ConversationPageInput::MessageSent(message) => {
+                self.sending = false;
+                self.reply_buffer.set_text("");
+                self.stick_to_bottom.set(true);
+                self.messages.guard().push_back(*message);
+            }
+            ConversationPageInput::SendFailed(error) => {
+                // the reply stays in the text field, so that it can be sent again
+                self.sending = false;
+                self.error = Some(error);
+            }
+            ConversationPageInput::OpenPerson => {
+                if let Some(participant) = &self.participant {
+                    sender
+                        .output_sender()
+                        .emit(crate::AppMsg::OpenPerson(participant.id));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use gtk::prelude::*;
+use lemmy_api_common::{
+    lemmy_db_schema::source::person::Person, lemmy_db_views::structs::PrivateMessageView,
+};
+use relm4::prelude::*;
+use relm4_components::web_image::WebImage;
+
+use crate::util::{self, get_web_image_url};
+
+// the longest part of the last message that's shown as preview
+const MAX_PREVIEW_LENGTH: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    // the other person taking part in the conversation
+    pub participant: Person,
+    pub last_message: PrivateMessageView,
+    pub unread: usize,
+}
+
+// groups the messages by the person they were exchanged with, the most recent conversations first
+pub fn group_conversations(messages: Vec<PrivateMessageView>, my_id: i32) -> Vec<Conversation> {
+    let mut conversations: Vec<Conversation> = vec![];
+    for message in messages {
+        let received = message.recipient.id.0 == my_id;
+        let participant = if received {
+            message.creator.clone()
+        } else {
+            message.recipient.clone()
+        };
+        let unread = (received && !message.private_message.read) as usize;
+        match conversations
+            .iter_mut()
+            .find(|conversation| conversation.participant.id == participant.id)
+        {
+            Some(conversation) => {
+                conversation.unread += unread;
+                if message.private_message.published
+                    > conversation.last_message.private_message.published
+                {
+                    conversation.last_message = message;
+                }
+            }
+            None => conversations.push(Conversation {
+                participant,
+                last_message: message,
+                unread,
+            }),
+        }
+    }
+    conversations.sort_by_key(|conversation| {
+        std::cmp::Reverse(conversation.last_message.private_message.published)
+    });
+    conversations
+}
+
+pub struct ConversationRow {
+    conversation: Conversation,
+    avatar: Controller<WebImage>,
+}
+
+#[derive(Debug)]
+pub enum ConversationRowInput {
+    OpenConversation,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for ConversationRow {
+    type Init = Conversation;
+    type Input = ConversationRowInput;
+    type Output = crate::AppMsg;
+    type CommandOutput = ();
+    type ParentInput = crate::AppMsg;
+    type ParentWidget = gtk::Box;
+
+    view! {
+        root = gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+                set_margin_all: 10,
+                add_controller = gtk::GestureClick {
+                    connect_pressed[sender] => move |_, _, _, _| {
+                        sender.input(ConversationRowInput::OpenConversation);
+                    }
+                },
+
+                #[local_ref]
+                avatar -> gtk::Box {
+                    set_hexpand: false,
+                    set_visible: self.conversation.participant.avatar.is_some(),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_hexpand: true,
+                    set_spacing: 5,
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_label: self.conversation.participant.display_name.as_ref()
+                                .unwrap_or(&self.conversation.participant.name),
+                            add_css_class: "font-bold",
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::Label {
+                            add_css_class: "inbox-badge",
+                            set_label: &self.conversation.unread.to_string(),
+                            set_visible: self.conversation.unread > 0,
+                        },
+                        gtk::Box {
+                            set_hexpand: true,
+                        },
+                        gtk::Label {
+                            add_css_class: "dim-label",
+                            set_label: &util::format_elapsed_time(
+                                self.conversation.last_message.private_message.published
+                            ),
+                        },
+                    },
+
+                    gtk::Label {
+                        set_label: &preview(&self.conversation.last_message),
+                        set_halign: gtk::Align::Start,
+                        set_ellipsize: gtk::pango::EllipsizeMode::End,
+                        add_css_class: "dim-label",
+                    },
+                },
+            },
+
+            gtk::Separator {}
+        }
+    }
+
+    fn forward_to_parent(output: Self::Output) -> Option<Self::ParentInput> {
+        Some(output)
+    }
+
+    fn init_model(init: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+        let avatar = WebImage::builder()
+            .launch(get_web_image_url(init.participant.avatar.clone()))
+            .detach();
+        Self {
+            conversation: init,
+            avatar,
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: &Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let avatar = self.avatar.widget();
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        match message {
+            ConversationRowInput::OpenConversation => {
+                sender.output(crate::AppMsg::OpenConversation(Box::new(
+                    self.conversation.participant.clone(),
+                )));
+            }
+        }
+    }
+}
+
+fn preview(message: &PrivateMessageView) -> String {
+    let content = message.private_message.content.replace('\n', " ");
+    if content.chars().count() <= MAX_PREVIEW_LENGTH {
+        return content;
+    }
+    content.chars().take(MAX_PREVIEW_LENGTH).collect::<String>() + "…"
+}
@@ -0,0 +1,95 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+
+use crate::{drafts::Draft, util};
+
+use super::drafts_page::DraftsPageInput;
+
+pub struct DraftRow {
+    draft: Draft,
+}
+
+#[derive(Debug)]
+pub enum DraftRowInput {
+    Open,
+    Delete,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for DraftRow {
+    type Init = Draft;
+    type Input = DraftRowInput;
+    type Output = DraftsPageInput;
+    type ParentInput = DraftsPageInput;
+    type ParentWidget = gtk::Box;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            set_spacing: 5,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+
+                gtk::Label {
+                    set_label: &self.draft.target.description(),
+                    add_css_class: "font-bold",
+                },
+                gtk::Label {
+                    set_label: &util::format_elapsed_time(self.draft.updated),
+                },
+                gtk::Box {
+                    set_hexpand: true,
+                },
+                gtk::Button {
+                    set_label: "Open",
+                    connect_clicked => DraftRowInput::Open,
+                },
+                gtk::Button {
+                    set_icon_name: "edit-delete",
+                    connect_clicked => DraftRowInput::Delete,
+                },
+            },
+
+            gtk::Label {
+                set_label: &self.draft.name,
+                set_visible: !self.draft.name.is_empty(),
+                set_halign: gtk::Align::Start,
+                set_wrap: true,
+            },
+
+            gtk::Label {
+                set_label: &self.draft.body,
+                set_halign: gtk::Align::Start,
+                set_wrap: true,
+                set_lines: 3,
+                set_ellipsize: gtk::pango::EllipsizeMode::End,
+            },
+
+            gtk::Separator {
+                set_margin_all: 10,
+            }
+        }
+    }
+
+    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
+        Self { draft: init }
+    }
+
+    fn forward_to_parent(output: Self::Output) -> Option<Self::ParentInput> {
+        Some(output)
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        match message {
+            DraftRowInput::Open => sender
+                .output_sender()
+                .emit(DraftsPageInput::Open(self.draft.target)),
+            DraftRowInput::Delete => sender
+                .output_sender()
+                .emit(DraftsPageInput::Remove(self.draft.target)),
+        }
+    }
+}
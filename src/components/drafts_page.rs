@@ -0,0 +1,87 @@
+use gtk::prelude::*;
+use relm4::{factory::FactoryVecDeque, prelude::*};
+
+use crate::drafts::{self, DraftTarget};
+
+use super::draft_row::DraftRow;
+
+pub struct DraftsPage {
+    drafts: FactoryVecDeque<DraftRow>,
+}
+
+#[derive(Debug)]
+pub enum DraftsPageInput {
+    Update,
+    Open(DraftTarget),
+    Remove(DraftTarget),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for DraftsPage {
+    type Init = ();
+    type Input = DraftsPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Label {
+                set_label: "Drafts",
+                add_css_class: "font-very-bold",
+                set_margin_top: 10,
+            },
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_vexpand: true,
+                    set_margin_all: 10,
+
+                    gtk::Label {
+                        set_label: "There are no unsent drafts",
+                        #[watch]
+                        set_visible: model.drafts.is_empty(),
+                    },
+
+                    #[local_ref]
+                    drafts_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let drafts = FactoryVecDeque::new(gtk::Box::builder().build(), sender.input_sender());
+        let model = Self { drafts };
+        let drafts_box = model.drafts.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            DraftsPageInput::Update => {
+                self.drafts.guard().clear();
+                for draft in drafts::get_drafts() {
+                    self.drafts.guard().push_back(draft);
+                }
+            }
+            DraftsPageInput::Remove(target) => {
+                drafts::remove_draft(&target);
+                sender.input(DraftsPageInput::Update);
+            }
+            DraftsPageInput::Open(target) => {
+                sender.output_sender().emit(target.app_message());
+            }
+        }
+    }
+}
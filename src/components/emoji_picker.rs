@@ -0,0 +1,254 @@
+use gtk::prelude::*;
+use lemmy_api_common::lemmy_db_views::structs::CustomEmojiView;
+use relm4::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::{custom_emojis, util};
+
+const EMOJI_SIZE: i32 = 24;
+// limits the amount of buttons that are created when searching
+const MAX_SEARCH_RESULTS: usize = 200;
+
+pub struct EmojiPicker {
+    popover: gtk::Popover,
+    content: gtk::Box,
+    custom_emojis: Vec<CustomEmojiView>,
+    query: String,
+    loaded: bool,
+}
+
+#[derive(Debug)]
+pub enum EmojiPickerInput {
+    Load,
+    UpdateCustomEmojis(Vec<CustomEmojiView>),
+    Search(String),
+    Pick(String),
+}
+
+#[derive(Debug)]
+pub enum EmojiPickerOutput {
+    Picked(String),
+}
+
+fn group_name(group: emojis::Group) -> &'static str {
+    match group {
+        emojis::Group::SmileysAndEmotion => "Smileys & Emotion",
+        emojis::Group::PeopleAndBody => "People & Body",
+        emojis::Group::AnimalsAndNature => "Animals & Nature",
+        emojis::Group::FoodAndDrink => "Food & Drink",
+        emojis::Group::TravelAndPlaces => "Travel & Places",
+        emojis::Group::Activities => "Activities",
+        emojis::Group::Objects => "Objects",
+        emojis::Group::Symbols => "Symbols",
+        emojis::Group::Flags => "Flags",
+    }
+}
+
+fn matches_custom_emoji(emoji: &CustomEmojiView, query: &str) -> bool {
+    let custom_emoji = &emoji.custom_emoji;
+    custom_emoji.shortcode.to_lowercase().contains(query)
+        || custom_emoji.alt_text.to_lowercase().contains(query)
+        || custom_emoji.category.to_lowercase().contains(query)
+        || emoji
+            .keywords
+            .iter()
+            .any(|keyword| keyword.keyword.to_lowercase().contains(query))
+}
+
+fn matches_emoji(emoji: &emojis::Emoji, query: &str) -> bool {
+    emoji.name().contains(query) || emoji.shortcodes().any(|code| code.contains(query))
+}
+
+impl EmojiPicker {
+    fn add_section(&self, title: &str, buttons: Vec<gtk::Button>) {
+        if buttons.is_empty() {
+            return;
+        }
+        let label = gtk::Label::builder()
+            .label(title)
+            .halign(gtk::Align::Start)
+            .css_classes(vec!["font-bold".to_string()])
+            .build();
+        let flow_box = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .homogeneous(true)
+            .min_children_per_line(6)
+            .max_children_per_line(10)
+            .build();
+        for button in buttons {
+            flow_box.insert(&button, -1);
+        }
+        self.content.append(&label);
+        self.content.append(&flow_box);
+    }
+
+    fn rebuild(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.content.first_child() {
+            self.content.remove(&child);
+        }
+        let query = self.query.trim().to_lowercase();
+        let mut results = 0;
+
+        // the custom emojis of the instance come first, grouped by their category
+        let mut categories: BTreeMap<&str, Vec<&CustomEmojiView>> = BTreeMap::new();
+        for emoji in &self.custom_emojis {
+            if matches_custom_emoji(emoji, &query) {
+                categories
+                    .entry(emoji.custom_emoji.category.as_str())
+                    .or_default()
+                    .push(emoji);
+            }
+        }
+        for (category, emojis) in categories {
+            let buttons = emojis
+                .into_iter()
+                .map(|emoji| {
+                    let picture = gtk::Picture::new();
+                    util::load_picture(
+                        &picture,
+                        emoji.custom_emoji.image_url.to_string(),
+                        EMOJI_SIZE,
+                    );
+                    let shortcode = format!(":{}:", emoji.custom_emoji.shortcode);
+                    let button = gtk::Button::builder()
+                        .child(&picture)
+                        .tooltip_text(&shortcode)
+                        .css_classes(vec!["flat".to_string()])
+                        .build();
+                    let sender = sender.clone();
+                    button.connect_clicked(move |_| {
+                        sender.input(EmojiPickerInput::Pick(shortcode.clone()));
+                    });
+                    button
+                })
+                .collect::<Vec<_>>();
+            results += buttons.len();
+            let title = if category.is_empty() {
+                "Custom"
+            } else {
+                category
+            };
+            self.add_section(title, buttons);
+        }
+
+        for group in emojis::Group::iter() {
+            let limit = if query.is_empty() {
+                usize::MAX
+            } else {
+                MAX_SEARCH_RESULTS.saturating_sub(results)
+            };
+            let buttons = group
+                .emojis()
+                .filter(|emoji| matches_emoji(emoji, &query))
+                .take(limit)
+                .map(|emoji| {
+                    let button = gtk::Button::builder()
+                        .label(emoji.as_str())
+                        .tooltip_text(emoji.name())
+                        .css_classes(vec!["flat".to_string()])
+                        .build();
+                    let sender = sender.clone();
+                    button.connect_clicked(move |_| {
+                        sender.input(EmojiPickerInput::Pick(emoji.as_str().to_string()));
+                    });
+                    button
+                })
+                .collect::<Vec<_>>();
+            results += buttons.len();
+            self.add_section(group_name(group), buttons);
+        }
+
+        if results == 0 {
+            self.content
+                .append(&gtk::Label::new(Some("No emojis found")));
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for EmojiPicker {
+    type Init = ();
+    type Input = EmojiPickerInput;
+    type Output = EmojiPickerOutput;
+
+    view! {
+        gtk::Popover {
+            connect_show => EmojiPickerInput::Load,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 5,
+
+                gtk::SearchEntry {
+                    set_placeholder_text: Some("Search emojis"),
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(EmojiPickerInput::Search(entry.text().to_string()));
+                    },
+                },
+                gtk::ScrolledWindow {
+                    set_size_request: (380, 320),
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                    #[local_ref]
+                    content -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 5,
+                        set_margin_all: 5,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            popover: root.clone(),
+            content: gtk::Box::default(),
+            custom_emojis: vec![],
+            query: String::new(),
+            loaded: false,
+        };
+        let content = &model.content;
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            EmojiPickerInput::Load => {
+                // the emojis are only added once the picker is opened for the first time
+                if self.loaded {
+                    return;
+                }
+                self.loaded = true;
+                match custom_emojis::cached_custom_emojis() {
+                    Some(emojis) => sender.input(EmojiPickerInput::UpdateCustomEmojis(emojis)),
+                    None => {
+                        std::thread::spawn(move || {
+                            let emojis = custom_emojis::get_custom_emojis();
+                            sender.input(EmojiPickerInput::UpdateCustomEmojis(emojis));
+                        });
+                    }
+                }
+            }
+            EmojiPickerInput::UpdateCustomEmojis(emojis) => {
+                self.custom_emojis = emojis;
+                self.rebuild(&sender);
+            }
+            EmojiPickerInput::Search(query) => {
+                self.query = query;
+                self.rebuild(&sender);
+            }
+            EmojiPickerInput::Pick(emoji) => {
+                self.popover.popdown();
+                sender
+                    .output_sender()
+                    .emit(EmojiPickerOutput::Picked(emoji));
+            }
+        }
+    }
+}
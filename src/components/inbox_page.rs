@@ -1,12 +1,16 @@
 use gtk::prelude::*;
 use lemmy_api_common::{
-    lemmy_db_views::structs::PrivateMessageView, lemmy_db_views_actor::structs::CommentReplyView,
+    lemmy_db_schema::newtypes::PersonMentionId, lemmy_db_views::structs::PrivateMessageView,
+    lemmy_db_views_actor::structs::CommentReplyView, person::GetUnreadCountResponse,
 };
 use relm4::{factory::FactoryVecDeque, prelude::*};
 
-use crate::api;
+use crate::{api, settings};
 
-use super::{mention_row::MentionRow, private_message_row::PrivateMessageRow};
+use super::{
+    conversation_row::{group_conversations, ConversationRow},
+    mention_row::MentionRow,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum InboxType {
@@ -17,10 +21,19 @@ pub enum InboxType {
 
 pub struct InboxPage {
     mentions: FactoryVecDeque<MentionRow>,
-    private_messages: FactoryVecDeque<PrivateMessageRow>,
+    conversations: FactoryVecDeque<ConversationRow>,
     page: i64,
     unread_only: bool,
     type_: InboxType,
+    unread_count: GetUnreadCountResponse,
+}
+
+fn tab_label(name: &str, unread: i64) -> String {
+    if unread > 0 {
+        format!("{} ({})", name, unread)
+    } else {
+        name.to_string()
+    }
 }
 
 #[derive(Debug)]
@@ -28,9 +41,11 @@ pub enum InboxInput {
     UpdateType(InboxType),
     ToggleUnreadState,
     FetchInbox,
-    UpdateInbox(Vec<CommentReplyView>),
+    // mentions are converted to replies and additionally carry their own id
+    UpdateInbox(Vec<(CommentReplyView, Option<PersonMentionId>)>),
     UpdatePrivateMessages(Vec<PrivateMessageView>),
     MarkAllAsRead,
+    UpdateUnreadCount(GetUnreadCountResponse),
 }
 
 #[relm4::component(pub)]
@@ -47,19 +62,22 @@ impl SimpleComponent for InboxPage {
                 set_margin_all: 15,
                 set_spacing: 10,
                 gtk::ToggleButton {
-                    set_label: "Replies",
+                    #[watch]
+                    set_label: &tab_label("Replies", model.unread_count.replies),
                     connect_clicked => InboxInput::UpdateType(InboxType::Replies),
                     #[watch]
                     set_active: model.type_ == InboxType::Replies,
                 },
                 gtk::ToggleButton {
-                    set_label: "Mentions",
+                    #[watch]
+                    set_label: &tab_label("Mentions", model.unread_count.mentions),
                     connect_clicked => InboxInput::UpdateType(InboxType::Mentions),
                     #[watch]
                     set_active: model.type_ == InboxType::Mentions,
                 },
                 gtk::ToggleButton {
-                    set_label: "Private messages",
+                    #[watch]
+                    set_label: &tab_label("Private messages", model.unread_count.private_messages),
                     connect_clicked => InboxInput::UpdateType(InboxType::PrivateMessages),
                     #[watch]
                     set_active: model.type_ == InboxType::PrivateMessages,
@@ -82,7 +100,7 @@ impl SimpleComponent for InboxPage {
                     InboxType::PrivateMessages => {
                         gtk::Box {
                             #[local_ref]
-                            private_messages -> gtk::Box {
+                            conversations -> gtk::Box {
                                 set_vexpand: true,
                                 set_orientation: gtk::Orientation::Vertical,
                             }
@@ -108,16 +126,21 @@ impl SimpleComponent for InboxPage {
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let mentions = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
-        let private_messages = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
+        let conversations = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
         let model = Self {
             mentions,
-            private_messages,
+            conversations,
             page: 1,
             unread_only: false,
             type_: InboxType::Replies,
+            unread_count: GetUnreadCountResponse {
+                replies: 0,
+                mentions: 0,
+                private_messages: 0,
+            },
         };
         let mentions = model.mentions.widget();
-        let private_messages = model.private_messages.widget();
+        let conversations = model.conversations.widget();
         let widgets = view_output!();
         ComponentParts { model, widgets }
     }
@@ -133,8 +156,22 @@ impl SimpleComponent for InboxPage {
                         InboxType::Mentions => {
                             if let Ok(response) = api::user::get_mentions(page, unread_only) {
                                 // It's just a different object, but its contents are exactly the same
-                                let serialised = serde_json::to_string(&response.mentions).unwrap();
-                                let mentions = serde_json::from_str(&serialised).ok();
+                                // apart from the name of the mention itself
+                                let mentions = response
+                                    .mentions
+                                    .iter()
+                                    .map(|mention| {
+                                        let mut value = serde_json::to_value(mention)?;
+                                        if let Some(object) = value.as_object_mut() {
+                                            if let Some(mention) = object.remove("person_mention") {
+                                                object.insert("comment_reply".to_string(), mention);
+                                            }
+                                        }
+                                        serde_json::from_value(value)
+                                            .map(|reply| (reply, Some(mention.person_mention.id)))
+                                    })
+                                    .collect::<Result<Vec<_>, _>>()
+                                    .ok();
                                 mentions.map(InboxInput::UpdateInbox)
                             } else {
                                 None
@@ -142,7 +179,12 @@ impl SimpleComponent for InboxPage {
                         }
                         InboxType::Replies => {
                             if let Ok(response) = api::user::get_replies(page, unread_only) {
-                                Some(InboxInput::UpdateInbox(response.replies))
+                                let replies = response
+                                    .replies
+                                    .into_iter()
+                                    .map(|reply| (reply, None))
+                                    .collect();
+                                Some(InboxInput::UpdateInbox(replies))
                             } else {
                                 None
                             }
@@ -177,19 +219,24 @@ impl SimpleComponent for InboxPage {
                 }
             }
             InboxInput::UpdatePrivateMessages(messages) => {
-                self.private_messages.guard().clear();
-                for message in messages {
-                    self.private_messages.guard().push_back(message);
+                let my_id = settings::get_current_account().id;
+                let mut guard = self.conversations.guard();
+                guard.clear();
+                for conversation in group_conversations(messages, my_id) {
+                    guard.push_back(conversation);
                 }
             }
             InboxInput::MarkAllAsRead => {
-                let show_unread_only = self.unread_only;
                 std::thread::spawn(move || {
-                    if api::user::mark_all_as_read().is_ok() && show_unread_only {
-                        sender.input(InboxInput::UpdateInbox(vec![]));
+                    if api::user::mark_all_as_read().is_ok() {
+                        sender.input(InboxInput::FetchInbox);
+                        sender.output_sender().emit(crate::AppMsg::FetchUnreadCount);
                     }
                 });
             }
+            InboxInput::UpdateUnreadCount(unread_count) => {
+                self.unread_count = unread_count;
+            }
         }
     }
 }
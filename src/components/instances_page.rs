@@ -153,6 +153,15 @@ impl SimpleComponent for InstancesPage {
                             .output_sender()
                             .emit(crate::AppMsg::UpdateState(crate::AppState::Loading));
                         let mut current_account = settings::get_current_account();
+                        // the token isn't of any use for the new instance anymore
+                        if current_account.jwt.is_some() {
+                            let account = current_account.clone();
+                            std::thread::spawn(move || {
+                                if let Err(err) = api::auth::logout(&account) {
+                                    println!("{}", err);
+                                }
+                            });
+                        }
                         let url = url.to_string();
                         // remove the "/" at the end of the url
                         current_account.instance_url = url[0..url.len() - 1].to_string();
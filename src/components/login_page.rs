@@ -1,16 +1,67 @@
 use gtk::prelude::*;
 use relm4::prelude::*;
 
+use lemmy_api_common::sensitive::Sensitive;
+
 use crate::{
-    api,
+    api::{self, auth::AuthError},
     settings::{self, get_current_account},
 };
 
-pub struct LoginPage {}
+// saves the token of a new login to the current account, blocks while fetching the user's name
+pub fn finish_login(token: Sensitive<String>) -> crate::AppMsg {
+    let mut account = settings::get_current_account();
+    account.jwt = Some(token);
+    account.session_expired = false;
+    settings::update_current_account(account.clone());
+
+    if let Ok(site) = api::site::fetch_site() {
+        // the instance didn't accept the token after all
+        let Some(my_user) = site.my_user else {
+            account.jwt = None;
+            settings::update_current_account(account);
+            return crate::AppMsg::ShowMessage(
+                "Could not load the account of the new login, please log in again".to_string(),
+            );
+        };
+        let user = my_user.local_user_view.person;
+        account.name = user.name;
+        account.id = user.id.0;
+        settings::update_current_account(account);
+    }
+    crate::AppMsg::LoggedIn
+}
+
+pub struct LoginPage {
+    username_buffer: gtk::EntryBuffer,
+    // the credentials are kept while asking for the two-factor code
+    totp_credentials: Option<(String, String)>,
+    reset_password: bool,
+    error: Option<String>,
+    info: Option<String>,
+}
+
+impl LoginPage {
+    fn reset(&mut self) {
+        self.totp_credentials = None;
+        self.reset_password = false;
+        self.error = None;
+        self.info = None;
+    }
+}
 
 #[derive(Debug)]
 pub enum LoginPageInput {
     Login(String, String, String),
+    RequireTotp(String, String),
+    LoginFailed(String),
+    ToggleResetPassword,
+    ResetPassword(String),
+    ResetRequested(Result<(), String>),
+    Register,
+    // asks to log in again after the token of the account has been rejected
+    SessionExpired(String),
+    Reset,
     Cancel,
 }
 
@@ -30,32 +81,95 @@ impl SimpleComponent for LoginPage {
             set_hexpand: true,
 
             gtk::Label {
-                set_text: "Login",
+                #[watch]
+                set_text: if model.reset_password { "Reset password" } else { "Login" },
                 add_css_class: "font-bold",
             },
             #[name(username)]
             gtk::Entry {
                 set_placeholder_text: Some("Username or E-Mail"),
+                set_buffer: &model.username_buffer,
+                #[watch]
+                set_visible: model.totp_credentials.is_none() && !model.reset_password,
             },
             #[name(password)]
             gtk::PasswordEntry {
                 set_placeholder_text: Some("Password"),
                 set_show_peek_icon: true,
+                #[watch]
+                set_visible: model.totp_credentials.is_none() && !model.reset_password,
+            },
+            gtk::Label {
+                set_text: "Enter the code of your two-factor authentication app",
+                set_wrap: true,
+                #[watch]
+                set_visible: model.totp_credentials.is_some(),
             },
             #[name(totp_token)]
             gtk::Entry {
-                set_placeholder_text: Some("Totp token (Optional)"),
+                set_placeholder_text: Some("Two-factor code"),
+                set_input_purpose: gtk::InputPurpose::Digits,
+                #[watch]
+                set_visible: model.totp_credentials.is_some(),
+            },
+            #[name(email)]
+            gtk::Entry {
+                set_placeholder_text: Some("E-Mail"),
+                set_input_purpose: gtk::InputPurpose::Email,
+                #[watch]
+                set_visible: model.reset_password,
+            },
+            gtk::Label {
+                add_css_class: "error",
+                set_wrap: true,
+                #[watch]
+                set_visible: model.error.is_some(),
+                #[watch]
+                set_text: model.error.as_deref().unwrap_or_default(),
+            },
+            gtk::Label {
+                set_wrap: true,
+                #[watch]
+                set_visible: model.info.is_some(),
+                #[watch]
+                set_text: model.info.as_deref().unwrap_or_default(),
             },
             gtk::Box {
                 set_orientation: gtk::Orientation::Horizontal,
                 set_halign: gtk::Align::End,
+                gtk::Button {
+                    #[watch]
+                    set_label: if model.reset_password { "Back to login" } else { "Forgot password?" },
+                    add_css_class: "flat",
+                    connect_clicked => LoginPageInput::ToggleResetPassword,
+                    set_margin_end: 10,
+                    #[watch]
+                    set_visible: model.totp_credentials.is_none(),
+                },
+                gtk::Button {
+                    set_label: "Create account",
+                    connect_clicked => LoginPageInput::Register,
+                    set_margin_end: 10,
+                    #[watch]
+                    set_visible: model.totp_credentials.is_none() && !model.reset_password,
+                },
                 gtk::Button {
                     set_label: "Cancel/Skip",
                     connect_clicked => LoginPageInput::Cancel,
                     set_margin_end: 10,
                 },
+                gtk::Button {
+                    set_label: "Send reset link",
+                    #[watch]
+                    set_visible: model.reset_password,
+                    connect_clicked[sender, email] => move |_| {
+                        sender.input(LoginPageInput::ResetPassword(email.text().to_string()));
+                    },
+                },
                 gtk::Button {
                     set_label: "Login",
+                    #[watch]
+                    set_visible: !model.reset_password,
                     connect_clicked[sender, username, password, totp_token] => move |_| {
                         let username_text = username.text().as_str().to_string();
                         username.set_text("");
@@ -75,7 +189,13 @@ impl SimpleComponent for LoginPage {
         root: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = Self {};
+        let model = Self {
+            username_buffer: gtk::EntryBuffer::new(None::<String>),
+            totp_credentials: None,
+            reset_password: false,
+            error: None,
+            info: None,
+        };
         let widgets = view_output!();
         ComponentParts { model, widgets }
     }
@@ -86,41 +206,97 @@ impl SimpleComponent for LoginPage {
                 if get_current_account().instance_url.is_empty() {
                     return;
                 }
+                let (username, password) = self
+                    .totp_credentials
+                    .clone()
+                    .unwrap_or((username, password));
                 let token = if totp_token.is_empty() {
                     None
                 } else {
                     Some(totp_token)
                 };
+                self.error = None;
+                self.info = None;
                 sender
                     .output_sender()
                     .emit(crate::AppMsg::UpdateState(crate::AppState::Loading));
 
                 std::thread::spawn(move || {
-                    let message = match api::auth::login(username, password, token) {
+                    match api::auth::login(username.clone(), password.clone(), token) {
                         Ok(login) => {
-                            if let Some(token) = login.jwt {
-                                let mut account = settings::get_current_account();
-                                account.jwt = Some(token);
-                                settings::update_current_account(account.clone());
-
-                                if let Ok(site) = api::site::fetch_site() {
-                                    let user = site.my_user.unwrap().local_user_view.person;
-                                    account.name = user.name;
-                                    account.id = user.id.0;
-                                    settings::update_current_account(account);
-                                }
-                                crate::AppMsg::LoggedIn
+                            let message = if let Some(token) = login.jwt {
+                                sender.input(LoginPageInput::Reset);
+                                finish_login(token)
                             } else {
                                 crate::AppMsg::ShowMessage("Wrong credentials!".to_string())
-                            }
+                            };
+                            sender.output_sender().emit(message);
                         }
-                        Err(err) => crate::AppMsg::ShowMessage(err.to_string()),
+                        // only ask for the code if the account actually uses two-factor authentication
+                        Err(AuthError::Server(code)) if code == "missing_totp_token" => {
+                            sender.input(LoginPageInput::RequireTotp(username, password));
+                        }
+                        Err(err) => sender.input(LoginPageInput::LoginFailed(err.to_string())),
                     };
-                    sender.output_sender().emit(message);
                 });
             }
+            LoginPageInput::RequireTotp(username, password) => {
+                self.totp_credentials = Some((username, password));
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::UpdateState(crate::AppState::Login));
+            }
+            LoginPageInput::LoginFailed(err) => {
+                self.error = Some(err);
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::UpdateState(crate::AppState::Login));
+            }
+            LoginPageInput::ToggleResetPassword => {
+                self.reset_password = !self.reset_password;
+                self.error = None;
+                self.info = None;
+            }
+            LoginPageInput::ResetPassword(email) => {
+                if get_current_account().instance_url.is_empty() || email.is_empty() {
+                    return;
+                }
+                self.error = None;
+                self.info = None;
+                std::thread::spawn(move || {
+                    let result = api::auth::request_password_reset(email)
+                        .map(|_| ())
+                        .map_err(|err| err.to_string());
+                    sender.input(LoginPageInput::ResetRequested(result));
+                });
+            }
+            LoginPageInput::ResetRequested(result) => match result {
+                Ok(_) => {
+                    self.info = Some(
+                        "If there's an account with this email address, a link to reset its password has been sent to it.".to_string(),
+                    )
+                }
+                Err(err) => self.error = Some(err),
+            },
+            LoginPageInput::Register => {
+                if get_current_account().instance_url.is_empty() {
+                    return;
+                }
+                sender.output_sender().emit(crate::AppMsg::OpenRegister);
+            }
+            LoginPageInput::SessionExpired(name) => {
+                self.reset();
+                self.username_buffer.set_text(name);
+                self.info = Some("Your session has expired, please log in again.".to_string());
+            }
+            LoginPageInput::Reset => self.reset(),
             LoginPageInput::Cancel => {
-                sender.output_sender().emit(crate::AppMsg::OpenPosts);
+                // leaving the two-factor step or the password reset only returns to the login form
+                let back_to_login = self.totp_credentials.is_some() || self.reset_password;
+                self.reset();
+                if !back_to_login {
+                    sender.output_sender().emit(crate::AppMsg::OpenPosts);
+                }
             }
         }
     }
@@ -0,0 +1,145 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use std::rc::Rc;
+
+use crate::{
+    api, custom_emojis,
+    markdown::{self, LinkHandler},
+    settings,
+};
+
+pub struct MarkdownView {
+    text: String,
+    container: gtk::Box,
+    on_link: LinkHandler,
+    emojis_requested: bool,
+}
+
+#[derive(Debug)]
+pub enum MarkdownViewInput {
+    Update(String),
+    Rerender,
+    OpenLink(String),
+    OpenExternally(String),
+}
+
+impl MarkdownView {
+    fn render(&mut self, sender: &ComponentSender<Self>) {
+        let emojis = match custom_emojis::cached_custom_emojis() {
+            Some(emojis) => emojis,
+            None => {
+                // only fetch the emojis if the text might contain any
+                if self.text.contains(':') && !self.emojis_requested {
+                    self.emojis_requested = true;
+                    let sender = sender.clone();
+                    std::thread::spawn(move || {
+                        custom_emojis::get_custom_emojis();
+                        sender.input(MarkdownViewInput::Rerender);
+                    });
+                }
+                vec![]
+            }
+        };
+
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+        markdown::render(&self.container, &self.text, &emojis, &self.on_link);
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for MarkdownView {
+    type Init = String;
+    type Input = MarkdownViewInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            set_spacing: 10,
+        }
+    }
+
+    fn init(
+        init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = Self {
+            text: init,
+            container: root.clone(),
+            on_link: {
+                let sender = sender.clone();
+                Rc::new(move |url: String| sender.input(MarkdownViewInput::OpenLink(url)))
+            },
+            emojis_requested: false,
+        };
+        let widgets = view_output!();
+        model.render(&sender);
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            MarkdownViewInput::Update(text) => {
+                if text != self.text {
+                    self.text = text;
+                    self.render(&sender);
+                }
+            }
+            MarkdownViewInput::Rerender => self.render(&sender),
+            MarkdownViewInput::OpenLink(url) => {
+                let instance_url = settings::get_current_account().instance_url;
+                let Some(query) = markdown::lemmy_object_query(&url, &instance_url) else {
+                    sender.input(MarkdownViewInput::OpenExternally(url));
+                    return;
+                };
+                std::thread::spawn(move || {
+                    let message = match api::search::resolve_object(query) {
+                        Ok(object) => {
+                            if let Some(post) = object.post {
+                                Some(crate::AppMsg::OpenPost(post.post.id))
+                            } else if let Some(comment) = object.comment {
+                                Some(crate::AppMsg::OpenPost(comment.post.id))
+                            } else if let Some(community) = object.community {
+                                Some(crate::AppMsg::OpenCommunity(community.community.id))
+                            } else {
+                                object
+                                    .person
+                                    .map(|person| crate::AppMsg::OpenPerson(person.person.id))
+                            }
+                        }
+                        Err(err) => {
+                            println!("{}", err);
+                            None
+                        }
+                    };
+                    match message {
+                        Some(message) => sender.output_sender().emit(message),
+                        // the instance couldn't find it, so it's probably not a lemmy link after all
+                        None => sender.input(MarkdownViewInput::OpenExternally(url)),
+                    }
+                });
+            }
+            MarkdownViewInput::OpenExternally(url) => {
+                // identifiers have no web address that the browser could open
+                if url.starts_with('!') || url.starts_with('@') {
+                    sender
+                        .output_sender()
+                        .emit(crate::AppMsg::ShowMessage(format!(
+                            "Could not find {}",
+                            url
+                        )));
+                    return;
+                }
+                let instance_url = settings::get_current_account().instance_url;
+                let url = match url.starts_with('/') {
+                    true => format!("{}{}", instance_url, url),
+                    false => url,
+                };
+                gtk::show_uri(None::<&relm4::gtk::Window>, &url, 0);
+            }
+        }
+    }
+}
@@ -1,21 +1,32 @@
 use gtk::prelude::*;
-use lemmy_api_common::lemmy_db_views_actor::structs::CommentReplyView;
+use lemmy_api_common::{
+    lemmy_db_schema::newtypes::{CommentId, PersonMentionId},
+    lemmy_db_views_actor::structs::CommentReplyView,
+};
 use relm4::prelude::*;
 use relm4_components::web_image::WebImage;
 
+use crate::api;
+use crate::dialogs::editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType};
+use crate::drafts;
+use crate::drafts::DraftTarget;
 use crate::util;
 use crate::util::get_web_image_url;
-use crate::util::markdown_to_pango_markup;
 
+use super::markdown_view::MarkdownView;
 use super::voting_row::VotingRowModel;
 use super::voting_row::VotingStats;
 
-#[derive(Debug)]
 pub struct MentionRow {
     comment: CommentReplyView,
+    // mentions are shown with the same view as replies, but have their own id
+    mention_id: Option<PersonMentionId>,
+    read: bool,
     creator_image: Controller<WebImage>,
     community_image: Controller<WebImage>,
+    content: Controller<MarkdownView>,
     voting_row: Controller<VotingRowModel>,
+    editor_dialog: Controller<EditorDialog>,
 }
 
 #[derive(Debug)]
@@ -23,11 +34,38 @@ pub enum MentionRowMsg {
     OpenPerson,
     OpenPost,
     OpenCommunity,
+    OpenContext,
+    ToggleRead,
+    UpdateRead(bool),
+    ToggleSaved,
+    UpdateSaved(bool),
+    OpenEditor,
+    CreateReply(Box<EditorData>),
+}
+
+impl MentionRow {
+    fn draft_target(&self) -> DraftTarget {
+        DraftTarget::Comment {
+            post_id: self.comment.post.id,
+            parent_id: Some(self.comment.comment.id),
+        }
+    }
+
+    // the comment that was replied to is shown as well, if there's any
+    fn context_id(&self) -> CommentId {
+        let path: Vec<&str> = self.comment.comment.path.split('.').collect();
+        if path.len() > 2 {
+            if let Ok(parent_id) = path[path.len() - 2].parse() {
+                return CommentId(parent_id);
+            }
+        }
+        self.comment.comment.id
+    }
 }
 
 #[relm4::factory(pub)]
 impl FactoryComponent for MentionRow {
-    type Init = CommentReplyView;
+    type Init = (CommentReplyView, Option<PersonMentionId>);
     type Input = MentionRowMsg;
     type Output = crate::AppMsg;
     type CommandOutput = ();
@@ -50,7 +88,7 @@ impl FactoryComponent for MentionRow {
                 set_wrap: true,
                 add_controller = gtk::GestureClick {
                     connect_pressed[sender] => move |_, _, _, _| {
-                        sender.input(MentionRowMsg::OpenCommunity);
+                        sender.input(MentionRowMsg::OpenPost);
                     }
                 },
             },
@@ -93,19 +131,49 @@ impl FactoryComponent for MentionRow {
                 gtk::Label {
                     set_margin_start: 10,
                     set_label: &util::format_elapsed_time(self.comment.comment.published),
-                }
-            },
+                },
 
-            gtk::Label {
-                #[watch]
-               set_markup: &markdown_to_pango_markup(self.comment.comment.content.clone()),
-               set_wrap: true,
-               set_halign: gtk::Align::Start,
-               set_use_markup: true,
+                gtk::Box {
+                    set_hexpand: true,
+                },
+
+                gtk::Button {
+                    #[watch]
+                    set_label: if self.read { "Mark as unread" } else { "Mark as read" },
+                    connect_clicked => MentionRowMsg::ToggleRead,
+                },
             },
 
             #[local_ref]
-            voting_row -> gtk::Box {},
+            content -> gtk::Box {},
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+
+                #[local_ref]
+                voting_row -> gtk::Box {},
+
+                gtk::Button {
+                    set_icon_name: "mail-replied",
+                    set_tooltip_text: Some("Reply"),
+                    connect_clicked => MentionRowMsg::OpenEditor,
+                },
+
+                gtk::ToggleButton {
+                    set_icon_name: "bookmark-new",
+                    set_margin_start: 5,
+                    set_tooltip_text: Some("Save"),
+                    connect_clicked => MentionRowMsg::ToggleSaved,
+                    #[watch]
+                    set_active: self.comment.saved,
+                },
+
+                gtk::Button {
+                    set_label: "Open in context",
+                    connect_clicked => MentionRowMsg::OpenContext,
+                },
+            },
 
             gtk::Separator {}
         }
@@ -115,25 +183,41 @@ impl FactoryComponent for MentionRow {
         Some(output)
     }
 
-    fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+    fn init_model(init: Self::Init, _index: &DynamicIndex, sender: FactorySender<Self>) -> Self {
+        let (value, mention_id) = init;
         let creator_image = WebImage::builder()
             .launch(get_web_image_url(value.creator.avatar.clone()))
             .detach();
         let community_image = WebImage::builder()
             .launch(get_web_image_url(value.community.icon.clone()))
             .detach();
+        let content = MarkdownView::builder()
+            .launch(value.comment.content.clone())
+            .forward(sender.output_sender(), |msg| msg);
         let voting_row = VotingRowModel::builder()
             .launch(VotingStats::from_comment(
                 value.counts.clone(),
                 value.my_vote,
             ))
             .detach();
+        let editor_dialog = EditorDialog::builder().launch(EditorType::Comment).forward(
+            sender.input_sender(),
+            |msg| match msg {
+                EditorOutput::EditRequest(data, _) | EditorOutput::CreateRequest(data, _) => {
+                    MentionRowMsg::CreateReply(Box::new(data))
+                }
+            },
+        );
 
         Self {
+            read: value.comment_reply.read,
             comment: value,
+            mention_id,
             creator_image,
             community_image,
+            content,
             voting_row,
+            editor_dialog,
         }
     }
 
@@ -146,6 +230,7 @@ impl FactoryComponent for MentionRow {
     ) -> Self::Widgets {
         let creator_image = self.creator_image.widget();
         let community_image = self.community_image.widget();
+        let content = self.content.widget();
         let voting_row = self.voting_row.widget();
         let widgets = view_output!();
         widgets
@@ -162,6 +247,73 @@ impl FactoryComponent for MentionRow {
             MentionRowMsg::OpenCommunity => {
                 sender.output(crate::AppMsg::OpenCommunity(self.comment.community.id));
             }
+            MentionRowMsg::ToggleRead => {
+                let read = !self.read;
+                let reply_id = self.comment.comment_reply.id;
+                let mention_id = self.mention_id;
+                std::thread::spawn(move || {
+                    let result = match mention_id {
+                        Some(mention_id) => {
+                            api::user::mark_mention_as_read(mention_id, read).map(|_| ())
+                        }
+                        None => api::user::mark_reply_as_read(reply_id, read).map(|_| ()),
+                    };
+                    match result {
+                        Ok(_) => {
+                            sender.input(MentionRowMsg::UpdateRead(read));
+                            sender.output(crate::AppMsg::FetchUnreadCount);
+                        }
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            MentionRowMsg::UpdateRead(read) => {
+                self.read = read;
+            }
+            MentionRowMsg::OpenContext => {
+                sender.output(crate::AppMsg::OpenComment(
+                    self.comment.post.id,
+                    self.context_id(),
+                ));
+            }
+            MentionRowMsg::ToggleSaved => {
+                let comment_id = self.comment.comment.id;
+                let save = !self.comment.saved;
+                std::thread::spawn(move || match api::comment::save_comment(comment_id, save) {
+                    Ok(response) => {
+                        sender.input(MentionRowMsg::UpdateSaved(response.comment_view.saved))
+                    }
+                    Err(err) => println!("{}", err),
+                });
+            }
+            MentionRowMsg::UpdateSaved(saved) => {
+                self.comment.saved = saved;
+            }
+            MentionRowMsg::OpenEditor => {
+                let sender = self.editor_dialog.sender();
+                sender.emit(DialogMsg::UpdateData(Box::default()));
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(self.draft_target())));
+                sender.emit(DialogMsg::UpdateType(EditorType::Comment, true));
+                sender.emit(DialogMsg::Show);
+            }
+            MentionRowMsg::CreateReply(data) => {
+                let post_id = self.comment.post.id;
+                let parent_id = self.comment.comment.id;
+                let target = self.draft_target();
+                let was_read = self.read;
+                std::thread::spawn(move || {
+                    match api::comment::create_comment(post_id, data.body, Some(parent_id)) {
+                        Ok(_) => {
+                            drafts::remove_draft(&target);
+                            // answering something means it has been read
+                            if !was_read {
+                                sender.input(MentionRowMsg::ToggleRead);
+                            }
+                        }
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
         }
     }
 }
@@ -1,14 +1,22 @@
 pub mod account_row;
 pub mod accounts_page;
+pub mod block_row;
+pub mod blocks_page;
 pub mod comment_row;
 pub mod communities_page;
 pub mod community_page;
 pub mod community_row;
+pub mod conversation_page;
+pub mod conversation_row;
+pub mod draft_row;
+pub mod drafts_page;
+pub mod emoji_picker;
 pub mod inbox_page;
 pub mod instance_row;
 pub mod instances_page;
 pub mod loading_indicator;
 pub mod login_page;
+pub mod markdown_view;
 pub mod mention_row;
 pub mod moderates_row;
 pub mod post_page;
@@ -16,5 +24,10 @@ pub mod post_row;
 pub mod posts_page;
 pub mod private_message_row;
 pub mod profile_page;
+pub mod register_page;
+pub mod security_page;
 pub mod sort_dropdown;
+pub mod upload_row;
+pub mod uploads_page;
+pub mod user_settings_page;
 pub mod voting_row;
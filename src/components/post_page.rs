@@ -1,5 +1,6 @@
-use gtk::prelude::*;
+use gtk::{glib, prelude::*};
 use lemmy_api_common::{
+    lemmy_db_schema::{newtypes::CommentId, CommentSortType},
     lemmy_db_views::structs::{CommentView, PostView},
     post::GetPostResponse,
 };
@@ -12,12 +13,15 @@ use crate::{
         editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType},
         report_dialog::{ReportDialog, ReportDialogInput},
     },
+    drafts::{self, DraftTarget},
+    gsettings::{self, NsfwHandling},
     settings,
-    util::{self, get_web_image_msg, get_web_image_url, markdown_to_pango_markup},
+    util::{self, get_web_image_msg, get_web_image_url},
 };
 
 use super::{
-    comment_row::CommentRow,
+    comment_row::{CommentRow, CommentRowMsg},
+    markdown_view::{MarkdownView, MarkdownViewInput},
     voting_row::{VotingRowInput, VotingRowModel, VotingStats},
 };
 
@@ -26,14 +30,24 @@ pub struct PostPage {
     image: Controller<WebImage>,
     creator_avatar: Controller<WebImage>,
     community_avatar: Controller<WebImage>,
+    body: Controller<MarkdownView>,
     comments: FactoryVecDeque<CommentRow>,
     #[allow(dead_code)]
     create_comment_dialog: Controller<EditorDialog>,
     report_post_dialog: Controller<ReportDialog>,
     voting_row: Controller<VotingRowModel>,
     thumbnail_height: i32,
+    live_updates: Option<glib::SourceId>,
+    // only the comment thread below this comment is shown, e.g. when opened from the inbox
+    context: Option<CommentId>,
+    // whether the image is only loaded once the user asks for it
+    image_deferred: bool,
+    blur_image: bool,
 }
 
+// interval in seconds in which the comments are refetched when live updates are enabled
+const LIVE_UPDATES_INTERVAL: u32 = 30;
+
 #[derive(Debug)]
 pub enum PostPageInput {
     UpdatePost(GetPostResponse),
@@ -53,6 +67,29 @@ pub enum PostPageInput {
     DoneEditPost(PostView),
     PassAppMessage(crate::AppMsg),
     ShowReportDialog,
+    ToggleLiveUpdates,
+    // stops polling for new comments once the page isn't shown anymore
+    StopLiveUpdates,
+    FetchNewComments,
+    DoneFetchNewComments(Vec<CommentView>),
+    UpdateContext(Option<CommentId>),
+    ShowAllComments,
+    LoadImage,
+    BlockInstance,
+}
+
+impl PostPage {
+    fn fetch_comments(&self, sender: &ComponentSender<Self>) {
+        let post_id = self.info.post_view.post.id;
+        let context = self.context;
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let comments = api::post::get_comments(post_id, CommentSortType::Hot, context);
+            if let Ok(comments) = comments {
+                sender.input(PostPageInput::DoneFetchComments(comments));
+            }
+        });
+    }
 }
 
 #[relm4::component(pub)]
@@ -69,6 +106,14 @@ impl SimpleComponent for PostPage {
                 set_hexpand: true,
                 set_margin_all: 10,
 
+                gtk::Button {
+                    set_label: "Load image",
+                    set_halign: gtk::Align::Start,
+                    set_margin_top: 10,
+                    #[watch]
+                    set_visible: model.image_deferred,
+                    connect_clicked => PostPageInput::LoadImage,
+                },
                 #[local_ref]
                 image -> gtk::Box {
                     #[watch]
@@ -76,7 +121,9 @@ impl SimpleComponent for PostPage {
                     set_margin_bottom: 20,
                     set_margin_top: 20,
                     #[watch]
-                    set_visible: model.info.post_view.post.thumbnail_url.is_some(),
+                    set_visible: model.info.post_view.post.thumbnail_url.is_some() && !model.image_deferred,
+                    #[watch]
+                    set_class_active: ("nsfw-blur", model.blur_image),
                     add_controller = gtk::GestureClick {
                         connect_pressed[sender] => move |_, _, _, _| {
                             sender.input(PostPageInput::OpenImage);
@@ -94,14 +141,11 @@ impl SimpleComponent for PostPage {
                         }
                     },
                 },
-                gtk::Label {
-                    #[watch]
-                    set_markup: &markdown_to_pango_markup(model.info.post_view.post.body.clone().unwrap_or("".to_string())),
-                    set_wrap: true,
+                #[local_ref]
+                body -> gtk::Box {
                     set_margin_top: 10,
                     set_margin_start: 15,
                     set_margin_end: 15,
-                    set_use_markup: true,
                 },
 
                 gtk::Box {
@@ -200,6 +244,39 @@ impl SimpleComponent for PostPage {
                         connect_clicked => PostPageInput::ShowReportDialog,
                         set_visible: settings::get_current_account().jwt.is_some(),
                     },
+                    gtk::Button {
+                        set_icon_name: "network-server",
+                        set_margin_start: 10,
+                        set_tooltip_text: Some("Block the instance of the community"),
+                        connect_clicked => PostPageInput::BlockInstance,
+                        #[watch]
+                        set_visible: settings::get_current_account().jwt.is_some() && !model.info.community_view.community.local,
+                    },
+                    gtk::ToggleButton {
+                        set_icon_name: "view-refresh",
+                        set_margin_start: 10,
+                        set_tooltip_text: Some("Live updates"),
+                        connect_clicked => PostPageInput::ToggleLiveUpdates,
+                        #[watch]
+                        set_active: model.live_updates.is_some(),
+                    },
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 10,
+                    set_margin_all: 10,
+                    #[watch]
+                    set_visible: model.context.is_some(),
+
+                    gtk::Label {
+                        set_label: "Only a single comment thread is shown",
+                        add_css_class: "dim-label",
+                    },
+                    gtk::Button {
+                        set_label: "Show all comments",
+                        connect_clicked => PostPageInput::ShowAllComments,
+                    },
                 },
 
                 #[local_ref]
@@ -219,6 +296,9 @@ impl SimpleComponent for PostPage {
         let comments = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
         let creator_avatar = WebImage::builder().launch("".to_string()).detach();
         let community_avatar = WebImage::builder().launch("".to_string()).detach();
+        let body = MarkdownView::builder()
+            .launch("".to_string())
+            .forward(sender.output_sender(), |msg| msg);
         let create_comment_dialog = EditorDialog::builder()
             .transient_for(root)
             .launch(EditorType::Comment)
@@ -233,7 +313,7 @@ impl SimpleComponent for PostPage {
             .detach();
         let report_post_dialog = ReportDialog::builder()
             .transient_for(root)
-            .launch((None, None))
+            .launch((None, None, None))
             .detach();
 
         let model = PostPage {
@@ -242,10 +322,15 @@ impl SimpleComponent for PostPage {
             comments,
             creator_avatar,
             community_avatar,
+            body,
             create_comment_dialog,
             report_post_dialog,
             voting_row,
             thumbnail_height: 400,
+            live_updates: None,
+            context: None,
+            image_deferred: false,
+            blur_image: false,
         };
 
         let image = model.image.widget();
@@ -253,8 +338,14 @@ impl SimpleComponent for PostPage {
         let creator_avatar = model.creator_avatar.widget();
         let community_avatar = model.community_avatar.widget();
         let voting_row = model.voting_row.widget();
+        let body = model.body.widget();
         let widgets = view_output!();
 
+        {
+            let sender = sender.clone();
+            root.connect_unmap(move |_| sender.input(PostPageInput::StopLiveUpdates));
+        }
+
         ComponentParts { model, widgets }
     }
 
@@ -268,10 +359,26 @@ impl SimpleComponent for PostPage {
                     None => unreachable!(),
                 }
 
+                // live updates only belong to the previously opened post
+                if let Some(source) = self.live_updates.take() {
+                    source.remove();
+                }
+
                 self.info = post.clone();
+                self.body.emit(MarkdownViewInput::Update(
+                    post.post_view.post.body.clone().unwrap_or_default(),
+                ));
 
-                self.image
-                    .emit(get_web_image_msg(post.post_view.post.thumbnail_url));
+                let post_data = &post.post_view.post;
+                self.blur_image = (post_data.nsfw || post.community_view.community.nsfw)
+                    && gsettings::nsfw_handling() == NsfwHandling::Blur;
+                self.image_deferred =
+                    post_data.thumbnail_url.is_some() && !gsettings::load_images();
+                self.image.emit(if self.image_deferred {
+                    get_web_image_msg(None)
+                } else {
+                    get_web_image_msg(post_data.thumbnail_url.clone())
+                });
                 self.community_avatar
                     .emit(get_web_image_msg(post.community_view.community.icon));
                 self.creator_avatar
@@ -284,15 +391,23 @@ impl SimpleComponent for PostPage {
                     )));
                 self.comments.guard().clear();
 
-                std::thread::spawn(move || {
-                    if post.post_view.counts.comments == 0 {
-                        return;
-                    }
-                    let comments = api::post::get_comments(post.post_view.post.id);
-                    if let Ok(comments) = comments {
-                        sender.input(PostPageInput::DoneFetchComments(comments));
-                    }
-                });
+                if post.post_view.counts.comments > 0 {
+                    self.fetch_comments(&sender);
+                }
+            }
+            PostPageInput::UpdateContext(context) => {
+                self.context = context;
+            }
+            PostPageInput::LoadImage => {
+                self.image_deferred = false;
+                self.image.emit(get_web_image_msg(
+                    self.info.post_view.post.thumbnail_url.clone(),
+                ));
+            }
+            PostPageInput::ShowAllComments => {
+                self.context = None;
+                self.comments.guard().clear();
+                self.fetch_comments(&sender);
             }
             PostPageInput::DoneFetchComments(comments) => {
                 for comment in comments {
@@ -328,7 +443,12 @@ impl SimpleComponent for PostPage {
                 }
             }
             PostPageInput::OpenCreateCommentDialog => {
+                let target = DraftTarget::Comment {
+                    post_id: self.info.post_view.post.id,
+                    parent_id: None,
+                };
                 let sender = self.create_comment_dialog.sender();
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(target)));
                 sender.emit(DialogMsg::UpdateType(EditorType::Comment, true));
                 sender.emit(DialogMsg::Show);
             }
@@ -339,7 +459,13 @@ impl SimpleComponent for PostPage {
                 let id = self.info.post_view.post.id;
                 std::thread::spawn(move || {
                     let message = match api::comment::create_comment(id, post.body, None) {
-                        Ok(comment) => Some(PostPageInput::CreatedComment(comment.comment_view)),
+                        Ok(comment) => {
+                            drafts::remove_draft(&DraftTarget::Comment {
+                                post_id: id,
+                                parent_id: None,
+                            });
+                            Some(PostPageInput::CreatedComment(comment.comment_view))
+                        }
                         Err(err) => {
                             println!("{}", err);
                             None
@@ -369,31 +495,32 @@ impl SimpleComponent for PostPage {
                 });
             }
             PostPageInput::OpenEditPostDialog => {
-                let url = match self.info.post_view.post.url.clone() {
-                    Some(url) => url.to_string(),
-                    None => String::from(""),
-                };
+                let post = &self.info.post_view.post;
                 let data = EditorData {
-                    name: self.info.post_view.post.name.clone(),
-                    body: self
-                        .info
-                        .post_view
-                        .post
-                        .body
-                        .clone()
-                        .unwrap_or(String::from("")),
-                    url: reqwest::Url::parse(&url).ok(),
+                    name: post.name.clone(),
+                    body: post.body.clone().unwrap_or(String::from("")),
+                    url: post.url.clone().map(|url| url.into()),
+                    community_id: Some(post.community_id),
+                    language_id: Some(post.language_id),
+                    nsfw: post.nsfw,
+                    // the thumbnail is left untouched unless a new one is entered
+                    ..Default::default()
                 };
+                let target = DraftTarget::EditPost(self.info.post_view.post.id);
                 let sender = self.create_comment_dialog.sender();
-                sender.emit(DialogMsg::UpdateData(data));
+                sender.emit(DialogMsg::UpdateData(Box::new(data)));
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(target)));
                 sender.emit(DialogMsg::UpdateType(EditorType::Post, false));
                 sender.emit(DialogMsg::Show);
             }
             PostPageInput::EditPostRequest(post) => {
-                let id = self.info.post_view.post.id.0;
+                let id = self.info.post_view.post.id;
                 std::thread::spawn(move || {
-                    let message = match api::post::edit_post(post.name, post.url, post.body, id) {
-                        Ok(post) => Some(PostPageInput::DoneEditPost(post.post_view)),
+                    let message = match api::post::edit_post(post.post_details(), id) {
+                        Ok(post) => {
+                            drafts::remove_draft(&DraftTarget::EditPost(post.post_view.post.id));
+                            Some(PostPageInput::DoneEditPost(post.post_view))
+                        }
                         Err(err) => {
                             println!("{}", err);
                             None
@@ -405,18 +532,93 @@ impl SimpleComponent for PostPage {
                 });
             }
             PostPageInput::DoneEditPost(post) => {
+                self.body.emit(MarkdownViewInput::Update(
+                    post.post.body.clone().unwrap_or_default(),
+                ));
                 self.info.post_view = post;
             }
             PostPageInput::PassAppMessage(message) => {
                 sender.output_sender().emit(message);
             }
+            PostPageInput::BlockInstance => {
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::BlockInstance(Box::new(
+                        self.info.community_view.community.clone(),
+                    )));
+            }
             PostPageInput::ShowReportDialog => {
-                let message = ReportDialogInput::UpdateId(Some(self.info.post_view.post.id), None);
+                let message =
+                    ReportDialogInput::UpdateId(Some(self.info.post_view.post.id), None, None);
                 self.report_post_dialog.sender().emit(message);
                 self.report_post_dialog
                     .sender()
                     .emit(ReportDialogInput::Show);
             }
+            PostPageInput::ToggleLiveUpdates => match self.live_updates.take() {
+                Some(source) => source.remove(),
+                None => {
+                    sender.input(PostPageInput::FetchNewComments);
+                    let source =
+                        glib::timeout_add_seconds_local(LIVE_UPDATES_INTERVAL, move || {
+                            sender.input(PostPageInput::FetchNewComments);
+                            glib::Continue(true)
+                        });
+                    self.live_updates = Some(source);
+                }
+            },
+            PostPageInput::StopLiveUpdates => {
+                if let Some(source) = self.live_updates.take() {
+                    source.remove();
+                }
+            }
+            PostPageInput::FetchNewComments => {
+                let post_id = self.info.post_view.post.id;
+                let context = self.context;
+                std::thread::spawn(move || {
+                    match api::post::get_comments(post_id, CommentSortType::New, context) {
+                        Ok(comments) => sender.input(PostPageInput::DoneFetchNewComments(comments)),
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            PostPageInput::DoneFetchNewComments(mut comments) => {
+                // insert parents before their children
+                comments.sort_by_key(|c| c.comment.path.matches('.').count());
+
+                for comment in comments {
+                    let existing = self
+                        .comments
+                        .iter()
+                        .position(|row| row.comment.comment.id == comment.comment.id);
+                    if let Some(index) = existing {
+                        self.comments
+                            .send(index, CommentRowMsg::UpdateComment(comment));
+                        continue;
+                    }
+
+                    // the path consists of "0.<ancestor ids>.<comment id>"
+                    let parent_path = comment
+                        .comment
+                        .path
+                        .rsplit_once('.')
+                        .map(|(parent, _)| parent.to_string())
+                        .unwrap_or_default();
+                    let index = if parent_path == "0" {
+                        Some(0)
+                    } else {
+                        self.comments
+                            .iter()
+                            .position(|row| row.comment.comment.path == parent_path)
+                            .map(|parent_index| parent_index + 1)
+                    };
+                    // the parent isn't loaded (e.g. it's too deeply nested), hence skip the comment
+                    if let Some(index) = index {
+                        self.comments.guard().insert(index, comment);
+                        self.info.post_view.counts.comments += 1;
+                    }
+                }
+            }
         }
     }
 }
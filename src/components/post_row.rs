@@ -3,6 +3,7 @@ use lemmy_api_common::lemmy_db_views::structs::PostView;
 use relm4::prelude::*;
 use relm4_components::web_image::WebImage;
 
+use crate::gsettings::{self, NsfwHandling};
 use crate::{api, util::get_web_image_url};
 use crate::{settings, util};
 
@@ -16,6 +17,14 @@ pub struct PostRow {
     thumbnail: Controller<WebImage>,
     voting_row: Controller<VotingRowModel>,
     image_size: i32,
+    show_thumbnail: bool,
+    nsfw_handling: NsfwHandling,
+}
+
+impl PostRow {
+    fn is_nsfw(&self) -> bool {
+        self.post.post.nsfw || self.post.community.nsfw
+    }
 }
 
 #[derive(Debug)]
@@ -44,6 +53,7 @@ impl FactoryComponent for PostRow {
             set_orientation: gtk::Orientation::Vertical,
             set_margin_end: 10,
             set_margin_start: 10,
+            set_visible: !(self.is_nsfw() && self.nsfw_handling == NsfwHandling::Hide),
 
             gtk::Box {
                 set_orientation: gtk::Orientation::Horizontal,
@@ -51,7 +61,8 @@ impl FactoryComponent for PostRow {
 
                 #[local_ref]
                 thumbnail -> gtk::Box {
-                    set_visible: self.post.post.thumbnail_url.is_some(),
+                    set_visible: self.show_thumbnail,
+                    set_class_active: ("nsfw-blur", self.is_nsfw() && self.nsfw_handling == NsfwHandling::Blur),
                     set_size_request: (self.image_size, self.image_size),
                     set_margin_start: 10,
                     set_margin_end: 10,
@@ -160,9 +171,16 @@ impl FactoryComponent for PostRow {
     }
 
     fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
-        let thumbnail = WebImage::builder()
-            .launch(get_web_image_url(value.post.thumbnail_url.clone()))
-            .detach();
+        // the compact layout and disabled image loading both go without thumbnails
+        let show_thumbnail = value.post.thumbnail_url.is_some()
+            && !gsettings::compact_feed()
+            && gsettings::load_images();
+        let thumbnail_url = if show_thumbnail {
+            get_web_image_url(value.post.thumbnail_url.clone())
+        } else {
+            String::new()
+        };
+        let thumbnail = WebImage::builder().launch(thumbnail_url).detach();
         let author_image = WebImage::builder()
             .launch(get_web_image_url(value.creator.avatar.clone()))
             .detach();
@@ -180,6 +198,8 @@ impl FactoryComponent for PostRow {
             voting_row,
             thumbnail,
             image_size: 1500,
+            show_thumbnail,
+            nsfw_handling: gsettings::nsfw_handling(),
         }
     }
 
@@ -6,8 +6,10 @@ use lemmy_api_common::{
 use relm4::{factory::FactoryVecDeque, prelude::*};
 
 use crate::api;
-
-use crate::settings::get_prefs;
+use crate::dialogs::editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType};
+use crate::drafts::{self, DraftTarget};
+use crate::gsettings;
+use crate::settings;
 
 use super::{
     post_row::PostRow,
@@ -20,13 +22,19 @@ pub struct PostsPage {
     posts_order: SortType,
     posts_type: ListingType,
     posts_page: i64,
+    create_post_dialog: Controller<EditorDialog>,
 }
 
 #[derive(Debug)]
 pub enum PostsPageInput {
     FetchPosts(ListingType, SortType, bool),
+    // fetches the first page again, with the current listing type and sort order
+    Refresh,
     DoneFetchPosts(Vec<PostView>),
     UpdateOrder(SortType),
+    OpenCreatePostDialog,
+    CreatePostRequest(Box<EditorData>),
+    None,
 }
 
 #[relm4::component(pub)]
@@ -39,7 +47,7 @@ impl SimpleComponent for PostsPage {
         gtk::ScrolledWindow {
             set_hexpand: true,
             connect_edge_reached[sender] => move |_, pos| {
-                if pos == gtk::PositionType::Bottom && get_prefs().infinite_scroll {
+                if pos == gtk::PositionType::Bottom && gsettings::infinite_scroll() {
                     sender.input(
                         PostsPageInput::FetchPosts(model.posts_type, model.posts_order, false)
                     );
@@ -77,6 +85,13 @@ impl SimpleComponent for PostsPage {
                         set_hexpand: true,
                     },
 
+                    gtk::Button {
+                        set_label: "Create post",
+                        #[watch]
+                        set_visible: settings::get_current_account().jwt.is_some(),
+                        connect_clicked => PostsPageInput::OpenCreatePostDialog,
+                    },
+
                     #[local_ref]
                     sort_dropdown -> gtk::DropDown {
                         set_margin_end: 10,
@@ -101,19 +116,28 @@ impl SimpleComponent for PostsPage {
         root: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let sort_dropdown =
-            SortDropdown::builder()
-                .launch(())
-                .forward(sender.input_sender(), |msg| match msg {
-                    SortDropdownOutput::New(sort_order) => PostsPageInput::UpdateOrder(sort_order),
-                });
+        let sort_dropdown = SortDropdown::builder()
+            .launch(gsettings::default_sort())
+            .forward(sender.input_sender(), |msg| match msg {
+                SortDropdownOutput::New(sort_order) => PostsPageInput::UpdateOrder(sort_order),
+            });
         let posts = FactoryVecDeque::new(gtk::Box::default(), sender.output_sender());
+        let create_post_dialog = EditorDialog::builder()
+            .transient_for(root)
+            .launch(EditorType::Post)
+            .forward(sender.input_sender(), |msg| match msg {
+                EditorOutput::CreateRequest(post, _) => {
+                    PostsPageInput::CreatePostRequest(Box::new(post))
+                }
+                _ => PostsPageInput::None,
+            });
         let model = Self {
             sort_dropdown,
             posts,
-            posts_type: ListingType::Local,
-            posts_order: SortType::Hot,
+            posts_type: gsettings::default_listing(),
+            posts_order: gsettings::default_sort(),
             posts_page: 1,
+            create_post_dialog,
         };
         let sort_dropdown = model.sort_dropdown.widget();
         let posts_box = model.posts.widget();
@@ -149,6 +173,13 @@ impl SimpleComponent for PostsPage {
                     };
                 });
             }
+            PostsPageInput::Refresh => {
+                sender.input(PostsPageInput::FetchPosts(
+                    self.posts_type,
+                    self.posts_order,
+                    true,
+                ));
+            }
             PostsPageInput::DoneFetchPosts(posts) => {
                 sender
                     .output_sender()
@@ -168,6 +199,35 @@ impl SimpleComponent for PostsPage {
                     true,
                 ));
             }
+            PostsPageInput::OpenCreatePostDialog => {
+                let sender = self.create_post_dialog.sender();
+                // the draft target is set once a community has been chosen in the dialog
+                sender.emit(DialogMsg::UpdateDraftTarget(None));
+                sender.emit(DialogMsg::UpdateCommunity(None));
+                sender.emit(DialogMsg::Show);
+            }
+            PostsPageInput::CreatePostRequest(post) => {
+                let Some(community_id) = post.community_id else {
+                    sender.output_sender().emit(crate::AppMsg::ShowMessage(
+                        "The post needs a community".to_string(),
+                    ));
+                    return;
+                };
+                std::thread::spawn(move || {
+                    match api::post::create_post(post.post_details(), community_id) {
+                        Ok(post) => {
+                            drafts::remove_draft(&DraftTarget::NewPost(community_id));
+                            sender
+                                .output_sender()
+                                .emit(crate::AppMsg::OpenPost(post.post_view.post.id))
+                        }
+                        Err(err) => sender
+                            .output_sender()
+                            .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                    }
+                });
+            }
+            PostsPageInput::None => {}
         }
     }
 }
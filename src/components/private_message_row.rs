@@ -2,18 +2,34 @@ use gtk::prelude::*;
 use lemmy_api_common::lemmy_db_views::structs::PrivateMessageView;
 use relm4::prelude::FactoryComponent;
 use relm4::prelude::*;
-use relm4_components::web_image::WebImage;
 
-use crate::util::{self, get_web_image_url, markdown_to_pango_markup};
+use crate::api;
+use crate::dialogs::editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType};
+use crate::dialogs::report_dialog::{ReportDialog, ReportDialogInput};
+use crate::settings;
+use crate::util;
+
+use super::markdown_view::{MarkdownView, MarkdownViewInput};
 
 pub struct PrivateMessageRow {
     message: PrivateMessageView,
-    creator_image: Controller<WebImage>,
+    content: Controller<MarkdownView>,
+    editor_dialog: Controller<EditorDialog>,
+    report_dialog: Controller<ReportDialog>,
+    read: bool,
+    // only received messages can be marked as read or reported, only sent ones edited
+    received: bool,
 }
 
 #[derive(Debug)]
 pub enum PrivateMessageRowInput {
-    OpenPerson,
+    ToggleRead,
+    UpdateRead(bool),
+    OpenEditor,
+    EditMessage(String),
+    UpdateMessage(Box<PrivateMessageView>),
+    DeleteMessage,
+    ShowReportDialog,
 }
 
 #[relm4::factory(pub)]
@@ -28,56 +44,97 @@ impl FactoryComponent for PrivateMessageRow {
     view! {
         root = gtk::Box {
             set_orientation: gtk::Orientation::Vertical,
-            set_spacing: 10,
-            set_margin_end: 10,
-            set_margin_start: 10,
-            set_margin_top: 10,
-            set_vexpand: false,
+            set_spacing: 5,
+            set_margin_all: 10,
+            // sent messages are shown on the right side like in other messengers
+            set_halign: if self.received { gtk::Align::Start } else { gtk::Align::End },
+            #[watch]
+            set_visible: !self.message.private_message.deleted,
+            add_css_class: "message-bubble",
+            add_css_class: if self.received { "message-received" } else { "message-sent" },
+
+            #[local_ref]
+            content -> gtk::Box {},
 
             gtk::Box {
                 set_orientation: gtk::Orientation::Horizontal,
-                set_spacing: 10,
-                set_vexpand: false,
-                set_hexpand: true,
-
-                #[local_ref]
-                creator_image -> gtk::Box {
-                    set_hexpand: false,
-                    set_visible: self.message.creator.avatar.is_some(),
+                set_spacing: 5,
+
+                gtk::Label {
+                    add_css_class: "dim-label",
+                    #[watch]
+                    set_label: &match self.message.private_message.updated {
+                        Some(updated) => format!("{} (edited {})",
+                            util::format_elapsed_time(self.message.private_message.published),
+                            util::format_elapsed_time(updated)),
+                        None => util::format_elapsed_time(self.message.private_message.published),
+                    },
+                },
+
+                gtk::Box {
+                    set_hexpand: true,
                 },
 
                 gtk::Button {
-                    set_label: &self.message.creator.name,
-                    connect_clicked => PrivateMessageRowInput::OpenPerson,
+                    add_css_class: "flat",
+                    #[watch]
+                    set_label: if self.read { "Mark as unread" } else { "Mark as read" },
+                    set_visible: self.received,
+                    connect_clicked => PrivateMessageRowInput::ToggleRead,
                 },
 
-                gtk::Label {
-                    set_margin_start: 10,
-                    set_label: &util::format_elapsed_time(self.message.private_message.published)
-                }
-            },
+                gtk::Button {
+                    add_css_class: "flat",
+                    set_icon_name: "action-unavailable",
+                    set_tooltip_text: Some("Report"),
+                    set_visible: self.received,
+                    connect_clicked => PrivateMessageRowInput::ShowReportDialog,
+                },
 
-            gtk::Label {
-                #[watch]
-               set_markup: &markdown_to_pango_markup(self.message.private_message.content.clone()),
-               set_wrap: true,
-               set_halign: gtk::Align::Start,
-               set_use_markup: true,
-            },
+                gtk::Button {
+                    add_css_class: "flat",
+                    set_icon_name: "document-edit",
+                    set_tooltip_text: Some("Edit"),
+                    set_visible: !self.received,
+                    connect_clicked => PrivateMessageRowInput::OpenEditor,
+                },
 
-            gtk::Separator {}
+                gtk::Button {
+                    add_css_class: "flat",
+                    set_icon_name: "edit-delete",
+                    set_tooltip_text: Some("Delete"),
+                    set_visible: !self.received,
+                    connect_clicked => PrivateMessageRowInput::DeleteMessage,
+                },
+            },
         }
     }
 
-    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
-        let creator_image = WebImage::builder()
-            .launch(get_web_image_url(init.creator.avatar.clone()))
+    fn init_model(init: Self::Init, _index: &Self::Index, sender: FactorySender<Self>) -> Self {
+        let content = MarkdownView::builder()
+            .launch(init.private_message.content.clone())
+            .forward(sender.output_sender(), |msg| msg);
+        let editor_dialog = EditorDialog::builder()
+            .launch(EditorType::PrivateMessage)
+            .forward(sender.input_sender(), |msg| match msg {
+                EditorOutput::EditRequest(data, _) | EditorOutput::CreateRequest(data, _) => {
+                    PrivateMessageRowInput::EditMessage(data.body)
+                }
+            });
+        let report_dialog = ReportDialog::builder()
+            .launch((None, None, Some(init.private_message.id)))
             .detach();
+        let received = init.recipient.id.0 == settings::get_current_account().id;
         Self {
+            read: init.private_message.read,
             message: init,
-            creator_image,
+            content,
+            editor_dialog,
+            report_dialog,
+            received,
         }
     }
+
     fn init_widgets(
         &mut self,
         _index: &Self::Index,
@@ -85,7 +142,7 @@ impl FactoryComponent for PrivateMessageRow {
         _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
         sender: FactorySender<Self>,
     ) -> Self::Widgets {
-        let creator_image = self.creator_image.widget();
+        let content = self.content.widget();
         let widgets = view_output!();
         widgets
     }
@@ -96,8 +153,65 @@ impl FactoryComponent for PrivateMessageRow {
 
     fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
         match message {
-            PrivateMessageRowInput::OpenPerson => {
-                sender.output(crate::AppMsg::OpenPerson(self.message.creator.id))
+            PrivateMessageRowInput::ToggleRead => {
+                let read = !self.read;
+                let message_id = self.message.private_message.id;
+                std::thread::spawn(
+                    move || match api::private_message::mark_private_message_as_read(
+                        message_id, read,
+                    ) {
+                        Ok(_) => {
+                            sender.input(PrivateMessageRowInput::UpdateRead(read));
+                            sender.output(crate::AppMsg::FetchUnreadCount);
+                        }
+                        Err(err) => println!("{}", err),
+                    },
+                );
+            }
+            PrivateMessageRowInput::UpdateRead(read) => {
+                self.read = read;
+            }
+            PrivateMessageRowInput::OpenEditor => {
+                let data = EditorData {
+                    body: self.message.private_message.content.clone(),
+                    ..Default::default()
+                };
+                let sender = self.editor_dialog.sender();
+                sender.emit(DialogMsg::UpdateData(Box::new(data)));
+                sender.emit(DialogMsg::UpdateDraftTarget(None));
+                sender.emit(DialogMsg::UpdateType(EditorType::PrivateMessage, false));
+                sender.emit(DialogMsg::Show);
+            }
+            PrivateMessageRowInput::EditMessage(content) => {
+                let message_id = self.message.private_message.id;
+                std::thread::spawn(move || {
+                    match api::private_message::edit_private_message(content, message_id) {
+                        Ok(response) => sender.input(PrivateMessageRowInput::UpdateMessage(
+                            Box::new(response.private_message_view),
+                        )),
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            PrivateMessageRowInput::UpdateMessage(message) => {
+                self.content.emit(MarkdownViewInput::Update(
+                    message.private_message.content.clone(),
+                ));
+                self.message = *message;
+            }
+            PrivateMessageRowInput::DeleteMessage => {
+                let message_id = self.message.private_message.id;
+                std::thread::spawn(move || {
+                    match api::private_message::delete_private_message(message_id, true) {
+                        Ok(response) => sender.input(PrivateMessageRowInput::UpdateMessage(
+                            Box::new(response.private_message_view),
+                        )),
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            PrivateMessageRowInput::ShowReportDialog => {
+                self.report_dialog.emit(ReportDialogInput::Show);
             }
         }
     }
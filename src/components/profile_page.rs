@@ -8,6 +8,8 @@ use crate::dialogs::editor::DialogMsg;
 use crate::dialogs::editor::EditorDialog;
 use crate::dialogs::editor::EditorOutput;
 use crate::dialogs::editor::EditorType;
+use crate::drafts;
+use crate::drafts::DraftTarget;
 use crate::settings;
 use crate::util::format_elapsed_time;
 use crate::util::get_web_image_msg;
@@ -26,13 +28,15 @@ pub struct ProfilePage {
     editor_dialog: Controller<EditorDialog>,
     current_profile_page: i64,
     saved_only: bool,
+    blocked: bool,
 }
 
 #[derive(Debug)]
 pub enum ProfileInput {
     FetchPerson(Option<PersonId>),
     UpdatePerson(GetPersonDetailsResponse, bool),
-    BlockUser,
+    ToggleBlocked,
+    UpdateBlocked(bool),
     SendMessageRequest,
     SendMessage(String),
 }
@@ -94,10 +98,11 @@ impl SimpleComponent for ProfilePage {
                             set_text: &format!("{} posts, {} comments", model.info.person_view.counts.post_count, model.info.person_view.counts.comment_count),
                         },
                         gtk::Button {
-                            set_label: "Block",
+                            #[watch]
+                            set_label: if model.blocked { "Unblock" } else { "Block" },
                             #[watch]
                             set_visible: settings::get_current_account().jwt.is_some() && settings::get_current_account().id != model.info.person_view.person.id.0,
-                            connect_clicked => ProfileInput::BlockUser,
+                            connect_clicked => ProfileInput::ToggleBlocked,
                         },
                         gtk::Button {
                             set_label: "Send message",
@@ -177,6 +182,7 @@ impl SimpleComponent for ProfilePage {
             editor_dialog,
             current_profile_page: 1,
             saved_only,
+            blocked: false,
         };
         let avatar = model.avatar.widget();
         let posts = model.posts.widget();
@@ -219,11 +225,19 @@ impl SimpleComponent for ProfilePage {
                     self.moderates.guard().push_back(community);
                 }
             }
-            ProfileInput::SendMessageRequest => self.editor_dialog.sender().emit(DialogMsg::Show),
+            ProfileInput::SendMessageRequest => {
+                let target = DraftTarget::PrivateMessage(self.info.person_view.person.id);
+                let sender = self.editor_dialog.sender();
+                sender.emit(DialogMsg::UpdateDraftTarget(Some(target)));
+                sender.emit(DialogMsg::Show);
+            }
             ProfileInput::SendMessage(content) => {
                 let profile_id = self.info.person_view.person.id;
                 std::thread::spawn(move || {
-                    let _ = api::private_message::create_private_message(content, profile_id);
+                    match api::private_message::create_private_message(content, profile_id) {
+                        Ok(_) => drafts::remove_draft(&DraftTarget::PrivateMessage(profile_id)),
+                        Err(err) => println!("{}", err),
+                    }
                 });
             }
             ProfileInput::FetchPerson(person_id) => {
@@ -235,6 +249,25 @@ impl SimpleComponent for ProfilePage {
                 self.current_profile_page = page;
                 let person_id = person_id.unwrap_or(self.info.person_view.person.id);
                 let saved_only = self.saved_only;
+                if page == 1 {
+                    self.blocked = false;
+                }
+                if page == 1 && !saved_only && settings::get_current_account().jwt.is_some() {
+                    // the profile itself doesn't tell whether the user has been blocked
+                    let sender = sender.clone();
+                    std::thread::spawn(move || match api::site::fetch_site() {
+                        Ok(site) => {
+                            let blocked = site.my_user.is_some_and(|my_user| {
+                                my_user
+                                    .person_blocks
+                                    .iter()
+                                    .any(|block| block.target.id == person_id)
+                            });
+                            sender.input(ProfileInput::UpdateBlocked(blocked));
+                        }
+                        Err(err) => println!("{}", err),
+                    });
+                }
                 std::thread::spawn(move || {
                     match api::user::get_user(person_id, page, saved_only) {
                         Ok(person) => {
@@ -248,15 +281,17 @@ impl SimpleComponent for ProfilePage {
                     };
                 });
             }
-            ProfileInput::BlockUser => {
+            ProfileInput::ToggleBlocked => {
                 let person_id = self.info.person_view.person.id;
-                std::thread::spawn(move || match api::user::block_user(person_id, true) {
-                    Ok(_resp) => {}
+                let blocked = self.blocked;
+                std::thread::spawn(move || match api::user::block_user(person_id, !blocked) {
+                    Ok(resp) => sender.input(ProfileInput::UpdateBlocked(resp.blocked)),
                     Err(err) => {
                         println!("{}", err);
                     }
                 });
             }
+            ProfileInput::UpdateBlocked(blocked) => self.blocked = blocked,
         }
     }
 }
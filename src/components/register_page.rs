@@ -0,0 +1,423 @@
+use gtk::prelude::*;
+use lemmy_api_common::{
+    lemmy_db_schema::RegistrationMode,
+    person::{CaptchaResponse, LoginResponse, Register},
+    sensitive::Sensitive,
+    site::GetSiteResponse,
+};
+use relm4::{
+    gtk::{gdk, gio, glib},
+    prelude::*,
+};
+
+use crate::{api, settings, util::markdown_to_pango_markup};
+
+use super::login_page;
+
+struct Captcha {
+    uuid: String,
+    texture: Option<gdk::Texture>,
+    // kept alive while the audio is playing
+    audio: gtk::MediaFile,
+}
+
+pub struct RegisterPage {
+    instance_url: String,
+    loading: bool,
+    registration_mode: RegistrationMode,
+    application_question: Option<String>,
+    email_required: bool,
+    captcha_enabled: bool,
+    captcha: Option<Captcha>,
+    answer_buffer: gtk::TextBuffer,
+    registering: bool,
+    error: Option<String>,
+    // shown instead of the form once the account has been created but can't be used yet
+    outcome: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RegisterData {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub password_verify: String,
+    pub show_nsfw: bool,
+    pub captcha_answer: String,
+}
+
+#[derive(Debug)]
+pub enum RegisterPageInput {
+    Load,
+    DoneFetchSite(Box<GetSiteResponse>),
+    RefreshCaptcha,
+    DoneFetchCaptcha(Option<CaptchaResponse>),
+    PlayCaptcha,
+    Register(RegisterData),
+    Registered(LoginResponse),
+    ShowError(String),
+    Cancel,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for RegisterPage {
+    type Init = ();
+    type Input = RegisterPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hexpand: true,
+            set_vexpand: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 12,
+                set_margin_all: 20,
+                set_valign: gtk::Align::Center,
+
+                gtk::Label {
+                    set_text: "Create account",
+                    add_css_class: "font-bold",
+                },
+                gtk::Label {
+                    add_css_class: "dim-label",
+                    #[watch]
+                    set_text: &model.instance_url,
+                },
+
+                gtk::Spinner {
+                    set_spinning: true,
+                    set_height_request: 40,
+                    #[watch]
+                    set_visible: model.loading,
+                },
+
+                gtk::Label {
+                    set_wrap: true,
+                    set_text: "This instance doesn't accept new registrations.",
+                    #[watch]
+                    set_visible: !model.loading && model.registration_mode == RegistrationMode::Closed,
+                },
+
+                gtk::Label {
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.outcome.is_some(),
+                    #[watch]
+                    set_text: model.outcome.as_deref().unwrap_or_default(),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 12,
+                    #[watch]
+                    set_visible: !model.loading
+                        && model.outcome.is_none()
+                        && model.registration_mode != RegistrationMode::Closed,
+
+                    #[name(username)]
+                    gtk::Entry {
+                        set_placeholder_text: Some("Username"),
+                    },
+                    #[name(email)]
+                    gtk::Entry {
+                        #[watch]
+                        set_placeholder_text: Some(if model.email_required { "E-Mail" } else { "E-Mail (Optional)" }),
+                    },
+                    #[name(password)]
+                    gtk::PasswordEntry {
+                        set_placeholder_text: Some("Password"),
+                        set_show_peek_icon: true,
+                    },
+                    #[name(password_verify)]
+                    gtk::PasswordEntry {
+                        set_placeholder_text: Some("Confirm password"),
+                        set_show_peek_icon: true,
+                    },
+                    #[name(show_nsfw)]
+                    gtk::CheckButton {
+                        set_label: Some("Show NSFW content"),
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 5,
+                        #[watch]
+                        set_visible: model.registration_mode == RegistrationMode::RequireApplication,
+
+                        gtk::Label {
+                            set_text: "New accounts need to be approved by the admins of this instance.",
+                            set_wrap: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::Label {
+                            set_wrap: true,
+                            set_halign: gtk::Align::Start,
+                            set_use_markup: true,
+                            #[watch]
+                            set_markup: &markdown_to_pango_markup(model.application_question.clone().unwrap_or_default()),
+                        },
+                        gtk::ScrolledWindow {
+                            set_min_content_height: 80,
+                            set_max_content_height: 200,
+                            set_propagate_natural_height: true,
+
+                            gtk::TextView {
+                                set_buffer: Some(&model.answer_buffer),
+                                set_wrap_mode: gtk::WrapMode::WordChar,
+                                set_top_margin: 5,
+                                set_bottom_margin: 5,
+                                set_left_margin: 5,
+                                set_right_margin: 5,
+                                add_css_class: "card",
+                            },
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 5,
+                        #[watch]
+                        set_visible: model.captcha_enabled,
+
+                        gtk::Picture {
+                            set_height_request: 80,
+                            #[watch]
+                            set_paintable: model.captcha.as_ref().and_then(|captcha| captcha.texture.as_ref()),
+                        },
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_spacing: 10,
+                            set_halign: gtk::Align::Center,
+
+                            gtk::Button {
+                                set_icon_name: "audio-speakers-symbolic",
+                                set_tooltip_text: Some("Play the captcha"),
+                                #[watch]
+                                set_sensitive: model.captcha.is_some(),
+                                connect_clicked => RegisterPageInput::PlayCaptcha,
+                            },
+                            gtk::Button {
+                                set_icon_name: "view-refresh-symbolic",
+                                set_tooltip_text: Some("New captcha"),
+                                connect_clicked => RegisterPageInput::RefreshCaptcha,
+                            },
+                        },
+                        #[name(captcha_answer)]
+                        gtk::Entry {
+                            set_placeholder_text: Some("Captcha"),
+                        },
+                    },
+                },
+
+                gtk::Label {
+                    add_css_class: "error",
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.error.is_some(),
+                    #[watch]
+                    set_text: model.error.as_deref().unwrap_or_default(),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_halign: gtk::Align::End,
+                    gtk::Button {
+                        #[watch]
+                        set_label: if model.outcome.is_some() { "Back to login" } else { "Cancel" },
+                        connect_clicked => RegisterPageInput::Cancel,
+                        set_margin_end: 10,
+                    },
+                    gtk::Button {
+                        set_label: "Sign up",
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_visible: !model.loading
+                            && model.outcome.is_none()
+                            && model.registration_mode != RegistrationMode::Closed,
+                        #[watch]
+                        set_sensitive: !model.registering,
+                        connect_clicked[sender, username, email, password, password_verify, show_nsfw, captcha_answer] => move |_| {
+                            sender.input(RegisterPageInput::Register(RegisterData {
+                                username: username.text().to_string(),
+                                email: email.text().to_string(),
+                                password: password.text().to_string(),
+                                password_verify: password_verify.text().to_string(),
+                                show_nsfw: show_nsfw.is_active(),
+                                captcha_answer: captcha_answer.text().to_string(),
+                            }));
+                            captcha_answer.set_text("");
+                        },
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            instance_url: String::new(),
+            loading: false,
+            registration_mode: RegistrationMode::Open,
+            application_question: None,
+            email_required: false,
+            captcha_enabled: false,
+            captcha: None,
+            answer_buffer: gtk::TextBuffer::new(None),
+            registering: false,
+            error: None,
+            outcome: None,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            RegisterPageInput::Load => {
+                self.instance_url = settings::get_current_account().instance_url;
+                self.loading = true;
+                self.error = None;
+                self.outcome = None;
+                self.captcha = None;
+                self.answer_buffer.set_text("");
+                std::thread::spawn(move || {
+                    let message = match api::site::fetch_site() {
+                        Ok(site) => RegisterPageInput::DoneFetchSite(Box::new(site)),
+                        Err(err) => RegisterPageInput::ShowError(err.to_string()),
+                    };
+                    sender.input(message);
+                });
+            }
+            RegisterPageInput::DoneFetchSite(site) => {
+                self.loading = false;
+                let local_site = site.site_view.local_site;
+                self.registration_mode = local_site.registration_mode;
+                self.application_question = local_site.application_question;
+                self.email_required = local_site.require_email_verification;
+                self.captcha_enabled = local_site.captcha_enabled;
+                if self.captcha_enabled && self.registration_mode != RegistrationMode::Closed {
+                    sender.input(RegisterPageInput::RefreshCaptcha);
+                }
+            }
+            RegisterPageInput::RefreshCaptcha => {
+                self.captcha = None;
+                std::thread::spawn(move || {
+                    let message = match api::auth::get_captcha() {
+                        Ok(captcha) => RegisterPageInput::DoneFetchCaptcha(captcha.ok),
+                        Err(err) => RegisterPageInput::ShowError(err.to_string()),
+                    };
+                    sender.input(message);
+                });
+            }
+            RegisterPageInput::DoneFetchCaptcha(captcha) => {
+                // the server only doesn't return one if captchas have been disabled in the meantime
+                self.captcha_enabled = captcha.is_some();
+                self.captcha = captcha.map(|captcha| {
+                    let png = glib::Bytes::from_owned(glib::base64_decode(&captcha.png));
+                    let wav = glib::Bytes::from_owned(glib::base64_decode(&captcha.wav));
+                    Captcha {
+                        uuid: captcha.uuid,
+                        texture: gdk::Texture::from_bytes(&png).ok(),
+                        audio: gtk::MediaFile::for_input_stream(
+                            &gio::MemoryInputStream::from_bytes(&wav),
+                        ),
+                    }
+                });
+            }
+            RegisterPageInput::PlayCaptcha => {
+                if let Some(captcha) = &self.captcha {
+                    captcha.audio.seek(0);
+                    captcha.audio.play();
+                }
+            }
+            RegisterPageInput::Register(data) => {
+                if data.username.is_empty() || data.password.is_empty() {
+                    self.error = Some("The username and password must not be empty".to_string());
+                    return;
+                }
+                if data.password != data.password_verify {
+                    self.error = Some("The passwords don't match".to_string());
+                    return;
+                }
+                if self.email_required && data.email.is_empty() {
+                    self.error = Some("The instance requires an email address".to_string());
+                    return;
+                }
+                let answer = self
+                    .answer_buffer
+                    .text(
+                        &self.answer_buffer.start_iter(),
+                        &self.answer_buffer.end_iter(),
+                        false,
+                    )
+                    .trim()
+                    .to_string();
+                if self.registration_mode == RegistrationMode::RequireApplication
+                    && answer.is_empty()
+                {
+                    self.error = Some("Please answer the application question".to_string());
+                    return;
+                }
+                let params = Register {
+                    username: data.username,
+                    password: Sensitive::new(data.password),
+                    password_verify: Sensitive::new(data.password_verify),
+                    show_nsfw: data.show_nsfw,
+                    email: Some(data.email)
+                        .filter(|email| !email.is_empty())
+                        .map(Sensitive::new),
+                    captcha_uuid: self.captcha.as_ref().map(|captcha| captcha.uuid.clone()),
+                    captcha_answer: Some(data.captcha_answer).filter(|_| self.captcha_enabled),
+                    honeypot: None,
+                    answer: Some(answer).filter(|answer| !answer.is_empty()),
+                };
+                self.registering = true;
+                self.error = None;
+                std::thread::spawn(move || {
+                    let message = match api::auth::register(params) {
+                        Ok(response) => RegisterPageInput::Registered(response),
+                        Err(err) => RegisterPageInput::ShowError(err.to_string()),
+                    };
+                    sender.input(message);
+                });
+            }
+            RegisterPageInput::Registered(response) => {
+                self.registering = false;
+                if let Some(token) = response.jwt {
+                    std::thread::spawn(move || {
+                        sender.output_sender().emit(login_page::finish_login(token));
+                    });
+                    return;
+                }
+                let mut outcome = vec!["Your account has been created."];
+                if response.verify_email_sent {
+                    outcome.push("A verification link has been sent to your email address, open it to activate your account.");
+                }
+                if response.registration_created {
+                    outcome.push("Your application is pending, you can log in once it has been approved by the admins.");
+                }
+                self.outcome = Some(outcome.join(" "));
+            }
+            RegisterPageInput::ShowError(err) => {
+                self.loading = false;
+                self.registering = false;
+                self.error = Some(err);
+                // a captcha can only be answered once
+                if self.captcha.is_some() {
+                    sender.input(RegisterPageInput::RefreshCaptcha);
+                }
+            }
+            RegisterPageInput::Cancel => {
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::UpdateState(crate::AppState::Login));
+            }
+        }
+    }
+}
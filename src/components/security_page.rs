@@ -0,0 +1,375 @@
+use gtk::prelude::*;
+use qrcode::{Color, QrCode};
+use relm4::{
+    gtk::{gdk, glib},
+    prelude::*,
+};
+
+use crate::{api, settings};
+
+// pixels per module of the qr code, with a border of four modules around it
+const QR_MODULE_SIZE: usize = 6;
+const QR_BORDER: usize = 4;
+
+pub struct SecurityPage {
+    loading: bool,
+    totp_enabled: bool,
+    // only set while a new secret is being set up
+    totp_secret_url: Option<String>,
+    qr_code: Option<gdk::Texture>,
+    busy: bool,
+    password_error: Option<String>,
+    password_info: Option<String>,
+    totp_error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum SecurityPageInput {
+    Load,
+    DoneLoad(bool),
+    ChangePassword(String, String, String),
+    PasswordChanged(Result<(), String>),
+    GenerateTotpSecret,
+    DoneGenerateTotpSecret(Result<String, String>),
+    CancelTotpSetup,
+    UpdateTotp(String),
+    TotpUpdated(Result<bool, String>),
+}
+
+fn qr_code_texture(data: &str) -> Option<gdk::Texture> {
+    let code = QrCode::new(data).ok()?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = (modules + 2 * QR_BORDER) * QR_MODULE_SIZE;
+    let mut pixels = vec![255u8; size * size * 3];
+    for (index, color) in colors.iter().enumerate() {
+        if *color == Color::Light {
+            continue;
+        }
+        let (x, y) = (index % modules + QR_BORDER, index / modules + QR_BORDER);
+        for row in y * QR_MODULE_SIZE..(y + 1) * QR_MODULE_SIZE {
+            let start = (row * size + x * QR_MODULE_SIZE) * 3;
+            pixels[start..start + QR_MODULE_SIZE * 3].fill(0);
+        }
+    }
+    let texture = gdk::MemoryTexture::new(
+        size as i32,
+        size as i32,
+        gdk::MemoryFormat::R8g8b8,
+        &glib::Bytes::from_owned(pixels),
+        size * 3,
+    );
+    Some(texture.upcast())
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SecurityPage {
+    type Init = ();
+    type Input = SecurityPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hexpand: true,
+            set_vexpand: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 12,
+                set_margin_all: 20,
+                set_halign: gtk::Align::Center,
+                set_width_request: 400,
+
+                gtk::Label {
+                    set_text: "Account security",
+                    add_css_class: "font-very-bold",
+                },
+
+                gtk::Label {
+                    set_text: "Change password",
+                    add_css_class: "font-bold",
+                    set_halign: gtk::Align::Start,
+                    set_margin_top: 10,
+                },
+                #[name(old_password)]
+                gtk::PasswordEntry {
+                    set_placeholder_text: Some("Current password"),
+                    set_show_peek_icon: true,
+                },
+                #[name(new_password)]
+                gtk::PasswordEntry {
+                    set_placeholder_text: Some("New password"),
+                    set_show_peek_icon: true,
+                },
+                #[name(new_password_verify)]
+                gtk::PasswordEntry {
+                    set_placeholder_text: Some("Confirm new password"),
+                    set_show_peek_icon: true,
+                },
+                gtk::Label {
+                    add_css_class: "error",
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.password_error.is_some(),
+                    #[watch]
+                    set_text: model.password_error.as_deref().unwrap_or_default(),
+                },
+                gtk::Label {
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.password_info.is_some(),
+                    #[watch]
+                    set_text: model.password_info.as_deref().unwrap_or_default(),
+                },
+                gtk::Button {
+                    set_label: "Change password",
+                    set_halign: gtk::Align::End,
+                    #[watch]
+                    set_sensitive: !model.busy,
+                    connect_clicked[sender, old_password, new_password, new_password_verify] => move |_| {
+                        sender.input(SecurityPageInput::ChangePassword(
+                            old_password.text().to_string(),
+                            new_password.text().to_string(),
+                            new_password_verify.text().to_string(),
+                        ));
+                        old_password.set_text("");
+                        new_password.set_text("");
+                        new_password_verify.set_text("");
+                    },
+                },
+
+                gtk::Label {
+                    set_text: "Two-factor authentication",
+                    add_css_class: "font-bold",
+                    set_halign: gtk::Align::Start,
+                    set_margin_top: 20,
+                },
+                gtk::Spinner {
+                    set_spinning: true,
+                    #[watch]
+                    set_visible: model.loading,
+                },
+                gtk::Label {
+                    set_wrap: true,
+                    set_halign: gtk::Align::Start,
+                    #[watch]
+                    set_visible: !model.loading,
+                    #[watch]
+                    set_text: if model.totp_enabled {
+                        "Two-factor authentication is enabled. Enter a code of your authenticator app to disable it."
+                    } else if model.totp_secret_url.is_some() {
+                        "Scan the code with your authenticator app or add the link to it, then enter the code it generates to finish the setup."
+                    } else {
+                        "Two-factor authentication is disabled. When enabled, logging in also requires a code of an authenticator app."
+                    },
+                },
+                gtk::Picture {
+                    set_can_shrink: false,
+                    set_halign: gtk::Align::Center,
+                    #[watch]
+                    set_visible: model.qr_code.is_some(),
+                    #[watch]
+                    set_paintable: model.qr_code.as_ref(),
+                },
+                gtk::Label {
+                    set_selectable: true,
+                    set_wrap: true,
+                    set_wrap_mode: gtk::pango::WrapMode::Char,
+                    add_css_class: "dim-label",
+                    #[watch]
+                    set_visible: model.totp_secret_url.is_some(),
+                    #[watch]
+                    set_text: model.totp_secret_url.as_deref().unwrap_or_default(),
+                },
+                #[name(totp_token)]
+                gtk::Entry {
+                    set_placeholder_text: Some("Two-factor code"),
+                    set_input_purpose: gtk::InputPurpose::Digits,
+                    #[watch]
+                    set_visible: !model.loading && (model.totp_enabled || model.totp_secret_url.is_some()),
+                },
+                gtk::Label {
+                    add_css_class: "error",
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.totp_error.is_some(),
+                    #[watch]
+                    set_text: model.totp_error.as_deref().unwrap_or_default(),
+                },
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 10,
+                    set_halign: gtk::Align::End,
+                    #[watch]
+                    set_visible: !model.loading,
+
+                    gtk::Button {
+                        set_label: "Cancel",
+                        #[watch]
+                        set_visible: model.totp_secret_url.is_some(),
+                        connect_clicked => SecurityPageInput::CancelTotpSetup,
+                    },
+                    gtk::Button {
+                        set_label: "Set up",
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_visible: !model.totp_enabled && model.totp_secret_url.is_none(),
+                        #[watch]
+                        set_sensitive: !model.busy,
+                        connect_clicked => SecurityPageInput::GenerateTotpSecret,
+                    },
+                    gtk::Button {
+                        #[watch]
+                        set_label: if model.totp_enabled { "Disable" } else { "Enable" },
+                        #[watch]
+                        set_visible: model.totp_enabled || model.totp_secret_url.is_some(),
+                        #[watch]
+                        set_sensitive: !model.busy,
+                        connect_clicked[sender, totp_token] => move |_| {
+                            sender.input(SecurityPageInput::UpdateTotp(totp_token.text().to_string()));
+                            totp_token.set_text("");
+                        },
+                    },
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            loading: false,
+            totp_enabled: false,
+            totp_secret_url: None,
+            qr_code: None,
+            busy: false,
+            password_error: None,
+            password_info: None,
+            totp_error: None,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            SecurityPageInput::Load => {
+                self.loading = true;
+                self.totp_secret_url = None;
+                self.qr_code = None;
+                self.password_error = None;
+                self.password_info = None;
+                self.totp_error = None;
+                std::thread::spawn(move || match api::site::fetch_site() {
+                    Ok(site) => {
+                        let enabled = site
+                            .my_user
+                            .map(|user| user.local_user_view.local_user.totp_2fa_enabled)
+                            .unwrap_or_default();
+                        sender.input(SecurityPageInput::DoneLoad(enabled));
+                    }
+                    Err(err) => sender
+                        .output_sender()
+                        .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                });
+            }
+            SecurityPageInput::DoneLoad(totp_enabled) => {
+                self.loading = false;
+                self.totp_enabled = totp_enabled;
+            }
+            SecurityPageInput::ChangePassword(old_password, new_password, new_password_verify) => {
+                self.password_info = None;
+                if old_password.is_empty() || new_password.is_empty() {
+                    self.password_error = Some("The passwords must not be empty".to_string());
+                    return;
+                }
+                if new_password != new_password_verify {
+                    self.password_error = Some("The new passwords don't match".to_string());
+                    return;
+                }
+                self.password_error = None;
+                self.busy = true;
+                std::thread::spawn(move || {
+                    let result =
+                        api::auth::change_password(old_password, new_password, new_password_verify)
+                            .map(|login| {
+                                // the old token is invalidated together with all other sessions
+                                if let Some(token) = login.jwt {
+                                    let mut account = settings::get_current_account();
+                                    account.jwt = Some(token);
+                                    settings::update_current_account(account);
+                                }
+                            })
+                            .map_err(|err| err.to_string());
+                    sender.input(SecurityPageInput::PasswordChanged(result));
+                });
+            }
+            SecurityPageInput::PasswordChanged(result) => {
+                self.busy = false;
+                match result {
+                    Ok(_) => {
+                        self.password_info = Some(
+                            "Your password has been changed, other devices have been logged out."
+                                .to_string(),
+                        )
+                    }
+                    Err(err) => self.password_error = Some(err),
+                }
+            }
+            SecurityPageInput::GenerateTotpSecret => {
+                self.busy = true;
+                self.totp_error = None;
+                std::thread::spawn(move || {
+                    let result = api::auth::generate_totp_secret()
+                        .map(|response| response.totp_secret_url.into_inner())
+                        .map_err(|err| err.to_string());
+                    sender.input(SecurityPageInput::DoneGenerateTotpSecret(result));
+                });
+            }
+            SecurityPageInput::DoneGenerateTotpSecret(result) => {
+                self.busy = false;
+                match result {
+                    Ok(url) => {
+                        self.qr_code = qr_code_texture(&url);
+                        self.totp_secret_url = Some(url);
+                    }
+                    Err(err) => self.totp_error = Some(err),
+                }
+            }
+            SecurityPageInput::CancelTotpSetup => {
+                self.totp_secret_url = None;
+                self.qr_code = None;
+                self.totp_error = None;
+            }
+            SecurityPageInput::UpdateTotp(totp_token) => {
+                if totp_token.is_empty() {
+                    self.totp_error = Some("Enter the code of your authenticator app".to_string());
+                    return;
+                }
+                self.busy = true;
+                self.totp_error = None;
+                let enabled = !self.totp_enabled;
+                std::thread::spawn(move || {
+                    let result = api::auth::update_totp(totp_token, enabled)
+                        .map(|response| response.enabled)
+                        .map_err(|err| err.to_string());
+                    sender.input(SecurityPageInput::TotpUpdated(result));
+                });
+            }
+            SecurityPageInput::TotpUpdated(result) => {
+                self.busy = false;
+                match result {
+                    Ok(enabled) => {
+                        self.totp_enabled = enabled;
+                        self.totp_secret_url = None;
+                        self.qr_code = None;
+                    }
+                    Err(err) => self.totp_error = Some(err),
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,39 @@
 use lemmy_api_common::lemmy_db_schema::SortType;
 use relm4::prelude::*;
 
+// the sort types in the order they're shown in the dropdown
+pub const SORT_TYPES: [SortType; 13] = [
+    SortType::Hot,
+    SortType::New,
+    SortType::Active,
+    SortType::Old,
+    SortType::TopAll,
+    SortType::TopDay,
+    SortType::TopWeek,
+    SortType::TopMonth,
+    SortType::TopYear,
+    SortType::MostComments,
+    SortType::NewComments,
+    SortType::TopHour,
+    SortType::TopSixHour,
+];
+
+pub const SORT_LABELS: &[&str] = &[
+    "Hot",
+    "New",
+    "Active",
+    "Old",
+    "Top All",
+    "Top Today",
+    "Top Week",
+    "Top Month",
+    "Top Year",
+    "Most comments",
+    "New comments",
+    "Top current hour",
+    "Top 6 hours",
+];
+
 pub struct SortDropdown {}
 
 #[derive(Debug)]
@@ -11,54 +44,32 @@ pub enum SortDropdownOutput {
 pub struct Widgets {}
 
 impl SimpleComponent for SortDropdown {
-    type Init = ();
+    // the sort type that's selected initially
+    type Init = SortType;
     type Input = ();
     type Output = SortDropdownOutput;
     type Root = gtk::DropDown;
     type Widgets = Widgets;
 
     fn init_root() -> Self::Root {
-        gtk::DropDown::from_strings(&[
-            "Hot",
-            "New",
-            "Active",
-            "Old",
-            "Top All",
-            "Top Today",
-            "Top Week",
-            "Top Month",
-            "Top Year",
-            "Most comments",
-            "New comments",
-            "Top current hour",
-            "Top 6 hours",
-        ])
+        gtk::DropDown::from_strings(SORT_LABELS)
     }
 
     fn init(
-        _init: Self::Init,
+        init: Self::Init,
         root: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
         let model = Self {};
         let dropdown = root.clone();
+        if let Some(index) = SORT_TYPES.iter().position(|sort_type| *sort_type == init) {
+            dropdown.set_selected(index as u32);
+        }
         dropdown.connect_selected_item_notify(move |dropdown| {
-            let sort_type = match dropdown.selected() {
-                0 => SortType::Hot,
-                1 => SortType::New,
-                2 => SortType::Active,
-                3 => SortType::Old,
-                4 => SortType::TopAll,
-                5 => SortType::TopDay,
-                6 => SortType::TopWeek,
-                7 => SortType::TopMonth,
-                8 => SortType::TopYear,
-                9 => SortType::MostComments,
-                10 => SortType::NewComments,
-                11 => SortType::TopHour,
-                12 => SortType::TopSixHour,
-                _ => SortType::Active,
-            };
+            let sort_type = SORT_TYPES
+                .get(dropdown.selected() as usize)
+                .copied()
+                .unwrap_or(SortType::Active);
             sender
                 .output_sender()
                 .emit(SortDropdownOutput::New(sort_type));
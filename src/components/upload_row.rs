@@ -0,0 +1,114 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+use relm4_components::web_image::WebImage;
+
+use crate::{uploads::Upload, util};
+
+use super::uploads_page::UploadsPageInput;
+
+pub struct UploadRow {
+    upload: Upload,
+    image: Controller<WebImage>,
+}
+
+#[derive(Debug)]
+pub enum UploadRowInput {
+    CopyLink,
+    Delete,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for UploadRow {
+    type Init = Upload;
+    type Input = UploadRowInput;
+    type Output = UploadsPageInput;
+    type ParentInput = UploadsPageInput;
+    type ParentWidget = gtk::Box;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 10,
+
+                #[local_ref]
+                image -> gtk::Box {
+                    set_size_request: (100, 100),
+                    set_hexpand: false,
+                },
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_valign: gtk::Align::Center,
+                    set_hexpand: true,
+                    set_spacing: 5,
+
+                    gtk::Label {
+                        set_label: &self.upload.url,
+                        set_halign: gtk::Align::Start,
+                        set_selectable: true,
+                        set_ellipsize: gtk::pango::EllipsizeMode::Middle,
+                    },
+                    gtk::Label {
+                        set_label: &util::format_elapsed_time(self.upload.uploaded),
+                        set_halign: gtk::Align::Start,
+                    },
+                },
+                gtk::Button {
+                    set_label: "Copy link",
+                    set_valign: gtk::Align::Center,
+                    connect_clicked => UploadRowInput::CopyLink,
+                },
+                gtk::Button {
+                    set_icon_name: "edit-delete",
+                    set_valign: gtk::Align::Center,
+                    set_tooltip_text: Some("Delete from the server"),
+                    connect_clicked => UploadRowInput::Delete,
+                },
+            },
+
+            gtk::Separator {
+                set_margin_all: 10,
+            }
+        }
+    }
+
+    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
+        let image = WebImage::builder().launch(init.url.clone()).detach();
+        Self {
+            upload: init,
+            image,
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: &Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let image = self.image.widget();
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn forward_to_parent(output: Self::Output) -> Option<Self::ParentInput> {
+        Some(output)
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        match message {
+            UploadRowInput::CopyLink => {
+                if let Some(display) = gtk::gdk::Display::default() {
+                    display.clipboard().set_text(&self.upload.url);
+                }
+            }
+            UploadRowInput::Delete => sender
+                .output_sender()
+                .emit(UploadsPageInput::Delete(self.upload.clone())),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use gtk::prelude::*;
+use relm4::{factory::FactoryVecDeque, prelude::*};
+
+use crate::{
+    api,
+    uploads::{self, Upload},
+};
+
+use super::upload_row::UploadRow;
+
+pub struct UploadsPage {
+    uploads: FactoryVecDeque<UploadRow>,
+}
+
+#[derive(Debug)]
+pub enum UploadsPageInput {
+    Update,
+    Delete(Upload),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UploadsPage {
+    type Init = ();
+    type Input = UploadsPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Label {
+                set_label: "Uploaded images",
+                add_css_class: "font-very-bold",
+                set_margin_top: 10,
+            },
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_vexpand: true,
+                    set_margin_all: 10,
+
+                    gtk::Label {
+                        set_label: "No images have been uploaded from this device yet",
+                        #[watch]
+                        set_visible: model.uploads.is_empty(),
+                    },
+
+                    #[local_ref]
+                    uploads_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let uploads = FactoryVecDeque::new(gtk::Box::builder().build(), sender.input_sender());
+        let model = Self { uploads };
+        let uploads_box = model.uploads.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            UploadsPageInput::Update => {
+                self.uploads.guard().clear();
+                for upload in uploads::get_uploads() {
+                    self.uploads.guard().push_back(upload);
+                }
+            }
+            UploadsPageInput::Delete(upload) => {
+                std::thread::spawn(move || {
+                    match api::image::delete_image(&upload.file, &upload.delete_token) {
+                        Ok(_) => {
+                            uploads::remove_upload(&upload.url);
+                            sender.input(UploadsPageInput::Update);
+                        }
+                        Err(err) => sender
+                            .output_sender()
+                            .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,677 @@
+use std::collections::HashSet;
+
+use gtk::{prelude::*, ResponseType};
+use lemmy_api_common::{
+    lemmy_db_schema::{newtypes::LanguageId, source::language::Language, ListingType},
+    person::SaveUserSettings,
+    sensitive::Sensitive,
+    site::GetSiteResponse,
+};
+use relm4::prelude::*;
+use relm4_components::web_image::{WebImage, WebImageMsg};
+
+use crate::{
+    api,
+    dialogs::editor::{DialogMsg, EditorData, EditorDialog, EditorOutput, EditorType},
+    markdown,
+};
+
+use super::sort_dropdown::{SORT_LABELS, SORT_TYPES};
+
+const LISTING_TYPES: [ListingType; 3] = [
+    ListingType::All,
+    ListingType::Local,
+    ListingType::Subscribed,
+];
+
+// the language of the interface isn't used by the app itself, only by the web ui
+const BROWSER_LANGUAGE: &str = "browser";
+
+#[derive(Debug, Clone, Copy)]
+pub enum ProfileImage {
+    Avatar,
+    Banner,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UserFlag {
+    ShowNsfw,
+    ShowBotAccounts,
+    ShowReadPosts,
+    BotAccount,
+}
+
+pub struct UserSettingsPage {
+    loading: bool,
+    saving: bool,
+    display_name_buffer: gtk::EntryBuffer,
+    email_buffer: gtk::EntryBuffer,
+    matrix_user_id_buffer: gtk::EntryBuffer,
+    bio: String,
+    bio_preview: gtk::Box,
+    avatar: Option<String>,
+    banner: Option<String>,
+    avatar_image: Controller<WebImage>,
+    banner_image: Controller<WebImage>,
+    uploading: Option<ProfileImage>,
+    show_nsfw: bool,
+    show_bot_accounts: bool,
+    show_read_posts: bool,
+    bot_account: bool,
+    sort_index: u32,
+    listing_index: u32,
+    interface_languages: Vec<String>,
+    interface_language_list: gtk::StringList,
+    interface_language_index: u32,
+    languages_box: gtk::ListBox,
+    discussion_languages: HashSet<LanguageId>,
+    editor_dialog: Controller<EditorDialog>,
+    error: Option<String>,
+    info: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum UserSettingsPageInput {
+    Load,
+    DoneLoad(Box<GetSiteResponse>),
+    EditBio,
+    UpdateBio(String),
+    ChooseImage(ProfileImage),
+    UploadImage(ProfileImage, std::path::PathBuf),
+    DoneUpload(ProfileImage, Result<String, String>),
+    RemoveImage(ProfileImage),
+    SetFlag(UserFlag, bool),
+    SelectSort(u32),
+    SelectListing(u32),
+    SelectInterfaceLanguage(u32),
+    ToggleLanguage(LanguageId, bool),
+    Save,
+    DoneSave(Result<(), String>),
+}
+
+impl UserSettingsPage {
+    fn update_image(&mut self, kind: ProfileImage, url: Option<String>) {
+        let image = match kind {
+            ProfileImage::Avatar => &self.avatar_image,
+            ProfileImage::Banner => &self.banner_image,
+        };
+        image.emit(match &url {
+            Some(url) => WebImageMsg::LoadImage(url.clone()),
+            None => WebImageMsg::Unload,
+        });
+        match kind {
+            ProfileImage::Avatar => self.avatar = url,
+            ProfileImage::Banner => self.banner = url,
+        }
+    }
+
+    fn fill_languages(&self, languages: Vec<Language>, sender: &ComponentSender<Self>) {
+        while let Some(row) = self.languages_box.first_child() {
+            self.languages_box.remove(&row);
+        }
+        for language in languages {
+            let check = gtk::CheckButton::with_label(&language.name);
+            check.set_active(self.discussion_languages.contains(&language.id));
+            let sender = sender.clone();
+            check.connect_toggled(move |check| {
+                sender.input(UserSettingsPageInput::ToggleLanguage(
+                    language.id,
+                    check.is_active(),
+                ));
+            });
+            self.languages_box.append(&check);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UserSettingsPage {
+    type Init = ();
+    type Input = UserSettingsPageInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_hexpand: true,
+            set_vexpand: true,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 12,
+                set_margin_all: 20,
+                set_halign: gtk::Align::Center,
+                set_width_request: 500,
+
+                gtk::Label {
+                    set_text: "Account settings",
+                    add_css_class: "font-very-bold",
+                },
+                gtk::Spinner {
+                    set_spinning: true,
+                    #[watch]
+                    set_visible: model.loading,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 12,
+                    #[watch]
+                    set_visible: !model.loading,
+
+                    gtk::Label {
+                        set_text: "Profile",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                    },
+                    gtk::Entry {
+                        set_placeholder_text: Some("Display name"),
+                        set_buffer: &model.display_name_buffer,
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_text: "Bio",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::Button {
+                            set_label: "Edit",
+                            connect_clicked => UserSettingsPageInput::EditBio,
+                        },
+                    },
+                    #[local_ref]
+                    bio_preview -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 10,
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        #[local_ref]
+                        avatar_image -> gtk::Box {
+                            set_size_request: (80, 80),
+                            #[watch]
+                            set_visible: model.avatar.is_some(),
+                        },
+                        gtk::Label {
+                            set_text: "Avatar",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::Button {
+                            set_label: "Remove",
+                            #[watch]
+                            set_visible: model.avatar.is_some(),
+                            connect_clicked => UserSettingsPageInput::RemoveImage(ProfileImage::Avatar),
+                        },
+                        gtk::Button {
+                            set_label: "Upload",
+                            #[watch]
+                            set_sensitive: model.uploading.is_none(),
+                            connect_clicked => UserSettingsPageInput::ChooseImage(ProfileImage::Avatar),
+                        },
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        #[local_ref]
+                        banner_image -> gtk::Box {
+                            set_size_request: (240, 80),
+                            #[watch]
+                            set_visible: model.banner.is_some(),
+                        },
+                        gtk::Label {
+                            set_text: "Banner",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::Button {
+                            set_label: "Remove",
+                            #[watch]
+                            set_visible: model.banner.is_some(),
+                            connect_clicked => UserSettingsPageInput::RemoveImage(ProfileImage::Banner),
+                        },
+                        gtk::Button {
+                            set_label: "Upload",
+                            #[watch]
+                            set_sensitive: model.uploading.is_none(),
+                            connect_clicked => UserSettingsPageInput::ChooseImage(ProfileImage::Banner),
+                        },
+                    },
+                    gtk::Spinner {
+                        #[watch]
+                        set_spinning: model.uploading.is_some(),
+                        #[watch]
+                        set_visible: model.uploading.is_some(),
+                    },
+                    gtk::CheckButton {
+                        set_label: Some("Bot account"),
+                        #[watch]
+                        set_active: model.bot_account,
+                        connect_toggled[sender] => move |check| {
+                            sender.input(UserSettingsPageInput::SetFlag(UserFlag::BotAccount, check.is_active()));
+                        },
+                    },
+
+                    gtk::Label {
+                        set_text: "Contact",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    gtk::Entry {
+                        set_placeholder_text: Some("E-Mail"),
+                        set_input_purpose: gtk::InputPurpose::Email,
+                        set_buffer: &model.email_buffer,
+                    },
+                    gtk::Entry {
+                        set_placeholder_text: Some("Matrix user id, e.g. @user:matrix.org"),
+                        set_buffer: &model.matrix_user_id_buffer,
+                    },
+
+                    gtk::Label {
+                        set_text: "Content",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    gtk::CheckButton {
+                        set_label: Some("Show NSFW content"),
+                        #[watch]
+                        set_active: model.show_nsfw,
+                        connect_toggled[sender] => move |check| {
+                            sender.input(UserSettingsPageInput::SetFlag(UserFlag::ShowNsfw, check.is_active()));
+                        },
+                    },
+                    gtk::CheckButton {
+                        set_label: Some("Show bot accounts"),
+                        #[watch]
+                        set_active: model.show_bot_accounts,
+                        connect_toggled[sender] => move |check| {
+                            sender.input(UserSettingsPageInput::SetFlag(UserFlag::ShowBotAccounts, check.is_active()));
+                        },
+                    },
+                    gtk::CheckButton {
+                        set_label: Some("Show read posts"),
+                        #[watch]
+                        set_active: model.show_read_posts,
+                        connect_toggled[sender] => move |check| {
+                            sender.input(UserSettingsPageInput::SetFlag(UserFlag::ShowReadPosts, check.is_active()));
+                        },
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_text: "Default sort",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::DropDown::from_strings(SORT_LABELS) {
+                            #[watch]
+                            set_selected: model.sort_index,
+                            connect_selected_notify[sender] => move |dropdown| {
+                                sender.input(UserSettingsPageInput::SelectSort(dropdown.selected()));
+                            },
+                        },
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_text: "Default listing",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::DropDown::from_strings(&["All", "Local", "Subscribed"]) {
+                            #[watch]
+                            set_selected: model.listing_index,
+                            connect_selected_notify[sender] => move |dropdown| {
+                                sender.input(UserSettingsPageInput::SelectListing(dropdown.selected()));
+                            },
+                        },
+                    },
+
+                    gtk::Label {
+                        set_text: "Languages",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_text: "Interface language",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        gtk::DropDown {
+                            set_model: Some(&model.interface_language_list),
+                            set_enable_search: true,
+                            #[watch]
+                            set_selected: model.interface_language_index,
+                            connect_selected_notify[sender] => move |dropdown| {
+                                sender.input(UserSettingsPageInput::SelectInterfaceLanguage(dropdown.selected()));
+                            },
+                        },
+                    },
+                    gtk::Label {
+                        set_text: "Only show content in these languages",
+                        set_halign: gtk::Align::Start,
+                    },
+                    gtk::ScrolledWindow {
+                        set_min_content_height: 150,
+                        set_max_content_height: 250,
+                        set_propagate_natural_height: true,
+
+                        #[local_ref]
+                        languages_box -> gtk::ListBox {
+                            set_selection_mode: gtk::SelectionMode::None,
+                            add_css_class: "card",
+                        },
+                    },
+
+                    gtk::Button {
+                        set_label: "Account security",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                        connect_clicked[sender] => move |_| {
+                            sender.output_sender().emit(crate::AppMsg::OpenSecurity);
+                        },
+                    },
+
+                    gtk::Label {
+                        add_css_class: "error",
+                        set_wrap: true,
+                        #[watch]
+                        set_visible: model.error.is_some(),
+                        #[watch]
+                        set_text: model.error.as_deref().unwrap_or_default(),
+                    },
+                    gtk::Label {
+                        set_wrap: true,
+                        #[watch]
+                        set_visible: model.info.is_some(),
+                        #[watch]
+                        set_text: model.info.as_deref().unwrap_or_default(),
+                    },
+                    gtk::Button {
+                        set_label: "Save",
+                        add_css_class: "suggested-action",
+                        set_halign: gtk::Align::End,
+                        #[watch]
+                        set_sensitive: !model.saving && model.uploading.is_none(),
+                        connect_clicked => UserSettingsPageInput::Save,
+                    },
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let editor_dialog = EditorDialog::builder()
+            .transient_for(root)
+            .launch(EditorType::Bio)
+            .forward(sender.input_sender(), |msg| match msg {
+                EditorOutput::CreateRequest(data, _) | EditorOutput::EditRequest(data, _) => {
+                    UserSettingsPageInput::UpdateBio(data.body)
+                }
+            });
+        let model = Self {
+            loading: false,
+            saving: false,
+            display_name_buffer: gtk::EntryBuffer::new(None::<String>),
+            email_buffer: gtk::EntryBuffer::new(None::<String>),
+            matrix_user_id_buffer: gtk::EntryBuffer::new(None::<String>),
+            bio: String::new(),
+            bio_preview: gtk::Box::default(),
+            avatar: None,
+            banner: None,
+            avatar_image: WebImage::builder().launch("".to_string()).detach(),
+            banner_image: WebImage::builder().launch("".to_string()).detach(),
+            uploading: None,
+            show_nsfw: false,
+            show_bot_accounts: true,
+            show_read_posts: true,
+            bot_account: false,
+            sort_index: 0,
+            listing_index: 1,
+            interface_languages: vec![],
+            interface_language_list: gtk::StringList::new(&[]),
+            interface_language_index: 0,
+            languages_box: gtk::ListBox::new(),
+            discussion_languages: HashSet::new(),
+            editor_dialog,
+            error: None,
+            info: None,
+        };
+        let avatar_image = model.avatar_image.widget();
+        let banner_image = model.banner_image.widget();
+        let languages_box = &model.languages_box;
+        let bio_preview = &model.bio_preview;
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            UserSettingsPageInput::Load => {
+                self.loading = true;
+                self.error = None;
+                self.info = None;
+                std::thread::spawn(move || match api::site::fetch_site() {
+                    Ok(site) => sender.input(UserSettingsPageInput::DoneLoad(Box::new(site))),
+                    Err(err) => sender
+                        .output_sender()
+                        .emit(crate::AppMsg::ShowMessage(err.to_string())),
+                });
+            }
+            UserSettingsPageInput::DoneLoad(site) => {
+                let Some(my_user) = site.my_user else {
+                    sender.output_sender().emit(crate::AppMsg::ShowMessage(
+                        "You need to be logged in to change your settings".to_string(),
+                    ));
+                    return;
+                };
+                let person = my_user.local_user_view.person;
+                let local_user = my_user.local_user_view.local_user;
+
+                self.display_name_buffer
+                    .set_text(person.display_name.unwrap_or_default());
+                self.email_buffer
+                    .set_text(local_user.email.unwrap_or_default());
+                self.matrix_user_id_buffer
+                    .set_text(person.matrix_user_id.unwrap_or_default());
+                self.bio = person.bio.unwrap_or_default();
+                markdown::render_preview(&self.bio_preview, &self.bio);
+                self.update_image(
+                    ProfileImage::Avatar,
+                    person.avatar.map(|url| url.to_string()),
+                );
+                self.update_image(
+                    ProfileImage::Banner,
+                    person.banner.map(|url| url.to_string()),
+                );
+                self.bot_account = person.bot_account;
+                self.show_nsfw = local_user.show_nsfw;
+                self.show_bot_accounts = local_user.show_bot_accounts;
+                self.show_read_posts = local_user.show_read_posts;
+                self.sort_index = SORT_TYPES
+                    .iter()
+                    .position(|sort| *sort == local_user.default_sort_type)
+                    .unwrap_or_default() as u32;
+                self.listing_index = LISTING_TYPES
+                    .iter()
+                    .position(|listing| *listing == local_user.default_listing_type)
+                    .unwrap_or(1) as u32;
+
+                let mut languages = site.all_languages;
+                self.interface_languages = std::iter::once(BROWSER_LANGUAGE.to_string())
+                    .chain(
+                        languages
+                            .iter()
+                            .filter(|language| language.id != LanguageId(0))
+                            .map(|language| language.code.clone()),
+                    )
+                    .collect();
+                let names: Vec<&str> = std::iter::once("Browser default")
+                    .chain(
+                        languages
+                            .iter()
+                            .filter(|language| language.id != LanguageId(0))
+                            .map(|language| language.name.as_str()),
+                    )
+                    .collect();
+                self.interface_language_list.splice(
+                    0,
+                    self.interface_language_list.n_items(),
+                    &names,
+                );
+                self.interface_language_index = self
+                    .interface_languages
+                    .iter()
+                    .position(|code| *code == local_user.interface_language)
+                    .unwrap_or_default() as u32;
+
+                // only the languages that are allowed on the instance can be chosen
+                if !site.discussion_languages.is_empty() {
+                    languages.retain(|language| site.discussion_languages.contains(&language.id));
+                }
+                self.discussion_languages = my_user.discussion_languages.into_iter().collect();
+                self.fill_languages(languages, &sender);
+                self.loading = false;
+            }
+            UserSettingsPageInput::EditBio => {
+                let data = EditorData {
+                    body: self.bio.clone(),
+                    ..Default::default()
+                };
+                let sender = self.editor_dialog.sender();
+                sender.emit(DialogMsg::UpdateData(Box::new(data)));
+                sender.emit(DialogMsg::Show);
+            }
+            UserSettingsPageInput::UpdateBio(bio) => {
+                markdown::render_preview(&self.bio_preview, &bio);
+                self.bio = bio;
+            }
+            UserSettingsPageInput::ChooseImage(kind) => {
+                let buttons = [
+                    ("_Cancel", ResponseType::Cancel),
+                    ("_Okay", ResponseType::Accept),
+                ];
+                let dialog = gtk::FileChooserDialog::new(
+                    Some("Upload image"),
+                    relm4::main_application().active_window().as_ref(),
+                    gtk::FileChooserAction::Open,
+                    &buttons,
+                );
+                let image_filter = gtk::FileFilter::new();
+                image_filter.add_mime_type("image/*");
+                dialog.add_filter(&image_filter);
+                dialog.run_async(move |dialog, result| {
+                    if result == ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                            sender.input(UserSettingsPageInput::UploadImage(kind, path));
+                        }
+                    }
+                    dialog.destroy();
+                });
+            }
+            UserSettingsPageInput::UploadImage(kind, path) => {
+                self.uploading = Some(kind);
+                self.error = None;
+                std::thread::spawn(move || {
+                    let result = api::image::upload_image_file(&path, |_| {})
+                        .map(|image| image.url)
+                        .map_err(|err| err.to_string());
+                    sender.input(UserSettingsPageInput::DoneUpload(kind, result));
+                });
+            }
+            UserSettingsPageInput::DoneUpload(kind, result) => {
+                self.uploading = None;
+                match result {
+                    Ok(url) => self.update_image(kind, Some(url)),
+                    Err(err) => self.error = Some(err),
+                }
+            }
+            UserSettingsPageInput::RemoveImage(kind) => {
+                self.update_image(kind, None);
+            }
+            UserSettingsPageInput::SetFlag(flag, value) => match flag {
+                UserFlag::ShowNsfw => self.show_nsfw = value,
+                UserFlag::ShowBotAccounts => self.show_bot_accounts = value,
+                UserFlag::ShowReadPosts => self.show_read_posts = value,
+                UserFlag::BotAccount => self.bot_account = value,
+            },
+            UserSettingsPageInput::SelectSort(index) => self.sort_index = index,
+            UserSettingsPageInput::SelectListing(index) => self.listing_index = index,
+            UserSettingsPageInput::SelectInterfaceLanguage(index) => {
+                self.interface_language_index = index
+            }
+            UserSettingsPageInput::ToggleLanguage(language_id, enabled) => {
+                if enabled {
+                    self.discussion_languages.insert(language_id);
+                } else {
+                    self.discussion_languages.remove(&language_id);
+                }
+            }
+            UserSettingsPageInput::Save => {
+                let email = self.email_buffer.text().to_string();
+                let params = SaveUserSettings {
+                    // empty values remove the field from the profile
+                    display_name: Some(self.display_name_buffer.text().to_string()),
+                    bio: Some(self.bio.clone()),
+                    matrix_user_id: Some(self.matrix_user_id_buffer.text().to_string()),
+                    avatar: Some(self.avatar.clone().unwrap_or_default()),
+                    banner: Some(self.banner.clone().unwrap_or_default()),
+                    // the email can't be removed, only changed
+                    email: Some(email)
+                        .filter(|email| !email.is_empty())
+                        .map(Sensitive::new),
+                    bot_account: Some(self.bot_account),
+                    show_nsfw: Some(self.show_nsfw),
+                    show_bot_accounts: Some(self.show_bot_accounts),
+                    show_read_posts: Some(self.show_read_posts),
+                    default_sort_type: SORT_TYPES.get(self.sort_index as usize).copied(),
+                    default_listing_type: LISTING_TYPES.get(self.listing_index as usize).copied(),
+                    interface_language: self
+                        .interface_languages
+                        .get(self.interface_language_index as usize)
+                        .cloned(),
+                    discussion_languages: Some(self.discussion_languages.iter().copied().collect()),
+                    ..Default::default()
+                };
+                self.saving = true;
+                self.error = None;
+                self.info = None;
+                std::thread::spawn(move || {
+                    let result = api::user::save_user_settings(params)
+                        .map(|_| ())
+                        .map_err(|err| err.to_string());
+                    sender.input(UserSettingsPageInput::DoneSave(result));
+                });
+            }
+            UserSettingsPageInput::DoneSave(result) => {
+                self.saving = false;
+                match result {
+                    Ok(_) => self.info = Some("Your settings have been saved".to_string()),
+                    Err(err) => self.error = Some(err),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{api, settings};
+use lemmy_api_common::lemmy_db_views::structs::CustomEmojiView;
+use relm4::once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+// the instance url and its custom emojis
+type CustomEmojiCache = Option<(String, Vec<CustomEmojiView>)>;
+
+// the custom emojis of the current instance, fetched at most once per instance
+static CUSTOM_EMOJIS: Lazy<Mutex<CustomEmojiCache>> = Lazy::new(|| Mutex::new(None));
+
+// returns the custom emojis if they've already been fetched for the current instance
+pub fn cached_custom_emojis() -> Option<Vec<CustomEmojiView>> {
+    let instance_url = settings::get_current_account().instance_url;
+    match &*CUSTOM_EMOJIS.lock().unwrap() {
+        Some((url, emojis)) if url == &instance_url => Some(emojis.clone()),
+        _ => None,
+    }
+}
+
+// blocks until the custom emojis are fetched, hence only call it from a background thread
+pub fn get_custom_emojis() -> Vec<CustomEmojiView> {
+    if let Some(emojis) = cached_custom_emojis() {
+        return emojis;
+    }
+    let instance_url = settings::get_current_account().instance_url;
+    // the cache isn't locked during the request, the ui reads it while rendering
+    let emojis = match api::site::fetch_site() {
+        Ok(site) => site.custom_emojis,
+        Err(err) => {
+            println!("{}", err);
+            vec![]
+        }
+    };
+    *CUSTOM_EMOJIS.lock().unwrap() = Some((instance_url, emojis.clone()));
+    emojis
+}
+
+pub fn find_custom_emoji<'a>(
+    emojis: &'a [CustomEmojiView],
+    shortcode: &str,
+) -> Option<&'a CustomEmojiView> {
+    emojis
+        .iter()
+        .find(|emoji| emoji.custom_emoji.shortcode == shortcode)
+}
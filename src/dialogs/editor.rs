@@ -1,16 +1,48 @@
-use gtk::prelude::*;
+use gtk::{gdk, gio, glib, prelude::*};
+use lemmy_api_common::lemmy_db_schema::{
+    newtypes::{CommunityId, DbUrl, LanguageId},
+    source::language::Language,
+    ListingType, SearchType,
+};
 use relm4::{
     gtk::{FileFilter, ResponseType},
     prelude::*,
 };
+use std::collections::VecDeque;
 
-use crate::api;
+use crate::{
+    api::{self, image::UploadedImage, post::PostDetails},
+    components::emoji_picker::{EmojiPicker, EmojiPickerOutput},
+    drafts::{self, DraftTarget},
+    gsettings::{self, ImageProcessing},
+    image_processing::{self, ProcessedImage},
+    markdown, uploads,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct EditorData {
     pub name: String,
     pub body: String,
     pub url: Option<reqwest::Url>,
+    pub community_id: Option<CommunityId>,
+    pub language_id: Option<LanguageId>,
+    pub nsfw: bool,
+    pub custom_thumbnail: Option<reqwest::Url>,
+    pub alt_text: Option<String>,
+}
+
+impl EditorData {
+    pub fn post_details(self) -> PostDetails {
+        PostDetails {
+            name: self.name,
+            body: self.body,
+            url: self.url,
+            nsfw: self.nsfw,
+            language_id: self.language_id,
+            custom_thumbnail: self.custom_thumbnail,
+            alt_text: self.alt_text,
+        }
+    }
 }
 
 pub struct EditorDialog {
@@ -20,14 +52,191 @@ pub struct EditorDialog {
     name_buffer: gtk::EntryBuffer,
     url_buffer: gtk::EntryBuffer,
     body_buffer: gtk::TextBuffer,
+    community_search_buffer: gtk::EntryBuffer,
+    thumbnail_buffer: gtk::EntryBuffer,
+    alt_text_buffer: gtk::EntryBuffer,
+    communities: Vec<(CommunityId, String)>,
+    community_list: gtk::StringList,
+    community_id: Option<CommunityId>,
+    languages: Vec<Language>,
+    language_list: gtk::StringList,
+    language_id: Option<LanguageId>,
+    nsfw: bool,
+    error: Option<String>,
+    uploads_in_progress: usize,
+    upload_progress: f64,
+    pending_images: VecDeque<PendingImage>,
+    preview_texture: Option<gdk::Texture>,
+    preview: gtk::Box,
+    draft_target: Option<DraftTarget>,
+    // the data the dialog has been opened with, it's only saved as draft once it has been changed
+    loaded: EditorData,
+    // whether a draft has been saved since the dialog has been opened
+    autosaved: bool,
+    autosave: Option<glib::SourceId>,
+    mention_query: Option<MentionQuery>,
+    mention_search: Option<glib::SourceId>,
+    mention_suggestions: Vec<MentionSuggestion>,
+    mention_popover: gtk::Popover,
+    mention_list: gtk::ListBox,
+    emoji_picker: Controller<EmojiPicker>,
     window: gtk::Window,
 }
 
+// delay in seconds after the last change before the draft gets saved
+const AUTOSAVE_DELAY: u32 = 2;
+// delay in milliseconds after the last key stroke before users or communities are searched
+const MENTION_SEARCH_DELAY: u64 = 400;
+
+// a processed image that waits to be confirmed before it gets uploaded
+#[derive(Debug)]
+pub struct PendingImage {
+    name: String,
+    original_size: usize,
+    image: ProcessedImage,
+}
+
+impl PendingImage {
+    fn description(&self) -> String {
+        format!(
+            "{}: {}×{} pixels, {} KiB instead of {} KiB",
+            self.name,
+            self.image.width,
+            self.image.height,
+            self.image.data.len() / 1024,
+            self.original_size / 1024
+        )
+    }
+}
+
+fn process_image(
+    name: String,
+    data: std::io::Result<Vec<u8>>,
+    options: &ImageProcessing,
+    sender: &ComponentSender<EditorDialog>,
+) {
+    let result = data.map_err(|err| err.to_string()).and_then(|data| {
+        image_processing::process_image(&data, options)
+            .map(|image| PendingImage {
+                name: name.clone(),
+                original_size: data.len(),
+                image,
+            })
+            .map_err(|err| err.to_string())
+    });
+    match result {
+        Ok(image) => sender.input(DialogMsg::PreviewImage(image)),
+        Err(err) => sender.input(DialogMsg::ImageFailed(format!("{}: {}", name, err))),
+    }
+}
+
+// only forwards whole percent steps in order to not flood the main loop with messages
+fn progress_reporter(sender: ComponentSender<EditorDialog>) -> impl FnMut(f64) + Send + 'static {
+    let mut reported = 0.0;
+    move |progress| {
+        if progress - reported >= 0.01 || progress >= 1.0 {
+            reported = progress;
+            sender.input(DialogMsg::UploadProgress(progress));
+        }
+    }
+}
+
+// a started @user or !community mention before the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct MentionQuery {
+    trigger: char,
+    query: String,
+}
+
+impl MentionQuery {
+    fn search_type(&self) -> SearchType {
+        match self.trigger {
+            '!' => SearchType::Communities,
+            _ => SearchType::Users,
+        }
+    }
+
+    // the length of the typed mention including the trigger character
+    fn char_count(&self) -> i32 {
+        self.query.chars().count() as i32 + 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MentionSuggestion {
+    label: String,
+    link: String,
+}
+
+impl MentionSuggestion {
+    fn new(trigger: char, name: &str, title: &str, actor_id: &DbUrl) -> Self {
+        let identifier = format!(
+            "{}{}@{}",
+            trigger,
+            name,
+            actor_id.host_str().unwrap_or_default()
+        );
+        Self {
+            label: format!("{} ({})", title, identifier),
+            link: format!("[{}]({})", identifier, actor_id),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum EditorType {
     Post,
     Comment,
     PrivateMessage,
+    // the bio of the user's own profile
+    Bio,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MarkdownFormat {
+    Bold,
+    Italic,
+    Link,
+    Quote,
+    Code,
+    Spoiler,
+    List,
+    Heading,
+}
+
+impl MarkdownFormat {
+    // formats that apply to whole lines instead of the selected text only
+    fn is_line_format(&self) -> bool {
+        matches!(
+            self,
+            MarkdownFormat::Quote | MarkdownFormat::List | MarkdownFormat::Heading
+        )
+    }
+
+    fn apply(&self, text: &str) -> String {
+        let prefix_lines = |prefix: &str| {
+            text.split('\n')
+                .map(|line| format!("{}{}", prefix, line))
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        match self {
+            MarkdownFormat::Bold => format!("**{}**", text),
+            MarkdownFormat::Italic => format!("*{}*", text),
+            MarkdownFormat::Link => format!("[{}](https://)", text),
+            MarkdownFormat::Quote => prefix_lines("> "),
+            MarkdownFormat::Code => {
+                if text.contains('\n') {
+                    format!("```\n{}\n```", text)
+                } else {
+                    format!("`{}`", text)
+                }
+            }
+            MarkdownFormat::Spoiler => format!("::: spoiler Spoiler\n{}\n:::", text),
+            MarkdownFormat::List => prefix_lines("- "),
+            MarkdownFormat::Heading => format!("# {}", text),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -35,11 +244,35 @@ pub enum DialogMsg {
     Show,
     Hide,
     UpdateType(EditorType, bool),
-    UpdateData(EditorData),
+    UpdateData(Box<EditorData>),
     Okay,
     ChooseImage,
-    UploadImage(std::path::PathBuf),
+    UploadImages(Vec<std::path::PathBuf>),
+    UploadPastedImage(Vec<u8>),
+    PreviewImage(PendingImage),
+    ConfirmImage,
+    DiscardImage,
+    ImageFailed(String),
+    UploadProgress(f64),
+    UploadFinished(Result<UploadedImage, String>),
     AppendBody(String),
+    Format(MarkdownFormat),
+    UpdatePreview,
+    CheckMention,
+    SearchMention,
+    UpdateMentionSuggestions(MentionQuery, Vec<MentionSuggestion>),
+    InsertMention(usize),
+    UpdateDraftTarget(Option<DraftTarget>),
+    ScheduleAutosave,
+    SaveDraft,
+    UpdateCommunity(Option<(CommunityId, String)>),
+    SearchCommunities,
+    UpdateCommunities(Vec<(CommunityId, String)>),
+    SelectCommunity(u32),
+    UpdateLanguages(Vec<Language>),
+    SelectLanguage(u32),
+    SetNsfw(bool),
+    InsertText(String),
 }
 
 #[derive(Debug)]
@@ -48,6 +281,130 @@ pub enum EditorOutput {
     EditRequest(EditorData, EditorType),
 }
 
+impl EditorDialog {
+    fn data(&self) -> EditorData {
+        let name = self.name_buffer.text().to_string();
+        let url = self.url_buffer.text().to_string();
+        let (start, end) = &self.body_buffer.bounds();
+        let body = self.body_buffer.text(start, end, true).to_string();
+        let url = reqwest::Url::parse(&url).ok();
+        let custom_thumbnail = reqwest::Url::parse(self.thumbnail_buffer.text().as_str()).ok();
+        let alt_text = Some(self.alt_text_buffer.text().to_string()).filter(|t| !t.is_empty());
+        EditorData {
+            name,
+            body,
+            url,
+            community_id: self.community_id,
+            language_id: self.language_id,
+            nsfw: self.nsfw,
+            custom_thumbnail,
+            alt_text,
+        }
+    }
+
+    fn community_position(&self) -> u32 {
+        self.communities
+            .iter()
+            .position(|(id, _)| Some(*id) == self.community_id)
+            .unwrap_or(0) as u32
+    }
+
+    // the first entry of the language list is "Undetermined"
+    fn language_position(&self) -> u32 {
+        self.languages
+            .iter()
+            .position(|language| Some(language.id) == self.language_id)
+            .map(|index| index + 1)
+            .unwrap_or(0) as u32
+    }
+
+    fn save_draft(&mut self) {
+        if let Some(source) = self.autosave.take() {
+            source.remove();
+        }
+        let Some(target) = self.draft_target else {
+            return;
+        };
+        let data = self.data();
+        if !self.is_changed(&data) || (data.name.trim().is_empty() && data.body.trim().is_empty()) {
+            drafts::remove_draft(&target);
+        } else {
+            drafts::save_draft(target, data);
+            self.autosaved = true;
+        }
+    }
+
+    fn is_changed(&self, data: &EditorData) -> bool {
+        data.name != self.loaded.name
+            || data.body != self.loaded.body
+            || data.url != self.loaded.url
+    }
+
+    // new posts are kept as draft of the chosen community, which might change while writing
+    fn update_community(&mut self, community_id: Option<CommunityId>) {
+        self.community_id = community_id;
+        if !matches!((self.type_, self.is_new), (EditorType::Post, true)) {
+            return;
+        }
+        let target = community_id.map(DraftTarget::NewPost);
+        if target == self.draft_target {
+            return;
+        }
+        // the draft moves along with the post instead of staying at the previous community
+        if let Some(previous) = self.draft_target {
+            if self.autosaved {
+                drafts::remove_draft(&previous);
+            }
+        }
+        self.draft_target = target;
+        let draft = target.as_ref().and_then(drafts::get_draft);
+        match draft {
+            // nothing has been written yet, so the draft of the community can be continued
+            Some(draft) if !self.is_changed(&self.data()) => {
+                self.name_buffer.set_text(draft.name);
+                self.url_buffer.set_text(draft.url.unwrap_or_default());
+                self.body_buffer.set_text(&draft.body);
+            }
+            _ => self.save_draft(),
+        }
+    }
+
+    fn upload_image(&mut self, image: ProcessedImage, sender: ComponentSender<Self>) {
+        self.uploads_in_progress += 1;
+        std::thread::spawn(move || {
+            let result = api::image::upload_image(
+                image.data,
+                image.mime_type,
+                progress_reporter(sender.clone()),
+            )
+            .map_err(|err| err.to_string());
+            sender.input(DialogMsg::UploadFinished(result));
+        });
+    }
+
+    fn update_image_preview(&mut self) {
+        self.preview_texture = self.pending_images.front().and_then(|pending| {
+            gdk::Texture::from_bytes(&glib::Bytes::from(&pending.image.data)).ok()
+        });
+    }
+
+    fn clear(&mut self) {
+        self.name_buffer.set_text("");
+        self.url_buffer.set_text("");
+        self.body_buffer.set_text("");
+        self.community_search_buffer.set_text("");
+        self.thumbnail_buffer.set_text("");
+        self.alt_text_buffer.set_text("");
+        self.language_id = None;
+        self.nsfw = false;
+        self.loaded = EditorData::default();
+        self.autosaved = false;
+        self.error = None;
+        self.pending_images.clear();
+        self.update_image_preview();
+    }
+}
+
 #[relm4::component(pub)]
 impl SimpleComponent for EditorDialog {
     type Init = EditorType;
@@ -94,6 +451,64 @@ impl SimpleComponent for EditorDialog {
                                 set_margin_bottom: 10,
                                 set_buffer: &model.url_buffer,
                             },
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Horizontal,
+                                set_spacing: 10,
+                                #[watch]
+                                set_sensitive: model.is_new,
+
+                                gtk::Entry {
+                                    set_placeholder_text: Some("Search community"),
+                                    set_hexpand: true,
+                                    set_buffer: &model.community_search_buffer,
+                                    connect_activate => DialogMsg::SearchCommunities,
+                                },
+                                gtk::DropDown {
+                                    set_model: Some(&model.community_list),
+                                    #[watch]
+                                    set_selected: model.community_position(),
+                                    connect_selected_notify[sender] => move |dropdown| {
+                                        sender.input(DialogMsg::SelectCommunity(dropdown.selected()));
+                                    },
+                                },
+                            },
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Horizontal,
+                                set_spacing: 10,
+                                set_margin_top: 10,
+
+                                gtk::Label {
+                                    set_label: "Language",
+                                },
+                                gtk::DropDown {
+                                    set_model: Some(&model.language_list),
+                                    set_enable_search: true,
+                                    #[watch]
+                                    set_selected: model.language_position(),
+                                    connect_selected_notify[sender] => move |dropdown| {
+                                        sender.input(DialogMsg::SelectLanguage(dropdown.selected()));
+                                    },
+                                },
+                                gtk::CheckButton {
+                                    set_label: Some("NSFW"),
+                                    #[watch]
+                                    set_active: model.nsfw,
+                                    connect_toggled[sender] => move |checkbox| {
+                                        sender.input(DialogMsg::SetNsfw(checkbox.is_active()));
+                                    },
+                                },
+                            },
+                            gtk::Entry {
+                                set_placeholder_text: Some("Custom thumbnail url"),
+                                set_margin_top: 10,
+                                set_buffer: &model.thumbnail_buffer,
+                            },
+                            gtk::Entry {
+                                set_placeholder_text: Some("Alt text"),
+                                set_margin_top: 10,
+                                set_margin_bottom: 10,
+                                set_buffer: &model.alt_text_buffer,
+                            },
                         }
                     }
                     EditorType::Comment => {
@@ -116,17 +531,160 @@ impl SimpleComponent for EditorDialog {
                             },
                         }
                     }
+                    EditorType::Bio => {
+                        gtk::Box {
+                            gtk::Label {
+                                set_halign: gtk::Align::Center,
+                                set_valign: gtk::Align::Center,
+                                set_label: "Bio",
+                                add_css_class: "font-bold"
+                            },
+                        }
+                    }
                 },
-                gtk::Label {
-                    set_text: "Body:",
+                gtk::StackSwitcher {
+                    set_stack: Some(&body_stack),
                     set_halign: gtk::Align::Start,
                 },
-                gtk::TextView {
-                    set_editable: true,
+                #[name(body_stack)]
+                gtk::Stack {
                     set_margin_top: 5,
                     set_margin_bottom: 10,
                     set_vexpand: true,
-                    set_buffer: Some(&model.body_buffer),
+
+                    add_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_spacing: 5,
+                            set_margin_bottom: 5,
+
+                            gtk::Button {
+                                set_icon_name: "format-text-bold",
+                                set_tooltip_text: Some("Bold (Ctrl+B)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Bold),
+                            },
+                            gtk::Button {
+                                set_icon_name: "format-text-italic",
+                                set_tooltip_text: Some("Italic (Ctrl+I)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Italic),
+                            },
+                            gtk::Button {
+                                set_icon_name: "insert-link",
+                                set_tooltip_text: Some("Link (Ctrl+K)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Link),
+                            },
+                            gtk::Button {
+                                set_label: "❝",
+                                set_tooltip_text: Some("Quote (Ctrl+Shift+Q)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Quote),
+                            },
+                            gtk::Button {
+                                set_label: "</>",
+                                set_tooltip_text: Some("Code (Ctrl+E)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Code),
+                            },
+                            gtk::Button {
+                                set_icon_name: "view-conceal",
+                                set_tooltip_text: Some("Spoiler (Ctrl+Shift+S)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Spoiler),
+                            },
+                            gtk::Button {
+                                set_icon_name: "view-list-bullet",
+                                set_tooltip_text: Some("List (Ctrl+Shift+L)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::List),
+                            },
+                            gtk::Button {
+                                set_label: "H",
+                                set_tooltip_text: Some("Heading (Ctrl+Shift+H)"),
+                                connect_clicked => DialogMsg::Format(MarkdownFormat::Heading),
+                            },
+                            gtk::MenuButton {
+                                set_icon_name: "face-smile-symbolic",
+                                set_tooltip_text: Some("Insert emoji"),
+                                set_popover: Some(emoji_picker),
+                            },
+                        },
+                        gtk::ScrolledWindow {
+                            set_vexpand: true,
+
+                            #[name(body_view)]
+                            gtk::TextView {
+                                set_editable: true,
+                                set_wrap_mode: gtk::WrapMode::Word,
+                                set_buffer: Some(&model.body_buffer),
+                            },
+                        },
+                    } -> {
+                        set_title: "Write",
+                    },
+                    add_child = &gtk::ScrolledWindow {
+                        #[local_ref]
+                        preview -> gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_spacing: 10,
+                        },
+                    } -> {
+                        set_title: "Preview",
+                    },
+                },
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 5,
+                    set_margin_bottom: 10,
+                    #[watch]
+                    set_visible: !model.pending_images.is_empty(),
+
+                    gtk::Picture {
+                        set_size_request: (-1, 150),
+                        set_can_shrink: true,
+                        #[watch]
+                        set_paintable: model.preview_texture.as_ref(),
+                    },
+                    gtk::Label {
+                        #[watch]
+                        set_label: &model
+                            .pending_images
+                            .front()
+                            .map(|pending| pending.description())
+                            .unwrap_or_default(),
+                        set_wrap: true,
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 10,
+
+                        gtk::Button {
+                            set_label: "Discard",
+                            connect_clicked => DialogMsg::DiscardImage,
+                        },
+                        gtk::Button {
+                            set_label: "Upload",
+                            add_css_class: "suggested-action",
+                            connect_clicked => DialogMsg::ConfirmImage,
+                        },
+                    },
+                },
+                gtk::ProgressBar {
+                    #[watch]
+                    set_visible: model.uploads_in_progress > 0,
+                    #[watch]
+                    set_fraction: model.upload_progress,
+                    #[watch]
+                    set_text: Some(&format!("Uploading {} image(s)", model.uploads_in_progress)),
+                    set_show_text: true,
+                    set_margin_bottom: 10,
+                },
+                gtk::Label {
+                    #[watch]
+                    set_visible: model.error.is_some(),
+                    #[watch]
+                    set_label: model.error.as_deref().unwrap_or_default(),
+                    set_halign: gtk::Align::Start,
+                    set_margin_bottom: 10,
+                    add_css_class: "error",
                 },
                 gtk::Box {
                     set_orientation: gtk::Orientation::Horizontal,
@@ -161,6 +719,33 @@ impl SimpleComponent for EditorDialog {
         let name_buffer = gtk::EntryBuffer::builder().build();
         let url_buffer = gtk::EntryBuffer::builder().build();
         let body_buffer = gtk::TextBuffer::builder().build();
+        {
+            let sender = sender.clone();
+            body_buffer.connect_changed(move |_| {
+                sender.input(DialogMsg::UpdatePreview);
+                sender.input(DialogMsg::CheckMention);
+                sender.input(DialogMsg::ScheduleAutosave);
+            });
+        }
+        for buffer in [&name_buffer, &url_buffer] {
+            let sender = sender.clone();
+            buffer.connect_text_notify(move |_| {
+                sender.input(DialogMsg::ScheduleAutosave);
+            });
+        }
+        let mention_list = gtk::ListBox::new();
+        {
+            let sender = sender.clone();
+            mention_list.connect_row_activated(move |_, row| {
+                sender.input(DialogMsg::InsertMention(row.index() as usize));
+            });
+        }
+        let mention_popover = gtk::Popover::builder()
+            .child(&mention_list)
+            .autohide(false)
+            .has_arrow(false)
+            .position(gtk::PositionType::Bottom)
+            .build();
         let window = root.toplevel_window().unwrap();
         let model = EditorDialog {
             type_: init,
@@ -169,44 +754,227 @@ impl SimpleComponent for EditorDialog {
             name_buffer,
             url_buffer,
             body_buffer,
+            community_search_buffer: gtk::EntryBuffer::builder().build(),
+            thumbnail_buffer: gtk::EntryBuffer::builder().build(),
+            alt_text_buffer: gtk::EntryBuffer::builder().build(),
+            communities: vec![],
+            community_list: gtk::StringList::new(&[]),
+            community_id: None,
+            languages: vec![],
+            language_list: gtk::StringList::new(&["Undetermined"]),
+            language_id: None,
+            nsfw: false,
+            error: None,
+            uploads_in_progress: 0,
+            upload_progress: 0.0,
+            pending_images: VecDeque::new(),
+            preview_texture: None,
+            preview: gtk::Box::default(),
+            draft_target: None,
+            loaded: EditorData::default(),
+            autosaved: false,
+            autosave: None,
+            mention_query: None,
+            mention_search: None,
+            mention_suggestions: vec![],
+            mention_popover,
+            mention_list,
+            emoji_picker: EmojiPicker::builder()
+                .launch(())
+                .forward(sender.input_sender(), |msg| match msg {
+                    EmojiPickerOutput::Picked(emoji) => DialogMsg::InsertText(emoji),
+                }),
             window,
         };
+        let emoji_picker = model.emoji_picker.widget();
+        let preview = &model.preview;
         let widgets = view_output!();
+
+        let shortcuts = gtk::ShortcutController::new();
+        for (accelerator, format) in [
+            ("<Control>b", MarkdownFormat::Bold),
+            ("<Control>i", MarkdownFormat::Italic),
+            ("<Control>k", MarkdownFormat::Link),
+            ("<Control><Shift>q", MarkdownFormat::Quote),
+            ("<Control>e", MarkdownFormat::Code),
+            ("<Control><Shift>s", MarkdownFormat::Spoiler),
+            ("<Control><Shift>l", MarkdownFormat::List),
+            ("<Control><Shift>h", MarkdownFormat::Heading),
+        ] {
+            let sender = sender.clone();
+            let action = gtk::CallbackAction::new(move |_, _| {
+                sender.input(DialogMsg::Format(format));
+                true
+            });
+            shortcuts.add_shortcut(gtk::Shortcut::new(
+                gtk::ShortcutTrigger::parse_string(accelerator),
+                Some(action),
+            ));
+        }
+        widgets.body_view.add_controller(shortcuts);
+
+        // upload images that are dropped into or pasted into the body
+        let drop_target = gtk::DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+        {
+            let sender = sender.clone();
+            drop_target.connect_drop(move |_, value, _, _| {
+                let Ok(files) = value.get::<gdk::FileList>() else {
+                    return false;
+                };
+                let paths: Vec<_> = files
+                    .files()
+                    .iter()
+                    .filter_map(|file| file.path())
+                    .collect();
+                if paths.is_empty() {
+                    return false;
+                }
+                sender.input(DialogMsg::UploadImages(paths));
+                true
+            });
+        }
+        widgets.body_view.add_controller(drop_target);
+        {
+            let sender = sender.clone();
+            widgets.body_view.connect_paste_clipboard(move |view| {
+                let clipboard = view.clipboard();
+                let formats = clipboard.formats();
+                if formats.contains_type(gdk::FileList::static_type()) {
+                    view.stop_signal_emission_by_name("paste-clipboard");
+                    let sender = sender.clone();
+                    clipboard.read_value_async(
+                        gdk::FileList::static_type(),
+                        glib::PRIORITY_DEFAULT,
+                        None::<&gio::Cancellable>,
+                        move |value| {
+                            let files = value
+                                .ok()
+                                .and_then(|value| value.get::<gdk::FileList>().ok());
+                            if let Some(files) = files {
+                                let paths = files
+                                    .files()
+                                    .iter()
+                                    .filter_map(|file| file.path())
+                                    .collect();
+                                sender.input(DialogMsg::UploadImages(paths));
+                            }
+                        },
+                    );
+                } else if formats.contains_type(gdk::Texture::static_type()) {
+                    view.stop_signal_emission_by_name("paste-clipboard");
+                    let sender = sender.clone();
+                    clipboard.read_texture_async(None::<&gio::Cancellable>, move |texture| {
+                        if let Ok(Some(texture)) = texture {
+                            let data = texture.save_to_png_bytes().to_vec();
+                            sender.input(DialogMsg::UploadPastedImage(data));
+                        }
+                    });
+                }
+            });
+        }
+        model.mention_popover.set_parent(&widgets.body_view);
+
         ComponentParts { model, widgets }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
-            DialogMsg::Show => self.visible = true,
+            DialogMsg::Show => {
+                // drafts only keep the text, the other fields stay as they have been loaded
+                if let Some(draft) = self.draft_target.as_ref().and_then(drafts::get_draft) {
+                    self.name_buffer.set_text(draft.name);
+                    self.url_buffer.set_text(draft.url.unwrap_or_default());
+                    self.body_buffer.set_text(&draft.body);
+                }
+                if let EditorType::Post = self.type_ {
+                    if self.languages.is_empty() {
+                        std::thread::spawn(move || match api::site::fetch_site() {
+                            Ok(site) => {
+                                let mut languages = site.all_languages;
+                                // only offer the languages that are allowed on the instance
+                                if !site.discussion_languages.is_empty() {
+                                    languages.retain(|language| {
+                                        site.discussion_languages.contains(&language.id)
+                                    });
+                                }
+                                // the undetermined language is already part of the list
+                                languages.retain(|language| language.id != LanguageId(0));
+                                sender.input(DialogMsg::UpdateLanguages(languages));
+                            }
+                            Err(err) => println!("{}", err),
+                        });
+                    }
+                }
+                self.error = None;
+                self.visible = true;
+            }
             DialogMsg::Hide => {
-                self.name_buffer.set_text("");
-                self.url_buffer.set_text("");
-                self.body_buffer.set_text("");
+                // keep the text as draft when the dialog gets closed without sending
+                self.save_draft();
                 self.visible = false;
+                self.clear();
             }
             DialogMsg::Okay => {
-                let name = self.name_buffer.text().to_string();
-                let url = self.url_buffer.text().to_string();
-                let (start, end) = &self.body_buffer.bounds();
-                let body = self.body_buffer.text(start, end, true).to_string();
-                let url = reqwest::Url::parse(&url).ok();
-                let post = EditorData { name, body, url };
+                let post = self.data();
+                if let (EditorType::Post, true) = (self.type_, self.is_new) {
+                    if post.name.trim().is_empty() {
+                        self.error = Some("The post needs a title".to_string());
+                        return;
+                    }
+                    if post.community_id.is_none() {
+                        self.error = Some("Please choose a community".to_string());
+                        return;
+                    }
+                }
+                // the draft gets removed by the parent once the request succeeded
+                self.save_draft();
                 let message = match self.is_new {
                     true => EditorOutput::CreateRequest(post, self.type_),
                     false => EditorOutput::EditRequest(post, self.type_),
                 };
                 sender.output_sender().emit(message);
                 self.visible = false;
+                self.clear();
+            }
+            DialogMsg::UpdateDraftTarget(target) => {
+                self.draft_target = target;
+            }
+            DialogMsg::ScheduleAutosave => {
+                if self.visible && self.autosave.is_none() {
+                    let source = glib::timeout_add_seconds_local_once(AUTOSAVE_DELAY, move || {
+                        sender.input(DialogMsg::SaveDraft);
+                    });
+                    self.autosave = Some(source);
+                }
+            }
+            DialogMsg::SaveDraft => {
+                self.autosave = None;
+                if self.visible {
+                    self.save_draft();
+                }
             }
             DialogMsg::UpdateType(type_, is_new) => {
                 self.type_ = type_;
                 self.is_new = is_new;
             }
             DialogMsg::UpdateData(data) => {
+                self.loaded = (*data).clone();
                 self.name_buffer.set_text(data.name);
-                if let Some(url) = data.url {
-                    self.url_buffer.set_text(url.to_string());
+                match data.url {
+                    Some(url) => self.url_buffer.set_text(url.to_string()),
+                    None => self.url_buffer.set_text(""),
+                }
+                match data.custom_thumbnail {
+                    Some(url) => self.thumbnail_buffer.set_text(url.to_string()),
+                    None => self.thumbnail_buffer.set_text(""),
                 }
+                self.alt_text_buffer
+                    .set_text(data.alt_text.unwrap_or_default());
+                if data.community_id.is_some() {
+                    self.community_id = data.community_id;
+                }
+                self.language_id = data.language_id;
+                self.nsfw = data.nsfw;
                 self.body_buffer.set_text(&data.body);
             }
             DialogMsg::ChooseImage => {
@@ -221,34 +989,334 @@ impl SimpleComponent for EditorDialog {
                     &buttons,
                 );
                 dialog.set_transient_for(Some(&self.window));
+                dialog.set_select_multiple(true);
                 let image_filter = FileFilter::new();
-                image_filter.add_pattern("image/*");
+                image_filter.add_mime_type("image/*");
                 dialog.add_filter(&image_filter);
                 dialog.run_async(move |dialog, result| {
                     match result {
                         ResponseType::Accept => {
-                            let path = dialog.file().unwrap().path();
-                            sender.input(DialogMsg::UploadImage(path.unwrap()))
+                            let files = dialog.files();
+                            let paths = (0..files.n_items())
+                                .filter_map(|index| files.item(index).and_downcast::<gio::File>())
+                                .filter_map(|file| file.path())
+                                .collect();
+                            sender.input(DialogMsg::UploadImages(paths))
                         }
                         _ => dialog.hide(),
                     }
                     dialog.destroy();
                 });
             }
-            DialogMsg::UploadImage(path) => {
+            DialogMsg::UploadImages(paths) if gsettings::image_processing().enabled => {
+                self.error = None;
+                let options = gsettings::image_processing();
+                std::thread::spawn(move || {
+                    for path in paths {
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        process_image(name, std::fs::read(&path), &options, &sender);
+                    }
+                });
+            }
+            DialogMsg::UploadImages(paths) => {
+                self.error = None;
+                self.uploads_in_progress += paths.len();
                 std::thread::spawn(move || {
-                    if let Ok(image_path) = api::image::upload_image(path) {
-                        let new_text = format!("![]({})", image_path);
-                        sender.input(DialogMsg::AppendBody(new_text));
+                    for path in paths {
+                        let result =
+                            api::image::upload_image_file(&path, progress_reporter(sender.clone()))
+                                .map_err(|err| format!("{}: {}", path.display(), err));
+                        sender.input(DialogMsg::UploadFinished(result));
                     }
                 });
             }
+            DialogMsg::UploadPastedImage(data) => {
+                self.error = None;
+                let options = gsettings::image_processing();
+                if options.enabled {
+                    std::thread::spawn(move || {
+                        process_image("Pasted image".to_string(), Ok(data), &options, &sender);
+                    });
+                } else {
+                    // the dimensions are only needed for the preview
+                    let image = ProcessedImage {
+                        data,
+                        mime_type: "image/png",
+                        width: 0,
+                        height: 0,
+                    };
+                    self.upload_image(image, sender);
+                }
+            }
+            DialogMsg::PreviewImage(image) => {
+                self.pending_images.push_back(image);
+                if self.pending_images.len() == 1 {
+                    self.update_image_preview();
+                }
+            }
+            DialogMsg::ConfirmImage => {
+                if let Some(pending) = self.pending_images.pop_front() {
+                    self.upload_image(pending.image, sender);
+                }
+                self.update_image_preview();
+            }
+            DialogMsg::DiscardImage => {
+                self.pending_images.pop_front();
+                self.update_image_preview();
+            }
+            DialogMsg::ImageFailed(err) => {
+                self.error = Some(format!("Failed to process image {}", err));
+            }
+            DialogMsg::UploadProgress(progress) => self.upload_progress = progress,
+            DialogMsg::UploadFinished(result) => {
+                self.uploads_in_progress = self.uploads_in_progress.saturating_sub(1);
+                self.upload_progress = 0.0;
+                match result {
+                    Ok(image) => {
+                        uploads::add_upload(&image);
+                        sender.input(DialogMsg::AppendBody(format!("![]({})", image.url)));
+                    }
+                    Err(err) => self.error = Some(format!("Failed to upload image: {}", err)),
+                }
+            }
             DialogMsg::AppendBody(new_text) => {
                 let (start, end) = &self.body_buffer.bounds();
                 let body = self.body_buffer.text(start, end, true).to_string();
                 self.body_buffer
                     .set_text(&format!("{}\n{}", body, new_text));
             }
+            DialogMsg::Format(format) => {
+                let buffer = &self.body_buffer;
+                let (mut start, mut end) = buffer.selection_bounds().unwrap_or_else(|| {
+                    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+                    (cursor, cursor)
+                });
+                if format.is_line_format() {
+                    start.set_line_offset(0);
+                }
+                let selection = buffer.text(&start, &end, true).to_string();
+                buffer.begin_user_action();
+                buffer.delete(&mut start, &mut end);
+                buffer.insert(&mut start, &format.apply(&selection));
+                buffer.end_user_action();
+            }
+            DialogMsg::CheckMention => {
+                let cursor = self
+                    .body_buffer
+                    .iter_at_mark(&self.body_buffer.get_insert());
+                let mut line_start = cursor;
+                line_start.set_line_offset(0);
+                let line = self.body_buffer.text(&line_start, &cursor, false);
+                let word = line.rsplit(char::is_whitespace).next().unwrap_or_default();
+
+                let mut chars = word.chars();
+                let query = match chars.next() {
+                    Some(trigger @ ('@' | '!')) if word.chars().count() > 2 => Some(MentionQuery {
+                        trigger,
+                        query: chars.collect(),
+                    }),
+                    _ => None,
+                };
+                if query == self.mention_query {
+                    return;
+                }
+
+                if let Some(source) = self.mention_search.take() {
+                    source.remove();
+                }
+                if query.is_some() {
+                    let source = glib::timeout_add_local_once(
+                        std::time::Duration::from_millis(MENTION_SEARCH_DELAY),
+                        move || sender.input(DialogMsg::SearchMention),
+                    );
+                    self.mention_search = Some(source);
+                } else {
+                    self.mention_popover.popdown();
+                }
+                self.mention_query = query;
+            }
+            DialogMsg::SearchMention => {
+                self.mention_search = None;
+                let Some(query) = self.mention_query.clone() else {
+                    return;
+                };
+                std::thread::spawn(move || {
+                    let search_type = query.search_type();
+                    match api::search::fetch_search(1, query.query.clone(), Some(search_type)) {
+                        Ok(response) => {
+                            let suggestions = match search_type {
+                                SearchType::Communities => response
+                                    .communities
+                                    .iter()
+                                    .map(|view| {
+                                        MentionSuggestion::new(
+                                            '!',
+                                            &view.community.name,
+                                            &view.community.title,
+                                            &view.community.actor_id,
+                                        )
+                                    })
+                                    .collect(),
+                                _ => response
+                                    .users
+                                    .iter()
+                                    .map(|view| {
+                                        MentionSuggestion::new(
+                                            '@',
+                                            &view.person.name,
+                                            view.person
+                                                .display_name
+                                                .as_ref()
+                                                .unwrap_or(&view.person.name),
+                                            &view.person.actor_id,
+                                        )
+                                    })
+                                    .collect(),
+                            };
+                            sender.input(DialogMsg::UpdateMentionSuggestions(query, suggestions));
+                        }
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            DialogMsg::UpdateMentionSuggestions(query, suggestions) => {
+                // the user continued typing in the meantime
+                if self.mention_query.as_ref() != Some(&query) {
+                    return;
+                }
+                while let Some(row) = self.mention_list.row_at_index(0) {
+                    self.mention_list.remove(&row);
+                }
+                if suggestions.is_empty() {
+                    self.mention_popover.popdown();
+                    return;
+                }
+                for suggestion in &suggestions {
+                    let label = gtk::Label::builder()
+                        .label(&suggestion.label)
+                        .halign(gtk::Align::Start)
+                        .margin_top(5)
+                        .margin_bottom(5)
+                        .build();
+                    self.mention_list.append(&label);
+                }
+                self.mention_suggestions = suggestions;
+
+                // show the suggestions right below the cursor
+                if let Some(view) = self
+                    .mention_popover
+                    .parent()
+                    .and_downcast::<gtk::TextView>()
+                {
+                    let cursor = self
+                        .body_buffer
+                        .iter_at_mark(&self.body_buffer.get_insert());
+                    let location = view.iter_location(&cursor);
+                    let (x, y) = view.buffer_to_window_coords(
+                        gtk::TextWindowType::Widget,
+                        location.x(),
+                        location.y(),
+                    );
+                    let rect = gtk::gdk::Rectangle::new(x, y, 1, location.height());
+                    self.mention_popover.set_pointing_to(Some(&rect));
+                }
+                self.mention_popover.popup();
+            }
+            DialogMsg::InsertMention(index) => {
+                let (Some(query), Some(suggestion)) = (
+                    self.mention_query.take(),
+                    self.mention_suggestions.get(index),
+                ) else {
+                    return;
+                };
+                let mut end = self
+                    .body_buffer
+                    .iter_at_mark(&self.body_buffer.get_insert());
+                let mut start = self
+                    .body_buffer
+                    .iter_at_offset(end.offset() - query.char_count());
+                self.body_buffer.begin_user_action();
+                self.body_buffer.delete(&mut start, &mut end);
+                self.body_buffer
+                    .insert(&mut start, &format!("{} ", suggestion.link));
+                self.body_buffer.end_user_action();
+                self.mention_popover.popdown();
+            }
+            DialogMsg::UpdateCommunity(community) => match community {
+                Some(community) => {
+                    sender.input(DialogMsg::UpdateCommunities(vec![community]));
+                }
+                None => {
+                    self.update_community(None);
+                    sender.input(DialogMsg::SearchCommunities);
+                }
+            },
+            DialogMsg::SearchCommunities => {
+                let query = self.community_search_buffer.text().to_string();
+                // show the subscribed communities as long as nothing has been searched
+                let listing_type = if query.trim().is_empty() {
+                    ListingType::Subscribed
+                } else {
+                    ListingType::All
+                };
+                std::thread::spawn(move || {
+                    match api::communities::fetch_communities(1, Some(query), Some(listing_type)) {
+                        Ok(communities) => {
+                            let communities = communities
+                                .into_iter()
+                                .map(|view| {
+                                    let host =
+                                        view.community.actor_id.host_str().unwrap_or_default();
+                                    let name = format!("{}@{}", view.community.name, host);
+                                    (view.community.id, name)
+                                })
+                                .collect();
+                            sender.input(DialogMsg::UpdateCommunities(communities));
+                        }
+                        Err(err) => println!("{}", err),
+                    }
+                });
+            }
+            DialogMsg::UpdateCommunities(communities) => {
+                self.update_community(communities.first().map(|(id, _)| *id));
+                let names: Vec<&str> = communities.iter().map(|(_, name)| name.as_str()).collect();
+                self.community_list
+                    .splice(0, self.community_list.n_items(), &names);
+                self.communities = communities;
+            }
+            DialogMsg::SelectCommunity(position) => {
+                if let Some((id, _)) = self.communities.get(position as usize) {
+                    self.update_community(Some(*id));
+                }
+            }
+            DialogMsg::UpdateLanguages(languages) => {
+                let names: Vec<&str> = languages
+                    .iter()
+                    .map(|language| language.name.as_str())
+                    .collect();
+                self.language_list
+                    .splice(1, self.language_list.n_items() - 1, &names);
+                self.languages = languages;
+            }
+            DialogMsg::SelectLanguage(position) => {
+                self.language_id = match position {
+                    0 => None,
+                    _ => self
+                        .languages
+                        .get(position as usize - 1)
+                        .map(|language| language.id),
+                };
+            }
+            DialogMsg::SetNsfw(nsfw) => self.nsfw = nsfw,
+            DialogMsg::InsertText(text) => self.body_buffer.insert_at_cursor(&text),
+            DialogMsg::UpdatePreview => {
+                let (start, end) = &self.body_buffer.bounds();
+                let body = self.body_buffer.text(start, end, true).to_string();
+                markdown::render_preview(&self.preview, &body);
+            }
         }
     }
 }
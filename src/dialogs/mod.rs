@@ -1,5 +1,6 @@
 pub mod about;
 pub mod editor;
+pub mod passphrase;
 pub mod report_dialog;
 pub mod settings;
 pub mod site_info;
@@ -0,0 +1,169 @@
+use gtk::prelude::*;
+use relm4::prelude::*;
+
+use crate::secrets;
+
+// asks for the passphrase of the encrypted token file, used if there's no keyring
+pub struct PassphraseDialog {
+    visible: bool,
+    // whether a new passphrase is chosen instead of an existing one being entered
+    is_new: bool,
+    error: Option<String>,
+    unlocking: bool,
+}
+
+#[derive(Debug)]
+pub enum PassphraseDialogInput {
+    Show,
+    Hide,
+    Unlock(String, String),
+    UnlockResult(Result<(), String>),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for PassphraseDialog {
+    type Init = ();
+    type Input = PassphraseDialogInput;
+    type Output = crate::AppMsg;
+
+    view! {
+        dialog = gtk::Dialog {
+            #[watch]
+            set_visible: model.visible,
+            set_modal: true,
+            set_title: Some("Login tokens"),
+            connect_close_request[sender] => move |_| {
+                sender.input(PassphraseDialogInput::Hide);
+                gtk::Inhibit(false)
+            },
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 10,
+                set_margin_all: 15,
+                set_width_request: 350,
+
+                gtk::Label {
+                    set_wrap: true,
+                    set_max_width_chars: 40,
+                    #[watch]
+                    set_label: if model.is_new {
+                        "No keyring is available, choose a passphrase to protect your login tokens."
+                    } else {
+                        "Enter the passphrase to unlock your login tokens."
+                    },
+                },
+
+                #[name(passphrase)]
+                gtk::PasswordEntry {
+                    set_placeholder_text: Some("Passphrase"),
+                    set_show_peek_icon: true,
+                },
+
+                #[name(confirmation)]
+                gtk::PasswordEntry {
+                    set_placeholder_text: Some("Confirm passphrase"),
+                    set_show_peek_icon: true,
+                    #[watch]
+                    set_visible: model.is_new,
+                },
+
+                gtk::Label {
+                    add_css_class: "error",
+                    set_wrap: true,
+                    #[watch]
+                    set_visible: model.error.is_some(),
+                    #[watch]
+                    set_label: model.error.as_deref().unwrap_or_default(),
+                },
+
+                gtk::Label {
+                    add_css_class: "dim-label",
+                    set_wrap: true,
+                    set_max_width_chars: 40,
+                    set_label: "Without it, logins are only kept until the app is closed.",
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 10,
+                    set_halign: gtk::Align::End,
+                    set_margin_top: 10,
+
+                    gtk::Button {
+                        set_label: "Skip",
+                        connect_clicked => PassphraseDialogInput::Hide,
+                    },
+                    gtk::Button {
+                        add_css_class: "suggested-action",
+                        #[watch]
+                        set_label: if model.is_new { "Save" } else { "Unlock" },
+                        #[watch]
+                        set_sensitive: !model.unlocking,
+                        connect_clicked[sender, passphrase, confirmation] => move |_| {
+                            sender.input(PassphraseDialogInput::Unlock(
+                                passphrase.text().to_string(),
+                                confirmation.text().to_string(),
+                            ));
+                            passphrase.set_text("");
+                            confirmation.set_text("");
+                        },
+                    },
+                },
+            }
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Self {
+            visible: false,
+            is_new: false,
+            error: None,
+            unlocking: false,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            PassphraseDialogInput::Show => {
+                self.is_new = !secrets::has_encrypted_file();
+                self.error = None;
+                self.visible = true;
+            }
+            PassphraseDialogInput::Hide => {
+                self.visible = false;
+            }
+            PassphraseDialogInput::Unlock(passphrase, confirmation) => {
+                if passphrase.is_empty() {
+                    self.error = Some("The passphrase must not be empty".to_string());
+                    return;
+                }
+                if self.is_new && passphrase != confirmation {
+                    self.error = Some("The passphrases don't match".to_string());
+                    return;
+                }
+                self.unlocking = true;
+                std::thread::spawn(move || {
+                    let result = secrets::unlock(&passphrase).map_err(|err| err.to_string());
+                    sender.input(PassphraseDialogInput::UnlockResult(result));
+                });
+            }
+            PassphraseDialogInput::UnlockResult(result) => {
+                self.unlocking = false;
+                match result {
+                    Ok(_) => {
+                        self.visible = false;
+                        sender.output_sender().emit(crate::AppMsg::SecretsUnlocked);
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use gtk::prelude::*;
-use lemmy_api_common::lemmy_db_schema::newtypes::{CommentId, PostId};
+use lemmy_api_common::lemmy_db_schema::newtypes::{CommentId, PostId, PrivateMessageId};
 use relm4::prelude::*;
 
 use crate::api;
@@ -8,6 +8,7 @@ pub struct ReportDialog {
     visible: bool,
     post_id: Option<PostId>,
     comment_id: Option<CommentId>,
+    private_message_id: Option<PrivateMessageId>,
 }
 
 #[derive(Debug)]
@@ -15,12 +16,12 @@ pub enum ReportDialogInput {
     Show,
     Hide,
     Report(String),
-    UpdateId(Option<PostId>, Option<CommentId>),
+    UpdateId(Option<PostId>, Option<CommentId>, Option<PrivateMessageId>),
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for ReportDialog {
-    type Init = (Option<PostId>, Option<CommentId>);
+    type Init = (Option<PostId>, Option<CommentId>, Option<PrivateMessageId>);
     type Input = ReportDialogInput;
     type Output = crate::AppMsg;
 
@@ -29,7 +30,7 @@ impl SimpleComponent for ReportDialog {
             #[watch]
             set_visible: model.visible,
             set_modal: true,
-            set_title: Some("Report post/comment"),
+            set_title: Some("Report"),
             connect_close_request[sender] => move |_| {
                 sender.input(ReportDialogInput::Hide);
                 gtk::Inhibit(false)
@@ -51,9 +52,9 @@ impl SimpleComponent for ReportDialog {
                     set_label: "Report",
                     set_hexpand: false,
                     set_halign: gtk::Align::End,
-                    connect_clicked[report_message] => move |_| {
+                    connect_clicked[sender, report_message] => move |_| {
                         let reason = report_message.text().to_string();
-                        ReportDialogInput::Report(reason);
+                        sender.input(ReportDialogInput::Report(reason));
                     },
                 },
             }
@@ -69,6 +70,7 @@ impl SimpleComponent for ReportDialog {
             visible: false,
             post_id: init.0,
             comment_id: init.1,
+            private_message_id: init.2,
         };
         let widgets = view_output!();
         ComponentParts { model, widgets }
@@ -85,19 +87,26 @@ impl SimpleComponent for ReportDialog {
             ReportDialogInput::Report(reason) => {
                 let post_id = self.post_id;
                 let comment_id = self.comment_id;
+                let private_message_id = self.private_message_id;
 
                 std::thread::spawn(move || {
                     if let Some(post_id) = post_id {
                         _ = api::post::report_post(post_id, reason);
                     } else if let Some(comment_id) = comment_id {
                         _ = api::comment::report_comment(comment_id, reason);
+                    } else if let Some(private_message_id) = private_message_id {
+                        _ = api::private_message::report_private_message(
+                            private_message_id,
+                            reason,
+                        );
                     }
                     sender.input_sender().emit(ReportDialogInput::Hide);
                 });
             }
-            ReportDialogInput::UpdateId(post_id, comment_id) => {
+            ReportDialogInput::UpdateId(post_id, comment_id, private_message_id) => {
                 self.post_id = post_id;
                 self.comment_id = comment_id;
+                self.private_message_id = private_message_id;
             }
         }
     }
@@ -1,15 +1,79 @@
-use crate::settings::{get_prefs, save_prefs};
-use gtk::prelude::*;
+use crate::backup::{self, Backup, ConflictResolution};
+use crate::components::sort_dropdown::SORT_LABELS;
+use crate::gsettings::{self, bind_dropdown};
+use crate::settings::{self, get_prefs};
+use gtk::{gio, prelude::*, ResponseType};
 use relm4::prelude::*;
+use std::path::PathBuf;
+
+// the values of the gsettings key, in the same order as the labels of the sort dropdown
+const SORT_CHOICES: &[&str] = &[
+    "Hot",
+    "New",
+    "Active",
+    "Old",
+    "TopAll",
+    "TopDay",
+    "TopWeek",
+    "TopMonth",
+    "TopYear",
+    "MostComments",
+    "NewComments",
+    "TopHour",
+    "TopSixHour",
+];
 
 pub struct Settings {
     visible: bool,
+    notification_accounts: gtk::Box,
+    // the backup that is read, but waits for the user to confirm the import
+    pending_import: Option<Backup>,
+    backup_error: Option<String>,
+    backup_info: Option<String>,
+    // the widgets are bound to the settings while they're alive
+    gsettings: gio::Settings,
+}
+
+impl Settings {
+    // the accounts might have changed since the dialog has been shown the last time
+    fn update_notification_accounts(&self) {
+        while let Some(child) = self.notification_accounts.first_child() {
+            self.notification_accounts.remove(&child);
+        }
+        for (index, account) in get_prefs().accounts.into_iter().enumerate() {
+            if account.jwt.is_none() {
+                continue;
+            }
+            let instance = account
+                .instance_url
+                .trim_start_matches("https://")
+                .trim_start_matches("http://");
+            let check_button = gtk::CheckButton::builder()
+                .label(format!("{}@{}", account.name, instance))
+                .active(account.notifications)
+                .margin_start(12)
+                .build();
+            check_button.connect_toggled(move |checkbox| {
+                let mut account = get_prefs().accounts[index].clone();
+                account.notifications = checkbox.is_active();
+                settings::update_account(account, index);
+            });
+            self.notification_accounts.append(&check_button);
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum SettingsInput {
     Show,
     Hide,
+    // whether the login tokens are included
+    ChooseExportFile(bool),
+    Export(PathBuf, bool),
+    ChooseImportFile,
+    ReadBackup(PathBuf),
+    // none if the import has been cancelled
+    Import(Option<ConflictResolution>),
 }
 
 #[relm4::component(pub)]
@@ -42,16 +106,259 @@ impl SimpleComponent for Settings {
                     set_spacing: 5,
                     set_margin_all: 15,
 
+                    gtk::Label {
+                        set_label: "Appearance",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Theme",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(theme)]
+                        gtk::DropDown::from_strings(&["System", "Light", "Dark"]) {},
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Feed layout",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(feed_layout)]
+                        gtk::DropDown::from_strings(&["Cards", "Compact"]) {},
+                    },
+
+                    gtk::Label {
+                        set_label: "Feed",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    #[name(infinite_scroll)]
                     gtk::CheckButton {
-                        set_active: get_prefs().infinite_scroll,
                         set_margin_all: 12,
                         set_label: Some("Infinite scroll"),
                         set_tooltip:"Fetch new content automatically when scrolling down",
-                        connect_toggled => move |checkbox| {
-                            let mut prefs = get_prefs();
-                            prefs.infinite_scroll = checkbox.is_active();
-                            save_prefs(&prefs);
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Default sort",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(default_sort)]
+                        gtk::DropDown::from_strings(SORT_LABELS) {},
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Default listing",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(default_listing)]
+                        gtk::DropDown::from_strings(&["All", "Local", "Subscribed"]) {},
+                    },
+
+                    gtk::Label {
+                        set_label: "Content",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "NSFW posts",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(nsfw)]
+                        gtk::DropDown::from_strings(&["Show", "Blur", "Hide"]) {},
+                    },
+                    #[name(load_images)]
+                    gtk::CheckButton {
+                        set_margin_all: 12,
+                        set_label: Some("Load images automatically"),
+                        set_tooltip: "Otherwise, thumbnails and images are only loaded once they're clicked",
+                    },
+
+                    gtk::Label {
+                        set_label: "Image uploads",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    #[name(process_images)]
+                    gtk::CheckButton {
+                        set_margin_all: 12,
+                        set_label: Some("Process images before uploading"),
+                        set_tooltip: "Remove metadata like the location, downsize and re-encode images and show a preview before uploading them",
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Maximum width and height",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(image_max_dimension)]
+                        gtk::SpinButton::with_range(256.0, 8192.0, 128.0) {},
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Format",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
                         },
+                        #[name(image_format)]
+                        gtk::DropDown::from_strings(&["JPEG", "PNG"]) {},
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "JPEG quality",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(image_quality)]
+                        gtk::SpinButton::with_range(1.0, 100.0, 1.0) {},
+                    },
+
+                    gtk::Label {
+                        set_label: "Notifications",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    #[name(notifications)]
+                    gtk::CheckButton {
+                        set_margin_all: 12,
+                        set_label: Some("Run in the background and show notifications"),
+                        set_tooltip: "Keep checking for new replies, mentions and messages after the window has been closed",
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Label {
+                            set_label: "Check every (minutes)",
+                            set_hexpand: true,
+                            set_halign: gtk::Align::Start,
+                        },
+                        #[name(notification_interval)]
+                        gtk::SpinButton::with_range(1.0, 120.0, 1.0) {},
+                    },
+                    #[name(notify_replies)]
+                    gtk::CheckButton {
+                        set_margin_start: 12,
+                        set_label: Some("Replies"),
+                    },
+                    #[name(notify_mentions)]
+                    gtk::CheckButton {
+                        set_margin_start: 12,
+                        set_label: Some("Mentions"),
+                    },
+                    #[name(notify_private_messages)]
+                    gtk::CheckButton {
+                        set_margin_start: 12,
+                        set_label: Some("Private messages"),
+                    },
+                    gtk::Label {
+                        set_label: "Accounts",
+                        set_halign: gtk::Align::Start,
+                        set_margin_start: 12,
+                        set_margin_top: 5,
+                    },
+                    #[local_ref]
+                    notification_accounts -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+
+                    gtk::Label {
+                        set_label: "Backup",
+                        add_css_class: "font-bold",
+                        set_halign: gtk::Align::Start,
+                        set_margin_top: 10,
+                    },
+                    gtk::Label {
+                        set_label: "Save the accounts, preferences, drafts and uploads into a single file, e.g. to move them to another machine",
+                        set_wrap: true,
+                        set_halign: gtk::Align::Start,
+                        set_margin_start: 12,
+                        add_css_class: "dim-label",
+                    },
+                    #[name(include_tokens)]
+                    gtk::CheckButton {
+                        set_margin_all: 12,
+                        set_label: Some("Include login tokens"),
+                        set_tooltip: "Otherwise, the accounts need to log in again after importing. Anyone with the file can use the accounts whose tokens are included",
+                    },
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 10,
+                        set_margin_start: 12,
+
+                        gtk::Button {
+                            set_label: "Export…",
+                            connect_clicked[sender, include_tokens] => move |_| {
+                                sender.input(SettingsInput::ChooseExportFile(include_tokens.is_active()));
+                            },
+                        },
+                        gtk::Button {
+                            set_label: "Import…",
+                            connect_clicked => SettingsInput::ChooseImportFile,
+                        },
+                    },
+                    gtk::Label {
+                        add_css_class: "error",
+                        set_wrap: true,
+                        set_halign: gtk::Align::Start,
+                        set_margin_start: 12,
+                        #[watch]
+                        set_visible: model.backup_error.is_some(),
+                        #[watch]
+                        set_text: model.backup_error.as_deref().unwrap_or_default(),
+                    },
+                    gtk::Label {
+                        set_wrap: true,
+                        set_halign: gtk::Align::Start,
+                        set_margin_start: 12,
+                        #[watch]
+                        set_visible: model.backup_info.is_some(),
+                        #[watch]
+                        set_text: model.backup_info.as_deref().unwrap_or_default(),
                     },
                 }
             }
@@ -63,18 +370,229 @@ impl SimpleComponent for Settings {
         root: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = Self { visible: false };
+        let model = Self {
+            visible: false,
+            notification_accounts: gtk::Box::default(),
+            pending_import: None,
+            backup_error: None,
+            backup_info: None,
+            gsettings: gsettings::settings(),
+        };
+        let notification_accounts = &model.notification_accounts;
         let widgets = view_output!();
+
+        let settings = &model.gsettings;
+        bind_dropdown(
+            settings,
+            "theme",
+            &widgets.theme,
+            &["system", "light", "dark"],
+        );
+        bind_dropdown(
+            settings,
+            "feed-layout",
+            &widgets.feed_layout,
+            &["cards", "compact"],
+        );
+        settings
+            .bind("infinite-scroll", &widgets.infinite_scroll, "active")
+            .build();
+        bind_dropdown(
+            settings,
+            "default-sort",
+            &widgets.default_sort,
+            SORT_CHOICES,
+        );
+        bind_dropdown(
+            settings,
+            "default-listing",
+            &widgets.default_listing,
+            &["All", "Local", "Subscribed"],
+        );
+        bind_dropdown(settings, "nsfw", &widgets.nsfw, &["show", "blur", "hide"]);
+        settings
+            .bind("load-images", &widgets.load_images, "active")
+            .build();
+        settings
+            .bind("process-images", &widgets.process_images, "active")
+            .build();
+        settings
+            .bind("image-max-dimension", &widgets.image_max_dimension, "value")
+            .build();
+        bind_dropdown(
+            settings,
+            "image-format",
+            &widgets.image_format,
+            &["jpeg", "png"],
+        );
+        settings
+            .bind("image-quality", &widgets.image_quality, "value")
+            .build();
+        settings
+            .bind("notifications", &widgets.notifications, "active")
+            .build();
+        settings
+            .bind(
+                "notification-interval",
+                &widgets.notification_interval,
+                "value",
+            )
+            .build();
+        settings
+            .bind("notify-replies", &widgets.notify_replies, "active")
+            .build();
+        settings
+            .bind("notify-mentions", &widgets.notify_mentions, "active")
+            .build();
+        settings
+            .bind(
+                "notify-private-messages",
+                &widgets.notify_private_messages,
+                "active",
+            )
+            .build();
+
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
             SettingsInput::Show => {
+                self.update_notification_accounts();
+                self.backup_error = None;
+                self.backup_info = None;
                 self.visible = true;
             }
             SettingsInput::Hide => {
                 self.visible = false;
+                // the notification settings decide whether the app keeps running in the background
+                sender
+                    .output_sender()
+                    .emit(crate::AppMsg::UpdateBackgroundMode);
+            }
+            SettingsInput::ChooseExportFile(include_tokens) => {
+                let buttons = [
+                    ("_Cancel", ResponseType::Cancel),
+                    ("_Save", ResponseType::Accept),
+                ];
+                let dialog = gtk::FileChooserDialog::new(
+                    Some("Export backup"),
+                    relm4::main_application().active_window().as_ref(),
+                    gtk::FileChooserAction::Save,
+                    &buttons,
+                );
+                dialog.set_current_name(&format!(
+                    "lemoa-backup-{}.json",
+                    chrono::Local::now().format("%Y-%m-%d")
+                ));
+                dialog.run_async(move |dialog, result| {
+                    if result == ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                            sender.input(SettingsInput::Export(path, include_tokens));
+                        }
+                    }
+                    dialog.destroy();
+                });
+            }
+            SettingsInput::Export(path, include_tokens) => {
+                match backup::export(&path, include_tokens) {
+                    Ok(_) => {
+                        self.backup_error = None;
+                        self.backup_info =
+                            Some(format!("The backup has been saved to {}", path.display()));
+                    }
+                    Err(err) => {
+                        self.backup_info = None;
+                        self.backup_error = Some(err.to_string());
+                    }
+                }
+            }
+            SettingsInput::ChooseImportFile => {
+                let buttons = [
+                    ("_Cancel", ResponseType::Cancel),
+                    ("_Okay", ResponseType::Accept),
+                ];
+                let dialog = gtk::FileChooserDialog::new(
+                    Some("Import backup"),
+                    relm4::main_application().active_window().as_ref(),
+                    gtk::FileChooserAction::Open,
+                    &buttons,
+                );
+                let json_filter = gtk::FileFilter::new();
+                json_filter.add_mime_type("application/json");
+                dialog.add_filter(&json_filter);
+                dialog.run_async(move |dialog, result| {
+                    if result == ResponseType::Accept {
+                        if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                            sender.input(SettingsInput::ReadBackup(path));
+                        }
+                    }
+                    dialog.destroy();
+                });
+            }
+            SettingsInput::ReadBackup(path) => {
+                self.backup_info = None;
+                let backup = match backup::read(&path) {
+                    Ok(backup) => backup,
+                    Err(err) => {
+                        self.backup_error = Some(err.to_string());
+                        return;
+                    }
+                };
+                self.backup_error = None;
+                // nothing is merged before the user has seen what would change
+                let summary = backup.summary();
+                let has_conflicts = summary.has_conflicts();
+                let mut text = summary.description();
+                let buttons = if has_conflicts {
+                    text.push_str(
+                        "\n\nKeep the existing data or replace it with the imported one?",
+                    );
+                    vec![
+                        ("_Cancel", ResponseType::Cancel),
+                        ("_Keep Existing", ResponseType::Reject),
+                        ("_Use Imported", ResponseType::Accept),
+                    ]
+                } else {
+                    vec![
+                        ("_Cancel", ResponseType::Cancel),
+                        ("_Import", ResponseType::Accept),
+                    ]
+                };
+                self.pending_import = Some(backup);
+                let dialog = gtk::MessageDialog::builder()
+                    .modal(true)
+                    .message_type(gtk::MessageType::Question)
+                    .text("Import backup")
+                    .secondary_text(text)
+                    .build();
+                dialog.set_transient_for(relm4::main_application().active_window().as_ref());
+                dialog.add_buttons(&buttons);
+                dialog.run_async(move |dialog, result| {
+                    let resolution = match result {
+                        ResponseType::Accept if has_conflicts => {
+                            Some(ConflictResolution::UseImported)
+                        }
+                        ResponseType::Accept | ResponseType::Reject => {
+                            Some(ConflictResolution::KeepExisting)
+                        }
+                        _ => None,
+                    };
+                    sender.input(SettingsInput::Import(resolution));
+                    dialog.destroy();
+                });
+            }
+            SettingsInput::Import(resolution) => {
+                let Some(backup) = self.pending_import.take() else {
+                    return;
+                };
+                let Some(resolution) = resolution else {
+                    return;
+                };
+                backup.import(resolution);
+                self.update_notification_accounts();
+                self.backup_info = Some("The backup has been imported".to_string());
+                sender.output_sender().emit(crate::AppMsg::BackupImported);
             }
         }
     }
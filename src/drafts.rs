@@ -0,0 +1,132 @@
+use crate::config::APP_ID;
+use crate::dialogs::editor::EditorData;
+use crate::gtk::glib;
+use crate::settings;
+use lemmy_api_common::lemmy_db_schema::newtypes::{CommentId, CommunityId, PersonId, PostId};
+use serde::{Deserialize, Serialize};
+use std::{fs::File, path::PathBuf};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum DraftTarget {
+    NewPost(CommunityId),
+    EditPost(PostId),
+    Comment {
+        post_id: PostId,
+        parent_id: Option<CommentId>,
+    },
+    EditComment {
+        post_id: PostId,
+        comment_id: CommentId,
+    },
+    PrivateMessage(PersonId),
+}
+
+impl DraftTarget {
+    pub fn description(&self) -> String {
+        match self {
+            DraftTarget::NewPost(community_id) => {
+                format!("New post in community #{}", community_id.0)
+            }
+            DraftTarget::EditPost(post_id) => format!("Edit of post #{}", post_id.0),
+            DraftTarget::Comment {
+                post_id,
+                parent_id: None,
+            } => format!("Comment on post #{}", post_id.0),
+            DraftTarget::Comment {
+                parent_id: Some(parent_id),
+                ..
+            } => format!("Reply to comment #{}", parent_id.0),
+            DraftTarget::EditComment { comment_id, .. } => {
+                format!("Edit of comment #{}", comment_id.0)
+            }
+            DraftTarget::PrivateMessage(person_id) => {
+                format!("Private message to person #{}", person_id.0)
+            }
+        }
+    }
+
+    // the page that has to be opened in order to continue editing the draft
+    pub fn app_message(&self) -> crate::AppMsg {
+        match *self {
+            DraftTarget::NewPost(community_id) => crate::AppMsg::OpenCommunity(community_id),
+            DraftTarget::EditPost(post_id)
+            | DraftTarget::Comment { post_id, .. }
+            | DraftTarget::EditComment { post_id, .. } => crate::AppMsg::OpenPost(post_id),
+            DraftTarget::PrivateMessage(person_id) => crate::AppMsg::OpenPerson(person_id),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Draft {
+    pub target: DraftTarget,
+    // ids are only unique per instance, hence the instance is stored too
+    pub instance_url: String,
+    pub name: String,
+    pub body: String,
+    pub url: Option<String>,
+    pub updated: chrono::DateTime<chrono::Utc>,
+}
+
+pub fn drafts_path() -> PathBuf {
+    let mut path = glib::user_data_dir();
+    path.push(APP_ID);
+    std::fs::create_dir_all(&path).expect("Could not create directory.");
+    path.push("drafts.json");
+    path
+}
+
+pub fn save_drafts(drafts: &Vec<Draft>) {
+    let file = File::create(drafts_path()).expect("Could not create json file.");
+    serde_json::to_writer(file, drafts).expect("Could not write data to json file");
+}
+
+pub fn get_all_drafts() -> Vec<Draft> {
+    if let Ok(file) = File::open(drafts_path()) {
+        if let Ok(drafts) = serde_json::from_reader(file) {
+            return drafts;
+        }
+    }
+    vec![]
+}
+
+fn is_current_instance(draft: &Draft, instance_url: &str) -> bool {
+    draft.instance_url == instance_url
+}
+
+// all drafts of the current instance, the most recently updated ones first
+pub fn get_drafts() -> Vec<Draft> {
+    let instance_url = settings::get_current_account().instance_url;
+    let mut drafts = get_all_drafts();
+    drafts.retain(|draft| is_current_instance(draft, &instance_url));
+    drafts.sort_by_key(|draft| std::cmp::Reverse(draft.updated));
+    drafts
+}
+
+pub fn get_draft(target: &DraftTarget) -> Option<Draft> {
+    get_drafts()
+        .into_iter()
+        .find(|draft| &draft.target == target)
+}
+
+pub fn save_draft(target: DraftTarget, data: EditorData) {
+    let instance_url = settings::get_current_account().instance_url;
+    let mut drafts = get_all_drafts();
+    drafts.retain(|draft| !(draft.target == target && is_current_instance(draft, &instance_url)));
+    drafts.push(Draft {
+        target,
+        instance_url,
+        name: data.name,
+        body: data.body,
+        url: data.url.map(|url| url.to_string()),
+        updated: chrono::Utc::now(),
+    });
+    save_drafts(&drafts);
+}
+
+pub fn remove_draft(target: &DraftTarget) {
+    let instance_url = settings::get_current_account().instance_url;
+    let mut drafts = get_all_drafts();
+    drafts.retain(|draft| !(&draft.target == target && is_current_instance(draft, &instance_url)));
+    save_drafts(&drafts);
+}
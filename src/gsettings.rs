@@ -0,0 +1,161 @@
+// the preferences of the ui, stored with gsettings
+// only the account data is kept in the app's own store, see settings.rs
+use crate::config::APP_ID;
+use crate::gtk::{self, gio, glib};
+use gio::prelude::*;
+use lemmy_api_common::lemmy_db_schema::{ListingType, SortType};
+use relm4::{adw, once_cell::sync::Lazy};
+use std::{path::PathBuf, str::FromStr};
+
+// compiled by build.rs, for when the schema isn't installed, e.g. when started with cargo run
+static COMPILED_SCHEMA: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/schemas/gschemas.compiled"));
+
+static FALLBACK_SCHEMA_DIR: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let installed = gio::SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(APP_ID, true))
+        .is_some();
+    if installed {
+        return None;
+    }
+    let mut path = glib::user_cache_dir();
+    path.push(APP_ID);
+    path.push("schemas");
+    std::fs::create_dir_all(&path).expect("Could not create directory.");
+    std::fs::write(path.join("gschemas.compiled"), COMPILED_SCHEMA)
+        .expect("Could not write the gsettings schema");
+    Some(path)
+});
+
+pub fn settings() -> gio::Settings {
+    let Some(path) = FALLBACK_SCHEMA_DIR.as_ref() else {
+        return gio::Settings::new(APP_ID);
+    };
+    let schema = gio::SettingsSchemaSource::from_directory(
+        path,
+        gio::SettingsSchemaSource::default().as_ref(),
+        false,
+    )
+    .ok()
+    .and_then(|source| source.lookup(APP_ID, false))
+    .expect("The gsettings schema is neither installed nor bundled");
+    gio::Settings::new_full(&schema, None::<&gio::SettingsBackend>, None)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImageProcessing {
+    pub enabled: bool,
+    // maximum width and height of uploaded images in pixels
+    pub max_dimension: u32,
+    pub format: ImageFormat,
+    // only used by lossy formats, ranges from 1 to 100
+    pub quality: u8,
+}
+
+#[derive(Clone, Debug)]
+pub struct Notifications {
+    // keeps the app running in the background to check for new notifications
+    pub enabled: bool,
+    // how often to check for new notifications, in minutes
+    pub interval: u32,
+    pub replies: bool,
+    pub mentions: bool,
+    pub private_messages: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NsfwHandling {
+    Show,
+    Blur,
+    Hide,
+}
+
+pub fn image_processing() -> ImageProcessing {
+    let settings = settings();
+    ImageProcessing {
+        enabled: settings.boolean("process-images"),
+        max_dimension: settings.uint("image-max-dimension"),
+        format: match settings.string("image-format").as_str() {
+            "png" => ImageFormat::Png,
+            _ => ImageFormat::Jpeg,
+        },
+        quality: settings.uint("image-quality") as u8,
+    }
+}
+
+pub fn notifications() -> Notifications {
+    let settings = settings();
+    Notifications {
+        enabled: settings.boolean("notifications"),
+        interval: settings.uint("notification-interval"),
+        replies: settings.boolean("notify-replies"),
+        mentions: settings.boolean("notify-mentions"),
+        private_messages: settings.boolean("notify-private-messages"),
+    }
+}
+
+pub fn infinite_scroll() -> bool {
+    settings().boolean("infinite-scroll")
+}
+
+// the compact layout doesn't show the thumbnails of posts
+pub fn compact_feed() -> bool {
+    settings().string("feed-layout") == "compact"
+}
+
+pub fn load_images() -> bool {
+    settings().boolean("load-images")
+}
+
+pub fn nsfw_handling() -> NsfwHandling {
+    match settings().string("nsfw").as_str() {
+        "show" => NsfwHandling::Show,
+        "hide" => NsfwHandling::Hide,
+        _ => NsfwHandling::Blur,
+    }
+}
+
+// the choices in the schema are named like the variants of the lemmy types
+pub fn default_sort() -> SortType {
+    SortType::from_str(&settings().string("default-sort")).unwrap_or(SortType::Hot)
+}
+
+pub fn default_listing() -> ListingType {
+    ListingType::from_str(&settings().string("default-listing")).unwrap_or(ListingType::Local)
+}
+
+pub fn apply_theme(settings: &gio::Settings) {
+    let color_scheme = match settings.string("theme").as_str() {
+        "light" => adw::ColorScheme::ForceLight,
+        "dark" => adw::ColorScheme::ForceDark,
+        _ => adw::ColorScheme::Default,
+    };
+    adw::StyleManager::default().set_color_scheme(color_scheme);
+}
+
+// binds a dropdown to a string key, the choices need to be in the same order as the items of the dropdown
+pub fn bind_dropdown(
+    settings: &gio::Settings,
+    key: &str,
+    dropdown: &gtk::DropDown,
+    choices: &'static [&'static str],
+) {
+    settings
+        .bind(key, dropdown, "selected")
+        .mapping(move |variant, _| {
+            let value = variant.str()?;
+            let index = choices.iter().position(|choice| *choice == value)? as u32;
+            Some(index.to_value())
+        })
+        .set_mapping(move |value, _| {
+            let index = value.get::<u32>().ok()? as usize;
+            Some(choices.get(index)?.to_variant())
+        })
+        .build();
+}
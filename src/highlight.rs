@@ -0,0 +1,84 @@
+// syntax highlighting of fenced code blocks in posts and comments
+use gtk::prelude::*;
+use relm4::{adw, gtk, once_cell::sync::Lazy};
+use std::collections::HashMap;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Style, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+// loading the bundled definitions takes a moment, hence they're only loaded once needed
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+// the info string of a fence might contain more than the language, e.g. "rust,ignore"
+fn language_token(language: &str) -> &str {
+    language
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('.')
+}
+
+fn create_tag(buffer: &gtk::TextBuffer, style: &Style) -> gtk::TextTag {
+    let color = style.foreground;
+    let tag = gtk::TextTag::builder()
+        .foreground(format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b))
+        .build();
+    if style.font_style.contains(FontStyle::BOLD) {
+        tag.set_weight(700);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        tag.set_style(gtk::pango::Style::Italic);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        tag.set_underline(gtk::pango::Underline::Single);
+    }
+    buffer.tag_table().add(&tag);
+    tag
+}
+
+// fills the buffer with the code, highlighted if the language is known
+// returns whether the language was recognized
+pub fn highlight(buffer: &gtk::TextBuffer, language: Option<&str>, code: &str) -> bool {
+    let syntax = language
+        .map(language_token)
+        .filter(|token| !token.is_empty())
+        .and_then(|token| SYNTAX_SET.find_syntax_by_token(token));
+    let Some(syntax) = syntax else {
+        buffer.set_text(code);
+        return false;
+    };
+
+    let theme_name = if adw::StyleManager::default().is_dark() {
+        DARK_THEME
+    } else {
+        LIGHT_THEME
+    };
+    let mut highlighter = HighlightLines::new(syntax, &THEME_SET.themes[theme_name]);
+    // the same style is used by many tokens, so the tags are shared between them
+    let mut tags: HashMap<(u8, u8, u8, u8), gtk::TextTag> = HashMap::new();
+
+    buffer.set_text("");
+    for line in LinesWithEndings::from(code) {
+        let Ok(ranges) = highlighter.highlight_line(line, &SYNTAX_SET) else {
+            // give up on the highlighting but still show the rest of the code
+            buffer.insert(&mut buffer.end_iter(), line);
+            continue;
+        };
+        for (style, text) in ranges {
+            let color = style.foreground;
+            let key = (color.r, color.g, color.b, style.font_style.bits());
+            let tag = tags
+                .entry(key)
+                .or_insert_with(|| create_tag(buffer, &style));
+            buffer.insert_with_tags(&mut buffer.end_iter(), text, &[&*tag]);
+        }
+    }
+    true
+}
@@ -0,0 +1,87 @@
+use crate::gsettings::{ImageFormat, ImageProcessing};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageOutputFormat};
+use std::io::Cursor;
+
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+// the orientation is stored in the exif data which gets dropped when re-encoding,
+// so it has to be applied to the pixels first
+fn apply_orientation(image: DynamicImage, data: &[u8]) -> DynamicImage {
+    let orientation = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(data))
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        });
+    match orientation {
+        Some(2) => image.fliph(),
+        Some(3) => image.rotate180(),
+        Some(4) => image.flipv(),
+        Some(5) => image.rotate90().fliph(),
+        Some(6) => image.rotate90(),
+        Some(7) => image.rotate270().fliph(),
+        Some(8) => image.rotate270(),
+        _ => image,
+    }
+}
+
+// an alpha channel alone doesn't mean anything is transparent, e.g. many screenshots have one
+fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX)
+}
+
+// decodes and re-encodes the image, which strips all metadata like the GPS location
+pub fn process_image(
+    data: &[u8],
+    options: &ImageProcessing,
+) -> Result<ProcessedImage, image::ImageError> {
+    let format = image::guess_format(data)?;
+    let image = image::load_from_memory_with_format(data, format)?;
+    // re-encoding would only keep the first frame of animations, hence gifs are uploaded as they are
+    if format == image::ImageFormat::Gif {
+        return Ok(ProcessedImage {
+            data: data.to_vec(),
+            mime_type: "image/gif",
+            width: image.width(),
+            height: image.height(),
+        });
+    }
+
+    let mut image = apply_orientation(image, data);
+    if image.width() > options.max_dimension || image.height() > options.max_dimension {
+        image = image.resize(
+            options.max_dimension,
+            options.max_dimension,
+            FilterType::Lanczos3,
+        );
+    }
+
+    let mut encoded = Vec::new();
+    let mime_type = match options.format {
+        // jpeg has no transparency, so such images are saved as png instead
+        ImageFormat::Jpeg if !has_transparency(&image) => {
+            let quality = options.quality.clamp(1, 100);
+            JpegEncoder::new_with_quality(&mut encoded, quality)
+                .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))?;
+            "image/jpeg"
+        }
+        _ => {
+            image.write_to(&mut Cursor::new(&mut encoded), ImageOutputFormat::Png)?;
+            "image/png"
+        }
+    };
+
+    Ok(ProcessedImage {
+        data: encoded,
+        mime_type,
+        width: image.width(),
+        height: image.height(),
+    })
+}
@@ -1,24 +1,44 @@
 pub mod api;
+pub mod backup;
 pub mod components;
 pub mod config;
+pub mod custom_emojis;
 pub mod dialogs;
+pub mod drafts;
+pub mod gsettings;
+pub mod highlight;
+pub mod image_processing;
+pub mod markdown;
+pub mod notifications;
+pub mod secrets;
 pub mod settings;
+#[cfg(test)]
+mod test_util;
+pub mod uploads;
 pub mod util;
 
 use api::{community::default_community, post::default_post, user::default_person};
 use components::{
-    accounts_page::AccountsPage,
+    accounts_page::{AccountsPage, AccountsPageInput},
+    blocks_page::{BlocksPage, BlocksPageInput},
     communities_page::{CommunitiesPage, CommunitiesPageInput},
     community_page::{self, CommunityPage},
+    conversation_page::{ConversationPage, ConversationPageInput},
+    drafts_page::{DraftsPage, DraftsPageInput},
     inbox_page::{InboxInput, InboxPage},
     instances_page::{InstancesPage, InstancesPageInput},
     loading_indicator::LoadingIndicator,
     post_page::{self, PostPage},
     posts_page::{PostsPage, PostsPageInput},
     profile_page::{ProfileInput, ProfilePage},
+    register_page::{RegisterPage, RegisterPageInput},
+    security_page::{SecurityPage, SecurityPageInput},
+    uploads_page::{UploadsPage, UploadsPageInput},
+    user_settings_page::{UserSettingsPage, UserSettingsPageInput},
 };
 use dialogs::{
     about::AboutDialog,
+    passphrase::{PassphraseDialog, PassphraseDialogInput},
     settings::Settings,
     site_info::{SiteInfo, SiteInfoInput},
 };
@@ -26,18 +46,26 @@ use gtk::prelude::*;
 use lemmy_api_common::{
     community::GetCommunityResponse,
     lemmy_db_schema::{
-        newtypes::{CommunityId, PersonId, PostId},
-        ListingType, SortType,
+        newtypes::{CommentId, CommunityId, PersonId, PostId},
+        source::{community::Community, person::Person},
+        ListingType,
     },
+    person::GetUnreadCountResponse,
     post::GetPostResponse,
+    sensitive::Sensitive,
 };
 use relm4::{
-    actions::{RelmAction, RelmActionGroup},
+    actions::{AccelsPlus, ActionGroupName, ActionName, RelmAction, RelmActionGroup},
+    gtk::{gio, glib, glib::clone},
     prelude::*,
     set_global_css,
 };
 
-use crate::components::login_page::LoginPage;
+use crate::components::login_page::{LoginPage, LoginPageInput};
+use crate::notifications::{InboxNotification, NotificationTarget};
+
+// how often the unread count of the inbox is refreshed, in seconds
+const UNREAD_COUNT_INTERVAL: u32 = 60;
 
 #[derive(Debug, Clone, Copy)]
 pub enum AppState {
@@ -49,10 +77,17 @@ pub enum AppState {
     Person,
     Post,
     Login,
+    Register,
+    Security,
+    UserSettings,
+    Blocks,
     Message,
     Inbox,
     Saved,
     AccountsPage,
+    Drafts,
+    Uploads,
+    Conversation,
 }
 
 struct App {
@@ -67,12 +102,73 @@ struct App {
     post_page: Controller<PostPage>,
     inbox_page: Controller<InboxPage>,
     login_page: Controller<LoginPage>,
+    register_page: Controller<RegisterPage>,
+    security_page: Controller<SecurityPage>,
+    user_settings_page: Controller<UserSettingsPage>,
+    blocks_page: Controller<BlocksPage>,
     accounts_page: Controller<AccountsPage>,
+    drafts_page: Controller<DraftsPage>,
+    uploads_page: Controller<UploadsPage>,
+    conversation_page: Controller<ConversationPage>,
     saved_page: Controller<ProfilePage>,
     settings_dialog: Controller<Settings>,
     about_dialog: Controller<AboutDialog>,
     site_info: Controller<SiteInfo>,
+    passphrase_dialog: Controller<PassphraseDialog>,
     logged_in: bool,
+    unread_count: i64,
+    main_window: gtk::ApplicationWindow,
+    // the window size and theme are bound to it as long as it's alive
+    gsettings: gio::Settings,
+    // keeps the app running after the window has been closed
+    hold_guard: Option<gio::ApplicationHoldGuard>,
+}
+
+impl App {
+    fn fetch_post(&mut self, post_id: PostId, sender: ComponentSender<Self>) {
+        self.state = AppState::Loading;
+        std::thread::spawn(move || {
+            let message = match api::post::get_post(post_id) {
+                Ok(post) => AppMsg::DoneFetchPost(post),
+                Err(err) => AppMsg::ShowMessage(err.to_string()),
+            };
+            sender.input(message);
+        });
+    }
+
+    // checks whether the tokens of the accounts are still valid, e.g. they might have been revoked in the meantime
+    fn validate_sessions(sender: &ComponentSender<Self>) {
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let accounts = settings::get_prefs().accounts;
+            for (index, account) in accounts.iter().enumerate() {
+                let Some(token) = account.jwt.clone() else {
+                    continue;
+                };
+                match api::auth::validate_session(account) {
+                    Ok(true) => {}
+                    Ok(false) => sender.input(AppMsg::SessionExpired(index, token)),
+                    // e.g. when offline, the token might still be valid
+                    Err(err) => println!("{}", err),
+                }
+            }
+        });
+    }
+
+    // only keep running in the background if there's anything to notify about
+    fn update_background_mode(&mut self) {
+        let background = gsettings::notifications().enabled
+            && settings::get_prefs()
+                .accounts
+                .iter()
+                .any(|account| account.jwt.is_some() && account.notifications);
+        self.main_window.set_hide_on_close(background);
+        if !background {
+            self.hold_guard = None;
+        } else if self.hold_guard.is_none() {
+            self.hold_guard = Some(relm4::main_application().hold());
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -86,10 +182,36 @@ pub enum AppMsg {
     DoneFetchCommunity(GetCommunityResponse),
     OpenPerson(PersonId),
     OpenPost(PostId),
+    // opens the post with only the thread of the given comment shown
+    OpenComment(PostId, CommentId),
     DoneFetchPost(GetPostResponse),
     OpenInbox,
     OpenSaved,
     OpenCommunities,
+    OpenDrafts,
+    OpenUploads,
+    OpenRegister,
+    OpenSecurity,
+    OpenUserSettings,
+    OpenBlocks,
+    // blocks the instance that the community belongs to
+    BlockInstance(Box<Community>),
+    OpenConversation(Box<Person>),
+    FetchUnreadCount,
+    UpdateUnreadCount(GetUnreadCountResponse),
+    UpdateBackgroundMode,
+    CheckNotifications,
+    ShowNotifications(Vec<InboxNotification>),
+    // the notification belongs to the account with the key of notifications::account_key
+    OpenNotification(String, NotificationTarget),
+    SecretsUnlocked,
+    // the token of the account at the index has been rejected by the server
+    SessionExpired(usize, Sensitive<String>),
+    SwitchAccount(usize),
+    // the accounts and preferences might have been replaced by the ones of a backup
+    BackupImported,
+    // switches to the account after the current one
+    CycleAccount,
     PopBackStack,
     UpdateState(AppState),
 }
@@ -104,7 +226,6 @@ impl SimpleComponent for App {
         #[root]
         main_window = gtk::ApplicationWindow {
             set_title: Some("Lemoa"),
-            set_default_size: (1400, 800),
 
             #[wrap(Some)]
             set_titlebar = &gtk::HeaderBar {
@@ -112,6 +233,11 @@ impl SimpleComponent for App {
                     set_icon_name: "view-more",
                     set_menu_model: Some(&menu_model),
                 },
+                #[name(account_button)]
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "avatar-default-symbolic",
+                    set_tooltip_text: Some("Switch account (Ctrl+Shift+A)"),
+                },
                 pack_start = &gtk::Button {
                     set_icon_name: "go-previous",
                     connect_clicked => AppMsg::PopBackStack,
@@ -127,10 +253,23 @@ impl SimpleComponent for App {
                     connect_clicked => AppMsg::OpenCommunities,
                 },
                 pack_start = &gtk::Button {
-                    set_label: "Inbox",
                     connect_clicked => AppMsg::OpenInbox,
                     #[watch]
                     set_visible: model.logged_in,
+
+                    gtk::Box {
+                        set_spacing: 5,
+                        gtk::Label {
+                            set_label: "Inbox",
+                        },
+                        gtk::Label {
+                            add_css_class: "inbox-badge",
+                            #[watch]
+                            set_label: &model.unread_count.to_string(),
+                            #[watch]
+                            set_visible: model.unread_count > 0,
+                        },
+                    },
                 },
                 pack_start = &gtk::Button {
                     set_label: "Saved",
@@ -157,6 +296,22 @@ impl SimpleComponent for App {
                     #[local_ref]
                     login_page -> gtk::Box {}
                 }
+                AppState::Register => gtk::Box {
+                    #[local_ref]
+                    register_page -> gtk::ScrolledWindow {}
+                }
+                AppState::Security => gtk::Box {
+                    #[local_ref]
+                    security_page -> gtk::ScrolledWindow {}
+                }
+                AppState::UserSettings => gtk::Box {
+                    #[local_ref]
+                    user_settings_page -> gtk::ScrolledWindow {}
+                }
+                AppState::Blocks => gtk::Box {
+                    #[local_ref]
+                    blocks_page -> gtk::Box {}
+                }
                 AppState::Communities => gtk::Box {
                     #[local_ref]
                     communities_page -> gtk::Box {}
@@ -213,6 +368,26 @@ impl SimpleComponent for App {
                         accounts_page -> gtk::Box {}
                     }
                 }
+                AppState::Drafts => {
+                    gtk::Box {
+                        #[local_ref]
+                        drafts_page -> gtk::Box {}
+                    }
+                }
+                AppState::Uploads => {
+                    gtk::Box {
+                        #[local_ref]
+                        uploads_page -> gtk::Box {}
+                    }
+                }
+                AppState::Conversation => {
+                    gtk::Box {
+                        #[local_ref]
+                        conversation_page -> gtk::Box {
+                            set_hexpand: true,
+                        }
+                    }
+                }
             }
         }
     }
@@ -221,11 +396,16 @@ impl SimpleComponent for App {
         menu_model: {
             "Change Instance" => ChangeInstanceAction,
             "Accounts" => AccountsAction,
+            "Drafts" => DraftsAction,
+            "Uploads" => UploadsAction,
             "Login" => LoginAction,
             "Profile" => ProfileAction,
+            "Account Settings" => UserSettingsAction,
+            "Blocked" => BlocksAction,
             "Site Info" => SiteInfoAction,
             "Settings" => SettingsAction,
-            "About" => AboutAction
+            "About" => AboutAction,
+            "Quit" => QuitAction
         }
     }
 
@@ -265,16 +445,39 @@ impl SimpleComponent for App {
         let communities_page = CommunitiesPage::builder()
             .launch(())
             .forward(sender.input_sender(), |msg| msg);
-        let settings_dialog = dialogs::settings::Settings::builder().launch(()).detach();
+        let settings_dialog = dialogs::settings::Settings::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
         let about_dialog = AboutDialog::builder()
             .launch(root.toplevel_window().unwrap())
             .detach();
         let login_page = LoginPage::builder()
             .launch(())
             .forward(sender.input_sender(), |msg| msg);
+        let register_page = RegisterPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+        let security_page = SecurityPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+        let user_settings_page = UserSettingsPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+        let blocks_page = BlocksPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
         let accounts_page = AccountsPage::builder()
             .launch(())
             .forward(sender.input_sender(), |msg| msg);
+        let drafts_page = DraftsPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+        let uploads_page = UploadsPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
+        let conversation_page = ConversationPage::builder()
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
         let saved_page = ProfilePage::builder()
             .launch((default_person(), true))
             .forward(sender.input_sender(), |msg| msg);
@@ -282,8 +485,21 @@ impl SimpleComponent for App {
             .transient_for(root)
             .launch(())
             .forward(sender.input_sender(), |msg| msg);
+        let passphrase_dialog = PassphraseDialog::builder()
+            .transient_for(root)
+            .launch(())
+            .forward(sender.input_sender(), |msg| msg);
 
-        let model = App {
+        // without a keyring, the saved login tokens can only be read once the user entered the passphrase
+        let has_tokens = settings::get_prefs()
+            .accounts
+            .iter()
+            .any(|account| account.jwt.is_some() || account.token_ref.is_some());
+        if secrets::is_locked() && (secrets::has_encrypted_file() || has_tokens) {
+            passphrase_dialog.emit(PassphraseDialogInput::Show);
+        }
+
+        let mut model = App {
             state,
             back_queue: vec![],
             posts_page,
@@ -294,19 +510,87 @@ impl SimpleComponent for App {
             inbox_page,
             communities_page,
             login_page,
+            register_page,
+            security_page,
+            user_settings_page,
+            blocks_page,
             accounts_page,
+            drafts_page,
+            uploads_page,
+            conversation_page,
             message: None,
             about_dialog,
             settings_dialog,
             saved_page,
             site_info,
+            passphrase_dialog,
             logged_in,
+            unread_count: 0,
+            main_window: root.clone(),
+            hold_guard: None,
+            gsettings: gsettings::settings(),
         };
+        model.update_background_mode();
+
+        let settings = &model.gsettings;
+        settings.bind("window-width", root, "default-width").build();
+        settings
+            .bind("window-height", root, "default-height")
+            .build();
+        settings.bind("window-maximized", root, "maximized").build();
+        gsettings::apply_theme(settings);
+        settings.connect_changed(Some("theme"), |settings, _| {
+            gsettings::apply_theme(settings)
+        });
+
+        // e.g. if data.json got corrupted, the user should know why the accounts are gone
+        if let Some(err) = settings::take_load_error() {
+            let dialog = gtk::MessageDialog::builder()
+                .transient_for(root)
+                .modal(true)
+                .message_type(gtk::MessageType::Error)
+                .buttons(gtk::ButtonsType::Ok)
+                .text("Could not load the preferences")
+                .secondary_text(err)
+                .build();
+            dialog.connect_response(|dialog, _| dialog.close());
+            dialog.present();
+        }
+
+        let app = relm4::main_application();
+        // show the window again when the app is launched while running in the background
+        app.connect_activate(clone!(@weak root => move |_| root.present()));
+        let open_notification_action =
+            gio::SimpleAction::new("open-notification", Some(glib::VariantTy::STRING));
+        {
+            let sender = sender.clone();
+            open_notification_action.connect_activate(move |_, target| {
+                let target = target
+                    .and_then(|target| target.get::<String>())
+                    .and_then(|target| notifications::parse_action_target(&target));
+                if let Some((account, target)) = target {
+                    sender.input(AppMsg::OpenNotification(account, target));
+                }
+            });
+        }
+        app.add_action(&open_notification_action);
+        sender.input(AppMsg::CheckNotifications);
+
+        // keep the unread count of the inbox up to date
+        sender.input(AppMsg::FetchUnreadCount);
+        {
+            let sender = sender.clone();
+            glib::timeout_add_seconds_local(UNREAD_COUNT_INTERVAL, move || {
+                sender.input(AppMsg::FetchUnreadCount);
+                glib::Continue(true)
+            });
+        }
 
         // fetch posts if that's the initial page
         if !current_account.instance_url.is_empty() {
             sender.input(AppMsg::OpenPosts)
         };
+        App::validate_sessions(&sender);
 
         // setup all widgets and different stack pages
         let posts_page = model.posts_page.widget();
@@ -317,7 +601,14 @@ impl SimpleComponent for App {
         let inbox_page = model.inbox_page.widget();
         let communities_page = model.communities_page.widget();
         let login_page = model.login_page.widget();
+        let register_page = model.register_page.widget();
+        let security_page = model.security_page.widget();
+        let user_settings_page = model.user_settings_page.widget();
+        let blocks_page = model.blocks_page.widget();
         let accounts_page = model.accounts_page.widget();
+        let drafts_page = model.drafts_page.widget();
+        let uploads_page = model.uploads_page.widget();
+        let conversation_page = model.conversation_page.widget();
         let saved_page = model.saved_page.widget();
 
         let widgets = view_output!();
@@ -335,6 +626,34 @@ impl SimpleComponent for App {
                 sender.input(AppMsg::UpdateState(AppState::AccountsPage));
             })
         };
+        let drafts_action: RelmAction<DraftsAction> = {
+            let sender = sender.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.input(AppMsg::OpenDrafts);
+            })
+        };
+        let uploads_action: RelmAction<UploadsAction> = {
+            let sender = sender.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.input(AppMsg::OpenUploads);
+            })
+        };
+        let user_settings_action: RelmAction<UserSettingsAction> = {
+            let sender = sender.clone();
+            RelmAction::new_stateless(move |_| {
+                if settings::get_current_account().jwt.is_some() {
+                    sender.input(AppMsg::OpenUserSettings);
+                }
+            })
+        };
+        let blocks_action: RelmAction<BlocksAction> = {
+            let sender = sender.clone();
+            RelmAction::new_stateless(move |_| {
+                if settings::get_current_account().jwt.is_some() {
+                    sender.input(AppMsg::OpenBlocks);
+                }
+            })
+        };
         let profile_action: RelmAction<ProfileAction> = {
             let sender = sender.clone();
             RelmAction::new_stateless(move |_| {
@@ -344,6 +663,20 @@ impl SimpleComponent for App {
                 }
             })
         };
+        let switch_account_action: RelmAction<SwitchAccountAction> = {
+            let sender = sender.clone();
+            let current_index = settings::get_prefs().current_account_index;
+            RelmAction::new_stateful_with_target_value(&current_index, move |_, state, index| {
+                *state = index;
+                sender.input(AppMsg::SwitchAccount(index as usize));
+            })
+        };
+        let next_account_action: RelmAction<NextAccountAction> = {
+            let sender = sender.clone();
+            RelmAction::new_stateless(move |_| {
+                sender.input(AppMsg::CycleAccount);
+            })
+        };
         let login_action: RelmAction<LoginAction> = {
             RelmAction::new_stateless(move |_| {
                 sender.input(AppMsg::UpdateState(AppState::Login));
@@ -373,12 +706,55 @@ impl SimpleComponent for App {
         let mut group = RelmActionGroup::<WindowActionGroup>::new();
         group.add_action(instance_action);
         group.add_action(accounts_action);
+        group.add_action(drafts_action);
+        group.add_action(uploads_action);
         group.add_action(profile_action);
+        group.add_action(user_settings_action);
+        group.add_action(blocks_action);
+        group.add_action(switch_account_action);
+        group.add_action(next_account_action);
         group.add_action(login_action);
         group.add_action(site_info_action);
         group.add_action(settings_action);
         group.add_action(about_action);
-        group.register_for_widget(&widgets.main_window);
+        // closing the window only hides it while running in the background
+        group.add_action(RelmAction::<QuitAction>::new_stateless(|_| {
+            relm4::main_application().quit();
+        }));
+        // the group is kept to update the checked account of the switcher
+        let group = group.into_action_group();
+        widgets
+            .main_window
+            .insert_action_group(WindowActionGroup::NAME, Some(&group));
+
+        // the accounts are listed again every time the switcher is opened, the current one is checked
+        let account_menu = gio::Menu::new();
+        widgets.account_button.set_menu_model(Some(&account_menu));
+        widgets.account_button.set_create_popup_func(move |_| {
+            let prefs = settings::get_prefs();
+            group.change_action_state(
+                SwitchAccountAction::NAME,
+                &prefs.current_account_index.to_variant(),
+            );
+            account_menu.remove_all();
+            let accounts = gio::Menu::new();
+            for (index, account) in prefs.accounts.iter().enumerate() {
+                accounts.append_item(
+                    &RelmAction::<SwitchAccountAction>::to_menu_item_with_target_value(
+                        &account.label(),
+                        &(index as u32),
+                    ),
+                );
+            }
+            account_menu.append_section(None, &accounts);
+            let manage = gio::Menu::new();
+            manage.append_item(&RelmAction::<AccountsAction>::to_menu_item(
+                "Manage Accounts",
+            ));
+            account_menu.append_section(None, &manage);
+        });
+        relm4::main_application()
+            .set_accelerators_for_action::<NextAccountAction>(&["<Control><Shift>a"]);
 
         ComponentParts { model, widgets }
     }
@@ -391,6 +767,8 @@ impl SimpleComponent for App {
             | AppMsg::OpenPerson(_)
             | AppMsg::DoneFetchPost(_)
             | AppMsg::OpenPosts
+            | AppMsg::OpenInbox
+            | AppMsg::OpenConversation(_)
             | AppMsg::ShowMessage(_) => self.back_queue.push(msg.clone()),
             _ => {}
         }
@@ -436,14 +814,14 @@ impl SimpleComponent for App {
                 self.state = AppState::Community;
             }
             AppMsg::OpenPost(post_id) => {
-                self.state = AppState::Loading;
-                std::thread::spawn(move || {
-                    let message = match api::post::get_post(post_id) {
-                        Ok(post) => AppMsg::DoneFetchPost(post),
-                        Err(err) => AppMsg::ShowMessage(err.to_string()),
-                    };
-                    sender.input(message);
-                });
+                self.post_page
+                    .emit(post_page::PostPageInput::UpdateContext(None));
+                self.fetch_post(post_id, sender);
+            }
+            AppMsg::OpenComment(post_id, comment_id) => {
+                self.post_page
+                    .emit(post_page::PostPageInput::UpdateContext(Some(comment_id)));
+                self.fetch_post(post_id, sender);
             }
             AppMsg::DoneFetchPost(post) => {
                 self.post_page
@@ -453,11 +831,22 @@ impl SimpleComponent for App {
             }
             AppMsg::Logout => {
                 let mut account = settings::get_current_account();
+                if account.jwt.is_some() {
+                    let account = account.clone();
+                    std::thread::spawn(move || {
+                        if let Err(err) = api::auth::logout(&account) {
+                            println!("{}", err);
+                        }
+                    });
+                }
                 account.jwt = None;
+                account.session_expired = false;
                 account.name = "".to_string();
                 account.id = 0;
                 settings::update_current_account(account);
                 self.logged_in = false;
+                self.unread_count = 0;
+                self.update_background_mode();
             }
             AppMsg::ShowMessage(message) => {
                 self.message = Some(message);
@@ -466,6 +855,58 @@ impl SimpleComponent for App {
             AppMsg::OpenInbox => {
                 self.state = AppState::Inbox;
                 self.inbox_page.sender().emit(InboxInput::FetchInbox);
+                sender.input(AppMsg::FetchUnreadCount);
+            }
+            AppMsg::OpenDrafts => {
+                self.state = AppState::Drafts;
+                self.drafts_page.sender().emit(DraftsPageInput::Update);
+            }
+            AppMsg::OpenUploads => {
+                self.state = AppState::Uploads;
+                self.uploads_page.sender().emit(UploadsPageInput::Update);
+            }
+            AppMsg::OpenRegister => {
+                self.state = AppState::Register;
+                self.register_page.sender().emit(RegisterPageInput::Load);
+            }
+            AppMsg::OpenSecurity => {
+                self.state = AppState::Security;
+                self.security_page.sender().emit(SecurityPageInput::Load);
+            }
+            AppMsg::OpenUserSettings => {
+                self.state = AppState::UserSettings;
+                self.user_settings_page
+                    .sender()
+                    .emit(UserSettingsPageInput::Load);
+            }
+            AppMsg::OpenBlocks => {
+                self.state = AppState::Blocks;
+                self.blocks_page.sender().emit(BlocksPageInput::Update);
+            }
+            AppMsg::BlockInstance(community) => {
+                let domain = community
+                    .actor_id
+                    .inner()
+                    .host_str()
+                    .unwrap_or_default()
+                    .to_string();
+                std::thread::spawn(move || {
+                    let message = match api::instances::block_instance(community.instance_id, true)
+                    {
+                        Ok(_) => AppMsg::ShowMessage(format!(
+                            "{} has been blocked, you can unblock it again under Blocked in the menu.",
+                            domain
+                        )),
+                        Err(err) => AppMsg::ShowMessage(err.to_string()),
+                    };
+                    sender.input(message);
+                });
+            }
+            AppMsg::OpenConversation(participant) => {
+                self.state = AppState::Conversation;
+                self.conversation_page
+                    .sender()
+                    .emit(ConversationPageInput::Open(participant));
             }
             AppMsg::OpenSaved => {
                 let person_id = PersonId(settings::get_current_account().id);
@@ -474,11 +915,162 @@ impl SimpleComponent for App {
                     .sender()
                     .emit(ProfileInput::FetchPerson(Some(person_id)));
             }
+            AppMsg::FetchUnreadCount => {
+                if !self.logged_in {
+                    return;
+                }
+                std::thread::spawn(move || match api::user::get_unread_count() {
+                    Ok(unread_count) => sender.input(AppMsg::UpdateUnreadCount(unread_count)),
+                    Err(err) => println!("{}", err),
+                });
+            }
+            AppMsg::UpdateUnreadCount(unread_count) => {
+                self.unread_count =
+                    unread_count.replies + unread_count.mentions + unread_count.private_messages;
+                self.inbox_page
+                    .sender()
+                    .emit(InboxInput::UpdateUnreadCount(unread_count));
+            }
+            AppMsg::UpdateBackgroundMode => self.update_background_mode(),
+            AppMsg::CheckNotifications => {
+                self.update_background_mode();
+                let prefs = gsettings::notifications();
+                // the inbox badge is enough while the app is in use, the unread items are only
+                // notified about once it's in the background, so that they aren't skipped
+                if prefs.enabled && !self.main_window.is_active() {
+                    std::thread::spawn(clone!(@strong sender => move || {
+                        let notifications = notifications::check_notifications();
+                        sender.input(AppMsg::ShowNotifications(notifications));
+                    }));
+                }
+                // the interval might have been changed in the meantime, so it's scheduled again every time
+                glib::timeout_add_seconds_local_once(prefs.interval.max(1) * 60, move || {
+                    sender.input(AppMsg::CheckNotifications);
+                });
+            }
+            AppMsg::ShowNotifications(notifications) => {
+                let app = relm4::main_application();
+                for notification in notifications {
+                    app.send_notification(Some(&notification.id), &notification.to_gio());
+                }
+            }
+            AppMsg::OpenNotification(account, target) => {
+                self.main_window.present();
+                let prefs = settings::get_prefs();
+                // the account might have been removed in the meantime
+                let Some(account_index) = prefs
+                    .accounts
+                    .iter()
+                    .position(|existing| notifications::account_key(existing) == account)
+                else {
+                    return;
+                };
+                if account_index != prefs.current_account_index as usize {
+                    settings::update_account_index(account_index);
+                    self.logged_in = prefs.accounts[account_index].jwt.is_some();
+                    self.back_queue.clear();
+                }
+                match target {
+                    NotificationTarget::Comment(post_id, comment_id) => {
+                        sender.input(AppMsg::OpenComment(post_id, comment_id))
+                    }
+                    NotificationTarget::Conversation(person_id) => {
+                        self.state = AppState::Loading;
+                        std::thread::spawn(clone!(@strong sender => move || {
+                            let message = match api::user::get_user(person_id, 1, false) {
+                                Ok(person) => {
+                                    AppMsg::OpenConversation(Box::new(person.person_view.person))
+                                }
+                                Err(err) => AppMsg::ShowMessage(err.to_string()),
+                            };
+                            sender.input(message);
+                        }));
+                    }
+                }
+                sender.input(AppMsg::FetchUnreadCount);
+            }
             AppMsg::LoggedIn => {
+                // the new token can't be saved permanently before there's a passphrase
+                if secrets::is_locked() {
+                    self.passphrase_dialog.emit(PassphraseDialogInput::Show);
+                }
+                self.update_background_mode();
+                sender.input(AppMsg::FetchUnreadCount);
                 self.logged_in = true;
                 self.back_queue.clear();
                 sender.input(AppMsg::OpenPosts);
             }
+            AppMsg::SecretsUnlocked => {
+                let current_account = settings::get_current_account();
+                self.logged_in = current_account.jwt.is_some();
+                self.update_background_mode();
+                sender.input(AppMsg::FetchUnreadCount);
+                if !current_account.instance_url.is_empty() {
+                    self.back_queue.clear();
+                    sender.input(AppMsg::OpenPosts);
+                }
+                // the tokens couldn't be validated at startup while they were locked
+                App::validate_sessions(&sender);
+            }
+            AppMsg::SessionExpired(index, token) => {
+                let prefs = settings::get_prefs();
+                let Some(mut account) = prefs.accounts.get(index).cloned() else {
+                    return;
+                };
+                // the user might have logged in again in the meantime
+                if account.jwt.as_ref() != Some(&token) {
+                    return;
+                }
+                // the rest of the account stays, so that only the password needs to be entered again
+                account.jwt = None;
+                account.session_expired = true;
+                settings::update_account(account.clone(), index);
+                self.update_background_mode();
+                if index == prefs.current_account_index as usize {
+                    self.logged_in = false;
+                    self.unread_count = 0;
+                    self.back_queue.clear();
+                    self.login_page
+                        .emit(LoginPageInput::SessionExpired(account.name));
+                    self.state = AppState::Login;
+                }
+            }
+            AppMsg::SwitchAccount(index) => {
+                let prefs = settings::get_prefs();
+                let Some(account) = prefs.accounts.get(index).cloned() else {
+                    return;
+                };
+                settings::update_account_index(index);
+                self.logged_in = account.jwt.is_some();
+                self.unread_count = 0;
+                // the pages of the previous account can't be opened with the new one
+                self.back_queue.clear();
+                self.update_background_mode();
+                if account.instance_url.is_empty() {
+                    sender.input(AppMsg::ChooseInstance);
+                } else if account.session_expired {
+                    self.login_page
+                        .emit(LoginPageInput::SessionExpired(account.name));
+                    self.state = AppState::Login;
+                } else {
+                    self.state = AppState::Loading;
+                    sender.input(AppMsg::FetchUnreadCount);
+                    sender.input(AppMsg::OpenPosts);
+                }
+            }
+            AppMsg::BackupImported => {
+                let index = settings::get_prefs().current_account_index as usize;
+                sender.input(AppMsg::SwitchAccount(index));
+                self.accounts_page.emit(AccountsPageInput::Update);
+                App::validate_sessions(&sender);
+            }
+            AppMsg::CycleAccount => {
+                let prefs = settings::get_prefs();
+                if prefs.accounts.len() > 1 {
+                    let next = (prefs.current_account_index as usize + 1) % prefs.accounts.len();
+                    sender.input(AppMsg::SwitchAccount(next));
+                }
+            }
             AppMsg::PopBackStack => {
                 let action = self.back_queue.get(self.back_queue.len() - 2);
                 if let Some(action) = action {
@@ -491,11 +1083,11 @@ impl SimpleComponent for App {
             AppMsg::UpdateState(state) => {
                 self.state = state;
             }
-            AppMsg::OpenPosts => self.posts_page.sender().emit(PostsPageInput::FetchPosts(
-                ListingType::Local,
-                SortType::Hot,
-                true,
-            )),
+            AppMsg::OpenPosts => {
+                self.posts_page.sender().emit(PostsPageInput::Refresh);
+                // preload the custom emojis of the instance for rendering posts and comments
+                std::thread::spawn(custom_emojis::get_custom_emojis);
+            }
         }
     }
 }
@@ -503,11 +1095,24 @@ impl SimpleComponent for App {
 relm4::new_action_group!(WindowActionGroup, "win");
 relm4::new_stateless_action!(ChangeInstanceAction, WindowActionGroup, "instance");
 relm4::new_stateless_action!(AccountsAction, WindowActionGroup, "accounts");
+relm4::new_stateless_action!(DraftsAction, WindowActionGroup, "drafts");
+relm4::new_stateless_action!(UploadsAction, WindowActionGroup, "uploads");
+relm4::new_stateful_action!(
+    SwitchAccountAction,
+    WindowActionGroup,
+    "switch_account",
+    u32,
+    u32
+);
+relm4::new_stateless_action!(NextAccountAction, WindowActionGroup, "next_account");
 relm4::new_stateless_action!(LoginAction, WindowActionGroup, "login");
 relm4::new_stateless_action!(ProfileAction, WindowActionGroup, "profile");
+relm4::new_stateless_action!(UserSettingsAction, WindowActionGroup, "user_settings");
+relm4::new_stateless_action!(BlocksAction, WindowActionGroup, "blocks");
 relm4::new_stateless_action!(SiteInfoAction, WindowActionGroup, "site_info");
 relm4::new_stateless_action!(SettingsAction, WindowActionGroup, "settings");
 relm4::new_stateless_action!(AboutAction, WindowActionGroup, "about");
+relm4::new_stateless_action!(QuitAction, WindowActionGroup, "quit");
 
 fn main() {
     let app = RelmApp::new(config::APP_ID);
@@ -0,0 +1,847 @@
+// renders lemmy flavored markdown into a tree of native widgets
+use gtk::{
+    glib::{self, clone},
+    pango,
+    prelude::*,
+};
+use lemmy_api_common::lemmy_db_views::structs::CustomEmojiView;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use relm4::{adw, gtk};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{custom_emojis, gsettings, highlight, util};
+
+const EMOJI_SIZE: i32 = 24;
+const IMAGE_SIZE: i32 = 400;
+
+pub type LinkHandler = Rc<dyn Fn(String)>;
+
+struct Link {
+    start: i32,
+    end: i32,
+    url: String,
+}
+
+// a block of markdown that's either regular text or a collapsible spoiler
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Markdown(String),
+    Spoiler { title: String, content: String },
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+// the title of the spoiler if the line opens one, e.g. "::: spoiler title"
+fn spoiler_title(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix(":::")?.trim_start();
+    let title = rest.strip_prefix("spoiler")?;
+    Some(title.trim())
+}
+
+// splits the text into segments at `::: spoiler` blocks, nested spoilers stay part of the content
+fn split_spoilers(text: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut markdown = String::new();
+    let mut spoiler: Option<(String, String)> = None;
+    let mut depth = 0;
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        let title = if in_fence { None } else { spoiler_title(line) };
+        let closes = !in_fence && line.trim() == ":::";
+
+        match (&mut spoiler, title) {
+            (None, Some(title)) => {
+                if !markdown.is_empty() {
+                    segments.push(Segment::Markdown(std::mem::take(&mut markdown)));
+                }
+                spoiler = Some((title.to_string(), String::new()));
+                depth = 1;
+            }
+            (None, None) => {
+                markdown.push_str(line);
+                markdown.push('\n');
+            }
+            (Some((_, content)), Some(_)) => {
+                depth += 1;
+                content.push_str(line);
+                content.push('\n');
+            }
+            (Some((_, content)), None) => {
+                if closes {
+                    depth -= 1;
+                }
+                if depth == 0 {
+                    let (title, content) = spoiler.take().unwrap_or_default();
+                    segments.push(Segment::Spoiler { title, content });
+                } else {
+                    content.push_str(line);
+                    content.push('\n');
+                }
+            }
+        }
+    }
+
+    // unclosed spoilers reach until the end of the text
+    if let Some((title, content)) = spoiler {
+        segments.push(Segment::Spoiler { title, content });
+    }
+    if !markdown.is_empty() {
+        segments.push(Segment::Markdown(markdown));
+    }
+    segments
+}
+
+fn is_name_char(char: u8) -> bool {
+    char.is_ascii_alphanumeric() || char == b'_'
+}
+
+// finds the next !community@instance or @user@instance in the text as byte range
+fn find_identifier(text: &str) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    while start < bytes.len() {
+        let prefix = bytes[start];
+        // e-mail addresses and similar shouldn't be matched
+        let separated = start == 0 || !(is_name_char(bytes[start - 1]) || bytes[start - 1] == b'@');
+        if (prefix == b'!' || prefix == b'@') && separated {
+            let name_end = (start + 1..bytes.len())
+                .find(|index| !is_name_char(bytes[*index]))
+                .unwrap_or(bytes.len());
+            if name_end > start + 1 && bytes.get(name_end) == Some(&b'@') {
+                let mut end = (name_end + 1..bytes.len())
+                    .find(|index| {
+                        !(bytes[*index].is_ascii_alphanumeric() || b".-".contains(&bytes[*index]))
+                    })
+                    .unwrap_or(bytes.len());
+                // a dot at the end most likely ends the sentence
+                while end > name_end + 1 && bytes[end - 1] == b'.' {
+                    end -= 1;
+                }
+                if text[name_end + 1..end].contains('.') {
+                    return Some((start, end));
+                }
+            }
+        }
+        start += 1;
+    }
+    None
+}
+
+// the query for the ResolveObject api if the link points to something on a lemmy instance
+pub fn lemmy_object_query(link: &str, instance_url: &str) -> Option<String> {
+    if find_identifier(link) == Some((0, link.len())) {
+        return Some(link.to_string());
+    }
+    let url = match reqwest::Url::parse(link) {
+        Ok(url) => url,
+        // relative links point to the current instance
+        Err(_) if link.starts_with('/') => {
+            reqwest::Url::parse(instance_url).ok()?.join(link).ok()?
+        }
+        Err(_) => return None,
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["post" | "comment", id] if id.parse::<i32>().is_ok() => Some(format!(
+            "{}://{}/{}/{}",
+            url.scheme(),
+            host,
+            segments[0],
+            id
+        )),
+        ["c" | "u", name] => {
+            let prefix = if segments[0] == "c" { '!' } else { '@' };
+            // links like /c/rust@lemmy.ml already contain the instance of the community
+            if name.contains('@') {
+                Some(format!("{}{}", prefix, name))
+            } else {
+                Some(format!("{}{}@{}", prefix, name, host))
+            }
+        }
+        _ => None,
+    }
+}
+
+// converts lemmy's ^superscript^ and ~subscript~ syntax into html tags that the parser understands
+fn convert_sup_sub(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if in_fence || is_fence(line) {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let mut in_code = false;
+        let mut index = 0;
+        while index < chars.len() {
+            let char = chars[index];
+            if char == '`' {
+                in_code = !in_code;
+            }
+            let tag = match char {
+                '^' => Some("sup"),
+                // double tildes are strikethroughs
+                '~' if chars.get(index + 1) != Some(&'~')
+                    && (index == 0 || chars[index - 1] != '~') =>
+                {
+                    Some("sub")
+                }
+                _ => None,
+            };
+            if let (false, Some(tag)) = (in_code, tag) {
+                let closing = chars[index + 1..]
+                    .iter()
+                    .take_while(|c| !c.is_whitespace())
+                    .position(|c| *c == char)
+                    .map(|position| index + 1 + position);
+                if let Some(closing) = closing.filter(|closing| *closing > index + 1) {
+                    let content: String = chars[index + 1..closing].iter().collect();
+                    result.push_str(&format!("<{}>{}</{}>", tag, content, tag));
+                    index = closing + 1;
+                    continue;
+                }
+            }
+            result.push(char);
+            index += 1;
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn create_tags(buffer: &gtk::TextBuffer) {
+    let table = buffer.tag_table();
+    let tags = [
+        gtk::TextTag::builder().name("bold").weight(700).build(),
+        gtk::TextTag::builder()
+            .name("italic")
+            .style(pango::Style::Italic)
+            .build(),
+        gtk::TextTag::builder()
+            .name("strikethrough")
+            .strikethrough(true)
+            .build(),
+        gtk::TextTag::builder()
+            .name("code")
+            .family("monospace")
+            .build(),
+        gtk::TextTag::builder()
+            .name("link")
+            .underline(pango::Underline::Single)
+            .foreground("#3584e4")
+            .build(),
+        gtk::TextTag::builder()
+            .name("sup")
+            .rise(5 * pango::SCALE)
+            .scale(0.8)
+            .build(),
+        gtk::TextTag::builder()
+            .name("sub")
+            .rise(-3 * pango::SCALE)
+            .scale(0.8)
+            .build(),
+    ];
+    for tag in tags {
+        table.add(&tag);
+    }
+    for level in 1..=6 {
+        let tag = gtk::TextTag::builder()
+            .name(format!("heading{}", level))
+            .weight(700)
+            .scale(1.0 + (7 - level) as f64 * 0.15)
+            .build();
+        table.add(&tag);
+    }
+}
+
+// a read-only text view for a paragraph, heading or table cell with clickable links
+struct InlineText {
+    text_view: gtk::TextView,
+    links: Rc<RefCell<Vec<Link>>>,
+}
+
+fn link_at(text_view: &gtk::TextView, links: &[Link], x: f64, y: f64) -> Option<String> {
+    let (x, y) = text_view.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+    let offset = text_view.iter_at_location(x, y)?.offset();
+    links
+        .iter()
+        .find(|link| link.start <= offset && offset < link.end)
+        .map(|link| link.url.clone())
+}
+
+impl InlineText {
+    fn new(on_link: &LinkHandler) -> Self {
+        let buffer = gtk::TextBuffer::new(None);
+        create_tags(&buffer);
+        let text_view = gtk::TextView::builder()
+            .buffer(&buffer)
+            .editable(false)
+            .cursor_visible(false)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .hexpand(true)
+            .css_classes(vec!["markdown-view".to_string()])
+            .build();
+        let links: Rc<RefCell<Vec<Link>>> = Rc::new(RefCell::new(vec![]));
+
+        let click = gtk::GestureClick::new();
+        {
+            let links = links.clone();
+            let on_link = on_link.clone();
+            click.connect_released(move |gesture, _, x, y| {
+                let Ok(text_view) = gesture.widget().downcast::<gtk::TextView>() else {
+                    return;
+                };
+                // don't open links while selecting text
+                if text_view.buffer().has_selection() {
+                    return;
+                }
+                if let Some(url) = link_at(&text_view, &links.borrow(), x, y) {
+                    on_link(url);
+                }
+            });
+        }
+        text_view.add_controller(click);
+
+        let motion = gtk::EventControllerMotion::new();
+        {
+            let links = links.clone();
+            motion.connect_motion(move |controller, x, y| {
+                let Ok(text_view) = controller.widget().downcast::<gtk::TextView>() else {
+                    return;
+                };
+                let over_link = link_at(&text_view, &links.borrow(), x, y).is_some();
+                text_view.set_cursor_from_name(Some(if over_link { "pointer" } else { "text" }));
+            });
+        }
+        text_view.add_controller(motion);
+
+        Self { text_view, links }
+    }
+
+    fn offset(&self) -> i32 {
+        self.text_view.buffer().end_iter().offset()
+    }
+
+    fn insert(&self, text: &str, tags: &[&str]) {
+        let buffer = self.text_view.buffer();
+        buffer.insert_with_tags_by_name(&mut buffer.end_iter(), text, tags);
+    }
+
+    fn insert_picture(&self, url: String, tooltip: &str, max_size: i32) {
+        let buffer = self.text_view.buffer();
+        let anchor = buffer.create_child_anchor(&mut buffer.end_iter());
+        let picture = gtk::Picture::new();
+        if !tooltip.is_empty() {
+            picture.set_tooltip_text(Some(tooltip));
+        }
+        if gsettings::load_images() {
+            util::load_picture(&picture, url, max_size);
+            self.text_view.add_child_at_anchor(&picture, &anchor);
+            return;
+        }
+        // the picture replaces the button once it's clicked
+        let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        let button = gtk::Button::with_label("Load image");
+        button.set_tooltip_text(Some(&url));
+        button.connect_clicked(clone!(@weak container => move |button| {
+            container.remove(button);
+            util::load_picture(&picture, url.clone(), max_size);
+            container.append(&picture);
+        }));
+        container.append(&button);
+        self.text_view.add_child_at_anchor(&container, &anchor);
+    }
+}
+
+struct Table {
+    grid: gtk::Grid,
+    row: i32,
+    column: i32,
+    in_head: bool,
+}
+
+// walks through the markdown events and builds the widgets for them
+struct Renderer<'a> {
+    emojis: &'a [CustomEmojiView],
+    on_link: &'a LinkHandler,
+    // the boxes that new blocks get appended to, the last one is the innermost
+    containers: Vec<gtk::Box>,
+    inline: Option<InlineText>,
+    // the text tags of all currently open markdown elements
+    tags: Vec<Option<String>>,
+    lists: Vec<Option<u64>>,
+    table: Option<Table>,
+    code_block: Option<(Option<String>, String)>,
+    link_start: Option<(i32, String)>,
+    in_image: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(container: &gtk::Box, emojis: &'a [CustomEmojiView], on_link: &'a LinkHandler) -> Self {
+        Self {
+            emojis,
+            on_link,
+            containers: vec![container.clone()],
+            inline: None,
+            tags: vec![],
+            lists: vec![],
+            table: None,
+            code_block: None,
+            link_start: None,
+            in_image: false,
+        }
+    }
+
+    fn container(&self) -> &gtk::Box {
+        self.containers.last().unwrap()
+    }
+
+    fn append(&self, widget: &impl IsA<gtk::Widget>) {
+        self.container().append(widget);
+    }
+
+    // the text view that inline content is currently added to, created on demand
+    fn inline(&mut self) -> &InlineText {
+        if self.inline.is_none() {
+            let inline = InlineText::new(self.on_link);
+            match &self.table {
+                Some(table) => table
+                    .grid
+                    .attach(&inline.text_view, table.column, table.row, 1, 1),
+                None => self.append(&inline.text_view),
+            }
+            self.inline = Some(inline);
+        }
+        self.inline.as_ref().unwrap()
+    }
+
+    fn finish_inline(&mut self) {
+        if let Some(inline) = self.inline.take() {
+            // trailing line breaks would only add empty space
+            let buffer = inline.text_view.buffer();
+            let mut end = buffer.end_iter();
+            let mut start = end;
+            while start.backward_char() {
+                if start.char() != '\n' {
+                    start.forward_char();
+                    break;
+                }
+            }
+            buffer.delete(&mut start, &mut end);
+        }
+    }
+
+    fn insert(&mut self, text: &str) {
+        let tags: Vec<String> = self.tags.iter().flatten().cloned().collect();
+        let tags: Vec<&str> = tags.iter().map(|tag| tag.as_str()).collect();
+        self.inline().insert(text, &tags);
+    }
+
+    // replaces all :shortcode: occurrences of custom emojis with their images
+    fn insert_with_emojis(&mut self, text: &str) {
+        let mut rest = text;
+        while let Some(start) = rest.find(':') {
+            let after = &rest[start + 1..];
+            let Some(end) = after.find(':') else {
+                break;
+            };
+            let shortcode = &after[..end];
+            match custom_emojis::find_custom_emoji(self.emojis, shortcode) {
+                Some(emoji) => {
+                    self.insert(&rest[..start]);
+                    let url = emoji.custom_emoji.image_url.to_string();
+                    let tooltip = format!(":{}:", shortcode);
+                    self.inline().insert_picture(url, &tooltip, EMOJI_SIZE);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    // the closing colon might be the start of the next shortcode
+                    self.insert(&rest[..start + 1]);
+                    rest = after;
+                }
+            }
+        }
+        self.insert(rest);
+    }
+
+    // turns mentions of communities and users into links, like lemmy-ui does
+    fn insert_text(&mut self, text: &str) {
+        // the text of links can't contain further links
+        if self.link_start.is_some() {
+            self.insert_with_emojis(text);
+            return;
+        }
+        let mut rest = text;
+        while let Some((start, end)) = find_identifier(rest) {
+            self.insert_with_emojis(&rest[..start]);
+            let identifier = &rest[start..end];
+            let link_start = self.inline().offset();
+            self.tags.push(Some("link".to_string()));
+            self.insert(identifier);
+            self.tags.pop();
+            let inline = self.inline();
+            inline.links.borrow_mut().push(Link {
+                start: link_start,
+                end: inline.offset(),
+                url: identifier.to_string(),
+            });
+            rest = &rest[end..];
+        }
+        self.insert_with_emojis(rest);
+    }
+
+    fn push_container(&mut self, container: gtk::Box) {
+        self.finish_inline();
+        self.append(&container);
+        self.containers.push(container);
+    }
+
+    fn pop_container(&mut self) {
+        self.finish_inline();
+        self.containers.pop();
+    }
+
+    fn start(&mut self, tag: Tag) {
+        let name = match tag {
+            Tag::Paragraph => {
+                self.finish_inline();
+                None
+            }
+            Tag::Heading(level, _, _) => {
+                self.finish_inline();
+                Some(format!("heading{}", level as usize))
+            }
+            Tag::BlockQuote => {
+                let quote = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .spacing(10)
+                    .css_classes(vec!["markdown-quote".to_string()])
+                    .build();
+                self.push_container(quote);
+                None
+            }
+            Tag::CodeBlock(kind) => {
+                self.finish_inline();
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) if !language.is_empty() => {
+                        Some(language.to_string())
+                    }
+                    _ => None,
+                };
+                self.code_block = Some((language, String::new()));
+                None
+            }
+            Tag::List(start) => {
+                self.lists.push(start);
+                let list = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .spacing(5)
+                    .build();
+                self.push_container(list);
+                None
+            }
+            Tag::Item => {
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                let item = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Horizontal)
+                    .spacing(8)
+                    .build();
+                item.append(
+                    &gtk::Label::builder()
+                        .label(&bullet)
+                        .valign(gtk::Align::Start)
+                        .build(),
+                );
+                let content = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .spacing(5)
+                    .hexpand(true)
+                    .build();
+                item.append(&content);
+                self.finish_inline();
+                self.append(&item);
+                self.containers.push(content);
+                None
+            }
+            Tag::Table(_) => {
+                self.finish_inline();
+                let grid = gtk::Grid::builder()
+                    .column_spacing(15)
+                    .row_spacing(5)
+                    .build();
+                // wide tables can be scrolled horizontally
+                let scrolled_window = gtk::ScrolledWindow::builder()
+                    .vscrollbar_policy(gtk::PolicyType::Never)
+                    .child(&grid)
+                    .build();
+                self.append(&scrolled_window);
+                self.table = Some(Table {
+                    grid,
+                    row: 0,
+                    column: 0,
+                    in_head: false,
+                });
+                None
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.in_head = true;
+                }
+                Some("bold".to_string())
+            }
+            Tag::TableRow | Tag::TableCell => None,
+            Tag::Emphasis => Some("italic".to_string()),
+            Tag::Strong => Some("bold".to_string()),
+            Tag::Strikethrough => Some("strikethrough".to_string()),
+            Tag::Link(_, url, _) => {
+                let start = self.inline().offset();
+                self.link_start = Some((start, url.to_string()));
+                Some("link".to_string())
+            }
+            Tag::Image(_, url, title) => {
+                self.inline()
+                    .insert_picture(url.to_string(), &title, IMAGE_SIZE);
+                self.in_image = true;
+                None
+            }
+            Tag::FootnoteDefinition(_) => None,
+        };
+        self.tags.push(name);
+    }
+
+    fn end(&mut self, tag: Tag) {
+        self.tags.pop();
+        match tag {
+            Tag::Paragraph | Tag::Heading(..) => self.finish_inline(),
+            Tag::BlockQuote => self.pop_container(),
+            Tag::CodeBlock(_) => {
+                if let Some((language, code)) = self.code_block.take() {
+                    let code_view = code_view(language.as_deref(), code.trim_end_matches('\n'));
+                    self.append(&code_view);
+                }
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+                self.pop_container();
+            }
+            Tag::Item => self.pop_container(),
+            Tag::Table(_) => {
+                self.finish_inline();
+                self.table = None;
+            }
+            Tag::TableHead | Tag::TableRow => {
+                self.finish_inline();
+                if let Some(table) = &mut self.table {
+                    table.in_head = false;
+                    table.row += 1;
+                    table.column = 0;
+                }
+            }
+            Tag::TableCell => {
+                // empty cells still need a placeholder to keep the columns aligned
+                self.inline();
+                self.finish_inline();
+                if let Some(table) = &mut self.table {
+                    table.column += 1;
+                }
+            }
+            Tag::Link(..) => {
+                if let Some((start, url)) = self.link_start.take() {
+                    if let Some(inline) = &self.inline {
+                        let end = inline.offset();
+                        inline.links.borrow_mut().push(Link { start, end, url });
+                    }
+                }
+            }
+            Tag::Image(..) => self.in_image = false,
+            _ => {}
+        }
+    }
+
+    fn html(&mut self, html: &str) {
+        match html.trim() {
+            "<sup>" => self.tags.push(Some("sup".to_string())),
+            "<sub>" => self.tags.push(Some("sub".to_string())),
+            "</sup>" | "</sub>" => {
+                self.tags.pop();
+            }
+            "<br>" | "<br/>" | "<br />" => self.insert("\n"),
+            html => self.insert(html),
+        }
+    }
+
+    fn render(mut self, text: &str) {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_TASKLISTS);
+        for event in Parser::new_ext(&convert_sup_sub(text), options) {
+            match event {
+                Event::Start(tag) => self.start(tag),
+                Event::End(tag) => self.end(tag),
+                Event::Text(text) => {
+                    if let Some((_, code)) = &mut self.code_block {
+                        code.push_str(&text);
+                    } else if !self.in_image {
+                        // the alt text of images is already shown as tooltip
+                        self.insert_text(&text);
+                    }
+                }
+                Event::Code(code) => {
+                    self.tags.push(Some("code".to_string()));
+                    self.insert(&code);
+                    self.tags.pop();
+                }
+                Event::Html(html) => self.html(&html),
+                Event::SoftBreak => self.insert(" "),
+                Event::HardBreak => self.insert("\n"),
+                Event::Rule => {
+                    self.finish_inline();
+                    self.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+                }
+                Event::TaskListMarker(checked) => {
+                    self.insert(if checked { "☑ " } else { "☐ " })
+                }
+                Event::FootnoteReference(_) => {}
+            }
+        }
+        self.finish_inline();
+    }
+}
+
+fn code_view(language: Option<&str>, code: &str) -> gtk::Widget {
+    let text_view = gtk::TextView::builder()
+        .editable(false)
+        .cursor_visible(false)
+        .monospace(true)
+        .wrap_mode(gtk::WrapMode::None)
+        .top_margin(8)
+        .bottom_margin(8)
+        .left_margin(8)
+        .right_margin(8)
+        .build();
+    let highlighted = highlight::highlight(&text_view.buffer(), language, code);
+    if highlighted {
+        // the colors of the highlighting depend on whether the dark style is used, which can change any time
+        let style_manager = adw::StyleManager::default();
+        let language = language.map(str::to_string);
+        let code = code.to_string();
+        let handler = style_manager.connect_dark_notify(clone!(@weak text_view => move |_| {
+            // a new buffer, so that the tags of the previous theme don't pile up
+            let buffer = gtk::TextBuffer::new(None);
+            highlight::highlight(&buffer, language.as_deref(), &code);
+            text_view.set_buffer(Some(&buffer));
+        }));
+        let handler = RefCell::new(Some(handler));
+        text_view.connect_destroy(move |_| {
+            if let Some(handler) = handler.take() {
+                style_manager.disconnect(handler);
+            }
+        });
+    }
+    // long lines can be scrolled horizontally instead of being wrapped
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .child(&text_view)
+        .build();
+
+    let header = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .margin_start(8)
+        .margin_end(4)
+        .margin_top(4)
+        .build();
+    // only show the language if it's known, otherwise the info string might be anything
+    let language_label = gtk::Label::builder()
+        .label(language.filter(|_| highlighted).unwrap_or_default())
+        .halign(gtk::Align::Start)
+        .hexpand(true)
+        .css_classes(vec!["dim-label".to_string(), "caption".to_string()])
+        .build();
+    let copy_button = gtk::Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text("Copy")
+        .css_classes(vec!["flat".to_string()])
+        .build();
+    let code = code.to_string();
+    copy_button.connect_clicked(move |button| {
+        button.clipboard().set_text(&code);
+        button.set_icon_name("object-select-symbolic");
+    });
+    header.append(&language_label);
+    header.append(&copy_button);
+
+    let code_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .css_classes(vec!["markdown-code".to_string()])
+        .build();
+    code_box.append(&header);
+    code_box.append(&scrolled_window);
+    code_box.upcast()
+}
+
+// appends the widgets of the rendered markdown to the container
+pub fn render(container: &gtk::Box, text: &str, emojis: &[CustomEmojiView], on_link: &LinkHandler) {
+    for segment in split_spoilers(text) {
+        match segment {
+            Segment::Markdown(markdown) => {
+                Renderer::new(container, emojis, on_link).render(&markdown)
+            }
+            Segment::Spoiler { title, content } => {
+                let title = if title.is_empty() {
+                    "Spoiler".to_string()
+                } else {
+                    title
+                };
+                let spoiler = gtk::Box::builder()
+                    .orientation(gtk::Orientation::Vertical)
+                    .spacing(10)
+                    .margin_top(5)
+                    .build();
+                render(&spoiler, &content, emojis, on_link);
+                let expander = gtk::Expander::builder()
+                    .label(&title)
+                    .child(&spoiler)
+                    .build();
+                container.append(&expander);
+            }
+        }
+    }
+}
+
+// replaces the content of the container with the rendered text, e.g. for previews while editing
+// links are opened in the browser and only the custom emojis that have already been fetched are shown
+pub fn render_preview(container: &gtk::Box, text: &str) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    let emojis = custom_emojis::cached_custom_emojis().unwrap_or_default();
+    let on_link: LinkHandler = Rc::new(|url: String| {
+        gtk::show_uri(None::<&gtk::Window>, &url, 0);
+    });
+    render(container, text, &emojis, &on_link);
+}
@@ -0,0 +1,220 @@
+use crate::api;
+use crate::config::APP_ID;
+use crate::gsettings;
+use crate::gtk::{gio, glib};
+use crate::settings::{self, Account};
+use chrono::{DateTime, Utc};
+use lemmy_api_common::lemmy_db_schema::newtypes::{CommentId, PersonId, PostId};
+use std::{collections::HashMap, fs::File, path::PathBuf};
+
+// the longest part of a comment or message that's shown in a notification
+const MAX_BODY_LENGTH: usize = 200;
+
+#[derive(Debug, Clone)]
+pub enum NotificationTarget {
+    // a reply or mention, shown within its post
+    Comment(PostId, CommentId),
+    // the conversation with the person who sent a message
+    Conversation(PersonId),
+}
+
+#[derive(Debug, Clone)]
+pub struct InboxNotification {
+    // used to replace older notifications about the same item
+    pub id: String,
+    // the key of the account, the index could point to another account once one is removed
+    pub account: String,
+    pub title: String,
+    pub body: String,
+    pub target: NotificationTarget,
+    published: DateTime<Utc>,
+}
+
+impl InboxNotification {
+    pub fn to_gio(&self) -> gio::Notification {
+        let notification = gio::Notification::new(&self.title);
+        notification.set_body(Some(&self.body));
+        notification.set_default_action_and_target_value(
+            "app.open-notification",
+            Some(&glib::Variant::from(action_target(
+                &self.account,
+                &self.target,
+            ))),
+        );
+        notification
+    }
+}
+
+// the account key contains colons of the instance url, hence it's separated by a space
+fn action_target(account: &str, target: &NotificationTarget) -> String {
+    match target {
+        NotificationTarget::Comment(post_id, comment_id) => {
+            format!("{} comment:{}:{}", account, post_id.0, comment_id.0)
+        }
+        NotificationTarget::Conversation(person_id) => {
+            format!("{} conversation:{}", account, person_id.0)
+        }
+    }
+}
+
+// the reverse of action_target, for when a notification gets clicked
+pub fn parse_action_target(target: &str) -> Option<(String, NotificationTarget)> {
+    let (account, target) = target.split_once(' ')?;
+    let mut parts = target.split(':');
+    let target = match (parts.next()?, parts.next(), parts.next()) {
+        ("comment", Some(post_id), Some(comment_id)) => NotificationTarget::Comment(
+            PostId(post_id.parse().ok()?),
+            CommentId(comment_id.parse().ok()?),
+        ),
+        ("conversation", Some(person_id), None) => {
+            NotificationTarget::Conversation(PersonId(person_id.parse().ok()?))
+        }
+        _ => return None,
+    };
+    Some((account.to_string(), target))
+}
+
+fn state_path() -> PathBuf {
+    let mut path = glib::user_data_dir();
+    path.push(APP_ID);
+    std::fs::create_dir_all(&path).expect("Could not create directory.");
+    path.push("notifications.json");
+    path
+}
+
+// the publish date of the newest item that has been notified about, per account
+fn get_state() -> HashMap<String, DateTime<Utc>> {
+    if let Ok(file) = File::open(state_path()) {
+        if let Ok(state) = serde_json::from_reader(file) {
+            return state;
+        }
+    }
+    HashMap::new()
+}
+
+fn save_state(state: &HashMap<String, DateTime<Utc>>) {
+    let file = File::create(state_path()).expect("Could not create json file.");
+    serde_json::to_writer(file, state).expect("Could not write data to json file");
+}
+
+// identifies the account across changes of the account list
+pub fn account_key(account: &Account) -> String {
+    format!("{}@{}", account.id, account.instance_url)
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= MAX_BODY_LENGTH {
+        return text.to_string();
+    }
+    let shortened: String = text.chars().take(MAX_BODY_LENGTH).collect();
+    format!("{}…", shortened.trim_end())
+}
+
+// all unread items of the account that the user should be notified about
+fn fetch_notifications(
+    account: &Account,
+    prefs: &gsettings::Notifications,
+) -> Result<Vec<InboxNotification>, reqwest::Error> {
+    let key = account_key(account);
+    let mut notifications = vec![];
+
+    if prefs.replies {
+        for reply in api::user::get_unread_replies(account)?.replies {
+            notifications.push(InboxNotification {
+                id: format!("{}-comment-{}", key, reply.comment.id.0),
+                account: key.clone(),
+                title: format!("{} replied to you", reply.creator.name),
+                body: shorten(&reply.comment.content),
+                target: NotificationTarget::Comment(reply.post.id, reply.comment.id),
+                published: reply.comment.published,
+            });
+        }
+    }
+    if prefs.mentions {
+        for mention in api::user::get_unread_mentions(account)?.mentions {
+            notifications.push(InboxNotification {
+                id: format!("{}-comment-{}", key, mention.comment.id.0),
+                account: key.clone(),
+                title: format!("{} mentioned you", mention.creator.name),
+                body: shorten(&mention.comment.content),
+                target: NotificationTarget::Comment(mention.post.id, mention.comment.id),
+                published: mention.comment.published,
+            });
+        }
+    }
+    if prefs.private_messages {
+        for message in api::private_message::get_unread_private_messages(account)?.private_messages
+        {
+            // the list also contains the messages that were sent by the account itself
+            if message.recipient.id.0 != account.id {
+                continue;
+            }
+            notifications.push(InboxNotification {
+                id: format!(
+                    "{}-message-{}-{}",
+                    key,
+                    message.creator.id.0,
+                    message.private_message.published.timestamp()
+                ),
+                account: key.clone(),
+                title: format!("{} sent you a message", message.creator.name),
+                body: shorten(&message.private_message.content),
+                target: NotificationTarget::Conversation(message.creator.id),
+                published: message.private_message.published,
+            });
+        }
+    }
+    Ok(notifications)
+}
+
+// checks the inboxes of all logged in accounts and returns the items that haven't been notified about yet
+pub fn check_notifications() -> Vec<InboxNotification> {
+    let prefs = settings::get_prefs();
+    let notification_prefs = gsettings::notifications();
+    let mut state = get_state();
+    let mut new_notifications = vec![];
+
+    let accounts: Vec<&Account> = prefs
+        .accounts
+        .iter()
+        .filter(|account| account.jwt.is_some() && account.notifications)
+        .collect();
+    for account in &accounts {
+        let mut notifications = match fetch_notifications(account, &notification_prefs) {
+            Ok(notifications) => notifications,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        };
+        // the title needs to tell which account a notification belongs to if there are several ones
+        if accounts.len() > 1 {
+            for notification in &mut notifications {
+                notification.title = format!("{} ({})", notification.title, account.name);
+            }
+        }
+        let key = account_key(account);
+        let newest = notifications
+            .iter()
+            .map(|notification| notification.published)
+            .max();
+        match state.get(&key) {
+            Some(last_notified) => new_notifications.extend(
+                notifications
+                    .into_iter()
+                    .filter(|notification| notification.published > *last_notified),
+            ),
+            // don't flood the user with everything that's still unread when checking for the first time
+            None => {
+                state.insert(key.clone(), Utc::now());
+            }
+        }
+        if let Some(newest) = newest {
+            let last_notified = state.entry(key).or_insert(newest);
+            *last_notified = newest.max(*last_notified);
+        }
+    }
+
+    save_state(&state);
+    new_notifications
+}
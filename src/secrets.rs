@@ -0,0 +1,399 @@
+// the login tokens are kept out of data.json, which only references them
+// they're stored in the keyring of the desktop (Secret Service) if there's one,
+// otherwise in a file that's encrypted with a passphrase chosen by the user
+use crate::config::APP_ID;
+use crate::gtk::glib;
+use crate::settings::Account;
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use lemmy_api_common::sensitive::Sensitive;
+use relm4::once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+const KEY_LENGTH: usize = 32;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Backend {
+    Keyring,
+    EncryptedFile,
+}
+
+#[derive(Default)]
+struct Store {
+    backend: Option<Backend>,
+    // tokens that have already been looked up, by their reference
+    tokens: HashMap<String, Option<String>>,
+    // only known once the encrypted file has been unlocked
+    key: Option<([u8; SALT_LENGTH], [u8; KEY_LENGTH])>,
+    // references of tokens that are only kept in memory, because writing them failed
+    unsaved: HashSet<String>,
+    // plaintext tokens of older versions that have been read from data.json
+    legacy: HashSet<String>,
+}
+
+static STORE: Lazy<Mutex<Store>> = Lazy::new(Default::default);
+
+#[derive(Debug)]
+pub enum SecretsError {
+    WrongPassphrase,
+    Io(std::io::Error),
+    Encryption,
+}
+
+impl fmt::Display for SecretsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretsError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            SecretsError::Io(err) => write!(f, "Could not access the token file: {}", err),
+            SecretsError::Encryption => write!(f, "Could not encrypt the login tokens"),
+        }
+    }
+}
+
+fn file_path() -> PathBuf {
+    let mut path = glib::user_data_dir();
+    path.push(APP_ID);
+    std::fs::create_dir_all(&path).expect("Could not create directory.");
+    path.push("tokens.bin");
+    path
+}
+
+fn keyring_entry(reference: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(APP_ID, reference)
+}
+
+impl Store {
+    // the keyring is only used if it's actually reachable, e.g. not inside a sandbox without access to it
+    fn backend(&mut self) -> Backend {
+        *self.backend.get_or_insert_with(|| {
+            match keyring_entry("probe").and_then(|entry| entry.get_password()) {
+                Ok(_) | Err(keyring::Error::NoEntry) => Backend::Keyring,
+                Err(err) => {
+                    println!(
+                        "Keyring not available, falling back to the encrypted file: {}",
+                        err
+                    );
+                    Backend::EncryptedFile
+                }
+            }
+        })
+    }
+
+    fn is_locked(&mut self) -> bool {
+        self.backend() == Backend::EncryptedFile && self.key.is_none()
+    }
+
+    fn get(&mut self, reference: &str) -> Option<String> {
+        if let Some(token) = self.tokens.get(reference) {
+            return token.clone();
+        }
+        if self.backend() == Backend::EncryptedFile {
+            // the tokens of the file are all loaded at once when unlocking it
+            return None;
+        }
+        let token = match keyring_entry(reference).and_then(|entry| entry.get_password()) {
+            Ok(token) => Some(token),
+            Err(keyring::Error::NoEntry) => None,
+            Err(err) => {
+                // don't cache the failure, the keyring might just be locked at the moment
+                println!("{}", err);
+                return None;
+            }
+        };
+        self.tokens.insert(reference.to_string(), token.clone());
+        token
+    }
+
+    fn set(&mut self, reference: &str, token: &str) {
+        self.tokens
+            .insert(reference.to_string(), Some(token.to_string()));
+        let saved = match self.backend() {
+            Backend::Keyring => {
+                match keyring_entry(reference).and_then(|entry| entry.set_password(token)) {
+                    Ok(_) => true,
+                    Err(err) => {
+                        println!("{}", err);
+                        false
+                    }
+                }
+            }
+            Backend::EncryptedFile => self.write_file(),
+        };
+        if saved {
+            self.unsaved.remove(reference);
+        } else {
+            self.unsaved.insert(reference.to_string());
+        }
+    }
+
+    // whether the token has been written to the keyring or the encrypted file
+    fn is_saved(&mut self, reference: &str) -> bool {
+        !self.is_locked() && !self.unsaved.contains(reference)
+    }
+
+    fn delete(&mut self, reference: &str) {
+        self.tokens.remove(reference);
+        match self.backend() {
+            Backend::Keyring => {
+                match keyring_entry(reference).and_then(|entry| entry.delete_password()) {
+                    Ok(_) | Err(keyring::Error::NoEntry) => {}
+                    Err(err) => println!("{}", err),
+                }
+            }
+            Backend::EncryptedFile => {
+                self.write_file();
+            }
+        }
+    }
+
+    fn file_tokens(&self) -> HashMap<&String, &String> {
+        self.tokens
+            .iter()
+            .filter_map(|(reference, token)| Some((reference, token.as_ref()?)))
+            .collect()
+    }
+
+    // tokens that are added while the file is still locked are only kept in memory until it's unlocked
+    // returns whether all tokens have been written
+    fn write_file(&mut self) -> bool {
+        let Some((salt, key)) = &self.key else {
+            return false;
+        };
+        match encrypt(salt, key, &self.file_tokens()) {
+            Ok(_) => {
+                self.unsaved.clear();
+                true
+            }
+            Err(err) => {
+                println!("{}", err);
+                false
+            }
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LENGTH], SecretsError> {
+    let mut key = [0; KEY_LENGTH];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|_| SecretsError::Encryption)?;
+    Ok(key)
+}
+
+// the file consists of the salt, the nonce and the encrypted json of all tokens
+fn encrypt(
+    salt: &[u8; SALT_LENGTH],
+    key: &[u8; KEY_LENGTH],
+    tokens: &HashMap<&String, &String>,
+) -> Result<(), SecretsError> {
+    let nonce: [u8; NONCE_LENGTH] = rand::random();
+    let plaintext = serde_json::to_vec(tokens).map_err(|_| SecretsError::Encryption)?;
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| SecretsError::Encryption)?;
+
+    #[cfg(unix)]
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(file_path()).map_err(SecretsError::Io)?;
+    file.write_all(salt)
+        .and_then(|_| file.write_all(&nonce))
+        .and_then(|_| file.write_all(&ciphertext))
+        .map_err(SecretsError::Io)
+}
+
+fn decrypt(passphrase: &str, data: &[u8]) -> Result<DecryptedFile, SecretsError> {
+    if data.len() < SALT_LENGTH + NONCE_LENGTH {
+        return Err(SecretsError::Encryption);
+    }
+    let (salt, rest) = data.split_at(SALT_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    let key = derive_key(passphrase, salt)?;
+    let plaintext = XChaCha20Poly1305::new(&key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| SecretsError::WrongPassphrase)?;
+    let tokens = serde_json::from_slice(&plaintext).map_err(|_| SecretsError::Encryption)?;
+    Ok(DecryptedFile {
+        salt: salt.try_into().unwrap(),
+        key,
+        tokens,
+    })
+}
+
+struct DecryptedFile {
+    salt: [u8; SALT_LENGTH],
+    key: [u8; KEY_LENGTH],
+    tokens: HashMap<String, String>,
+}
+
+// whether the user needs to enter a passphrase before the tokens can be read or saved
+pub fn is_locked() -> bool {
+    STORE.lock().unwrap().is_locked()
+}
+
+// whether a passphrase has already been chosen, otherwise unlocking sets a new one
+pub fn has_encrypted_file() -> bool {
+    file_path().exists()
+}
+
+pub fn unlock(passphrase: &str) -> Result<(), SecretsError> {
+    // deriving the key takes a while, so the store isn't locked in the meantime
+    let file = match std::fs::read(file_path()) {
+        Ok(data) => decrypt(passphrase, &data)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let salt: [u8; SALT_LENGTH] = rand::random();
+            DecryptedFile {
+                salt,
+                key: derive_key(passphrase, &salt)?,
+                tokens: HashMap::new(),
+            }
+        }
+        Err(err) => return Err(SecretsError::Io(err)),
+    };
+    let mut store = STORE.lock().unwrap();
+    for (reference, token) in file.tokens {
+        // tokens from logins while the file was locked are newer
+        store.tokens.entry(reference).or_insert(Some(token));
+    }
+    encrypt(&file.salt, &file.key, &store.file_tokens())?;
+    store.key = Some((file.salt, file.key));
+    store.unsaved.clear();
+    Ok(())
+}
+
+// fills in the tokens of the accounts, returns whether any plaintext tokens need to be migrated
+pub fn load_tokens(accounts: &mut [Account]) -> bool {
+    let mut store = STORE.lock().unwrap();
+    let mut needs_migration = false;
+    for account in accounts {
+        // data.json only contains the token itself if it is from an older version
+        if let Some(jwt) = &account.jwt {
+            store.legacy.insert(jwt.to_string());
+            needs_migration = true;
+        } else if let Some(reference) = &account.token_ref {
+            account.jwt = store.get(reference).map(Sensitive::new);
+        }
+    }
+    // the plaintext tokens are kept until they can be moved into the unlocked file
+    needs_migration && !store.is_locked()
+}
+
+// moves the tokens of the accounts into the secret store before data.json gets written
+pub fn store_tokens(accounts: &mut [Account]) {
+    let mut store = STORE.lock().unwrap();
+    for account in accounts {
+        match (&account.jwt, &account.token_ref) {
+            (Some(jwt), Some(reference))
+                if store.get(reference).as_deref() != Some(jwt.as_ref()) =>
+            {
+                store.set(reference, jwt);
+            }
+            (Some(jwt), None) => {
+                // the same account might get saved multiple times before it's read again
+                let existing = store
+                    .tokens
+                    .iter()
+                    .find(|(_, token)| token.as_deref() == Some(jwt.as_ref()))
+                    .map(|(reference, _)| reference.clone());
+                let reference =
+                    existing.unwrap_or_else(|| format!("{:032x}", rand::random::<u128>()));
+                store.set(&reference, jwt);
+                account.token_ref = Some(reference);
+            }
+            // a token that couldn't be read, e.g. because the keyring or file is locked, isn't deleted
+            (None, Some(reference)) if store.tokens.contains_key(reference) => {
+                store.delete(reference);
+                account.token_ref = None;
+            }
+            _ => {}
+        }
+        // tokens that couldn't be saved yet only live in memory, except for the ones of older versions,
+        // which stay in data.json until they're safely stored, e.g. after unlocking the file
+        if let Some(reference) = &account.token_ref {
+            let legacy = account
+                .jwt
+                .as_ref()
+                .is_some_and(|jwt| store.legacy.contains(&**jwt));
+            if !legacy || store.is_saved(reference) {
+                account.jwt = None;
+            }
+        }
+    }
+}
+
+pub fn delete_token(account: &Account) {
+    if let Some(reference) = &account.token_ref {
+        STORE.lock().unwrap().delete(reference);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn encrypted_file(passphrase: &str, tokens: &[(&str, &str)]) -> Vec<u8> {
+        let salt: [u8; SALT_LENGTH] = rand::random();
+        let key = derive_key(passphrase, &salt).unwrap();
+        let tokens: Vec<(String, String)> = tokens
+            .iter()
+            .map(|(reference, token)| (reference.to_string(), token.to_string()))
+            .collect();
+        encrypt(
+            &salt,
+            &key,
+            &tokens
+                .iter()
+                .map(|(reference, token)| (reference, token))
+                .collect(),
+        )
+        .unwrap();
+        std::fs::read(file_path()).unwrap()
+    }
+
+    #[test]
+    fn encrypted_tokens_can_be_decrypted() {
+        let _guard = test_util::isolate();
+        let data = encrypted_file("passphrase", &[("first", "token1"), ("second", "token2")]);
+        let file = decrypt("passphrase", &data).unwrap();
+        assert_eq!(file.tokens.len(), 2);
+        assert_eq!(file.tokens["first"], "token1");
+        assert_eq!(file.tokens["second"], "token2");
+        // the key is kept to save further tokens without asking for the passphrase again
+        assert_eq!(file.key, derive_key("passphrase", &file.salt).unwrap());
+        let _ = std::fs::remove_file(file_path());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let _guard = test_util::isolate();
+        let data = encrypted_file("passphrase", &[("first", "token1")]);
+        assert!(matches!(
+            decrypt("wrong", &data),
+            Err(SecretsError::WrongPassphrase)
+        ));
+        let _ = std::fs::remove_file(file_path());
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        assert!(matches!(
+            decrypt("passphrase", &[0; SALT_LENGTH]),
+            Err(SecretsError::Encryption)
+        ));
+    }
+}
@@ -1,30 +1,98 @@
 use crate::config::APP_ID;
-use crate::gtk::glib;
+use crate::gsettings;
+use crate::gtk::{gio::prelude::*, glib};
+use crate::secrets;
 use lemmy_api_common::sensitive::Sensitive;
 use serde::{Deserialize, Serialize};
-use std::{fs::File, path::PathBuf};
+use serde_json::{json, Map, Value};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
-#[derive(Deserialize, Serialize, Default, Clone)]
+// increased whenever the format of data.json changes, together with a new migration
+const PREFS_VERSION: u32 = 2;
+
+// the migration at index i upgrades the json of version i to version i + 1
+const MIGRATIONS: [fn(&mut Map<String, Value>); PREFS_VERSION as usize] = [migrate_v0, migrate_v1];
+
+// why the preferences couldn't be read at startup, shown to the user once
+static LOAD_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Account {
     pub instance_url: String,
+    // only written to data.json by older versions, such tokens are kept there until they've been
+    // moved into the secret store, e.g. after unlocking the encrypted file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub jwt: Option<Sensitive<String>>,
+    // the reference of the token in the secret store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_ref: Option<String>,
     pub id: i32,
     pub name: String,
+    // whether new replies, mentions and messages of the account are shown as notifications
+    #[serde(default = "default_true")]
+    pub notifications: bool,
+    // the token was rejected by the server, the account needs to log in again
+    #[serde(default)]
+    pub session_expired: bool,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            instance_url: String::new(),
+            jwt: None,
+            token_ref: None,
+            id: 0,
+            name: String::new(),
+            notifications: true,
+            session_expired: false,
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+impl Account {
+    // e.g. `user@lemmy.ml`, used to tell the accounts apart when switching between them
+    pub fn label(&self) -> String {
+        let instance = self
+            .instance_url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let label = match (self.name.is_empty(), instance.is_empty()) {
+            (_, true) => "No instance".to_string(),
+            (true, false) => format!("Anonymous@{}", instance),
+            (false, false) => format!("{}@{}", self.name, instance),
+        };
+        if self.session_expired {
+            format!("{} (session expired)", label)
+        } else {
+            label
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Preferences {
+    #[serde(default)]
+    version: u32,
     pub accounts: Vec<Account>,
     pub current_account_index: u32,
-    pub infinite_scroll: bool,
 }
 
 impl Default for Preferences {
     fn default() -> Self {
         Self {
+            version: PREFS_VERSION,
             accounts: vec![],
             current_account_index: 0,
-            infinite_scroll: true,
         }
     }
 }
@@ -37,20 +105,158 @@ pub fn data_path() -> PathBuf {
     path
 }
 
+fn backup_path() -> PathBuf {
+    data_path().with_extension("json.bak")
+}
+
+// files from before the versioning might lack fields that have been added later on
+fn migrate_v0(prefs: &mut Map<String, Value>) {
+    prefs.entry("accounts").or_insert(json!([]));
+    prefs.entry("current_account_index").or_insert(json!(0));
+    prefs.entry("infinite_scroll").or_insert(json!(true));
+}
+
+// the ui preferences have been moved to gsettings
+fn migrate_v1(prefs: &mut Map<String, Value>) {
+    let settings = gsettings::settings();
+    let set_bool = |key: &str, value: Option<&Value>| {
+        if let Some(value) = value.and_then(Value::as_bool) {
+            let _ = settings.set_boolean(key, value);
+        }
+    };
+    let set_uint = |key: &str, value: Option<&Value>| {
+        if let Some(value) = value.and_then(Value::as_u64) {
+            let _ = settings.set_uint(key, value as u32);
+        }
+    };
+
+    set_bool("infinite-scroll", prefs.get("infinite_scroll"));
+    if let Some(Value::Object(images)) = prefs.get("image_processing") {
+        set_bool("process-images", images.get("enabled"));
+        set_uint("image-max-dimension", images.get("max_dimension"));
+        set_uint("image-quality", images.get("quality"));
+        if let Some(format) = images.get("format").and_then(Value::as_str) {
+            let _ = settings.set_string("image-format", &format.to_lowercase());
+        }
+    }
+    if let Some(Value::Object(notifications)) = prefs.get("notifications") {
+        set_bool("notifications", notifications.get("enabled"));
+        set_uint("notification-interval", notifications.get("interval"));
+        set_bool("notify-replies", notifications.get("replies"));
+        set_bool("notify-mentions", notifications.get("mentions"));
+        set_bool(
+            "notify-private-messages",
+            notifications.get("private_messages"),
+        );
+    }
+
+    for key in ["infinite_scroll", "image_processing", "notifications"] {
+        prefs.remove(key);
+    }
+}
+
+// returns the preferences and whether they had to be migrated from an older version
+fn parse_prefs(data: &[u8]) -> Result<(Preferences, bool), serde_json::Error> {
+    let mut value: Value = serde_json::from_slice(data)?;
+    let mut migrated = false;
+    if let Some(prefs) = value.as_object_mut() {
+        let version = prefs.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+        for migration in MIGRATIONS.iter().skip(version) {
+            migration(prefs);
+            migrated = true;
+        }
+    }
+    let mut prefs: Preferences = serde_json::from_value(value)?;
+    prefs.version = PREFS_VERSION;
+    if prefs.current_account_index as usize >= prefs.accounts.len() {
+        prefs.current_account_index = 0;
+    }
+    Ok((prefs, migrated))
+}
+
+// the new content is written next to the old file first, so that a crash can't leave a half written file
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(temp_path, path)
+}
+
+fn write_prefs(prefs: &Preferences) -> std::io::Result<()> {
+    let path = data_path();
+    // the previous file is kept as backup as long as it can still be read
+    if let Ok(data) = std::fs::read(&path) {
+        if parse_prefs(&data).is_ok() {
+            write_atomic(&backup_path(), &data)?;
+        }
+    }
+    write_atomic(&path, &serde_json::to_vec(prefs)?)
+}
+
 pub fn save_prefs(prefs: &Preferences) {
-    let file = File::create(data_path()).expect("Could not create json file.");
-    serde_json::to_writer(file, &prefs).expect("Could not write data to json file");
+    let mut prefs = prefs.clone();
+    secrets::store_tokens(&mut prefs.accounts);
+    if let Err(err) = write_prefs(&prefs) {
+        println!("Could not save the preferences: {}", err);
+    }
 }
 
-pub fn get_prefs() -> Preferences {
-    if let Ok(file) = File::open(data_path()) {
-        // Deserialize data from file to vector
-        let prefs: Result<Preferences, serde_json::Error> = serde_json::from_reader(file);
-        if let Ok(prefs) = prefs {
-            return prefs;
+// keeps the broken file for inspection and falls back to the backup or the defaults otherwise
+fn recover_prefs(err: String) -> Preferences {
+    let path = data_path();
+    let broken_path =
+        path.with_extension(format!("json.broken-{}", chrono::Utc::now().timestamp()));
+    let kept = match std::fs::rename(&path, &broken_path) {
+        Ok(_) => format!(
+            "The unreadable file has been kept at {}.",
+            broken_path.display()
+        ),
+        Err(_) => String::new(),
+    };
+    let backup = std::fs::read(backup_path())
+        .ok()
+        .and_then(|data| Some((parse_prefs(&data).ok()?.0, data)));
+    let (prefs, outcome) = match backup {
+        Some((prefs, data)) => {
+            if let Err(err) = write_atomic(&path, &data) {
+                println!("{}", err);
+            }
+            (prefs, "They have been restored from the last backup.")
         }
+        None => (
+            Preferences::default(),
+            "No backup was available, so all accounts and settings have been reset.",
+        ),
+    };
+    *LOAD_ERROR.lock().unwrap() = Some(format!(
+        "Your preferences could not be read: {}\n\n{} {}",
+        err, outcome, kept
+    ));
+    prefs
+}
+
+pub fn get_prefs() -> Preferences {
+    let (mut prefs, migrated) = match std::fs::read(data_path()) {
+        Ok(data) => match parse_prefs(&data) {
+            Ok(result) => result,
+            Err(err) => (recover_prefs(err.to_string()), false),
+        },
+        // nothing has been saved yet
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Preferences::default(),
+        Err(err) => (recover_prefs(err.to_string()), false),
+    };
+    let migrated_tokens = secrets::load_tokens(&mut prefs.accounts);
+    // the migrations only need to run once, this also removes the plaintext tokens from the file
+    if migrated || migrated_tokens {
+        save_prefs(&prefs);
     }
-    Preferences::default()
+    prefs
+}
+
+// the error that occurred while reading the preferences, if any
+pub fn take_load_error() -> Option<String> {
+    LOAD_ERROR.lock().unwrap().take()
 }
 
 pub fn get_current_account() -> Account {
@@ -74,7 +280,7 @@ pub fn update_account(account: Account, index: usize) {
 
 pub fn remove_account(index: usize) {
     let mut settings = get_prefs();
-    settings.accounts.remove(index);
+    secrets::delete_token(&settings.accounts.remove(index));
     // if the deleted account has been before the current one, the current index needs to decreased too
     if index < settings.current_account_index as usize {
         settings.current_account_index -= 1;
@@ -97,3 +303,137 @@ pub fn update_account_index(index: usize) {
     prefs.current_account_index = index as u32;
     save_prefs(&prefs);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+
+    fn remove_files() {
+        let path = data_path();
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(backup_path());
+        take_load_error();
+    }
+
+    #[test]
+    fn migrates_v0_to_current_version() {
+        let _guard = test_util::isolate();
+        let data = br#"{
+            "accounts": [{"instance_url": "https://lemmy.ml", "id": 3, "name": "user"}],
+            "current_account_index": 0,
+            "infinite_scroll": false
+        }"#;
+        let (prefs, migrated) = parse_prefs(data).unwrap();
+        assert!(migrated);
+        assert_eq!(prefs.version, PREFS_VERSION);
+        assert_eq!(prefs.accounts.len(), 1);
+        assert_eq!(prefs.accounts[0].name, "user");
+        assert!(prefs.accounts[0].notifications);
+        assert!(!gsettings::infinite_scroll());
+    }
+
+    #[test]
+    fn migrates_v0_without_optional_fields() {
+        let _guard = test_util::isolate();
+        let (prefs, migrated) = parse_prefs(b"{}").unwrap();
+        assert!(migrated);
+        assert_eq!(prefs.version, PREFS_VERSION);
+        assert!(prefs.accounts.is_empty());
+        assert_eq!(prefs.current_account_index, 0);
+    }
+
+    #[test]
+    fn migrates_v1_to_current_version() {
+        let _guard = test_util::isolate();
+        let data = br#"{
+            "version": 1,
+            "accounts": [],
+            "current_account_index": 0,
+            "infinite_scroll": true,
+            "image_processing": {"enabled": true, "max_dimension": 1000, "quality": 70, "format": "Png"},
+            "notifications": {"enabled": true, "interval": 15, "replies": false}
+        }"#;
+        let (prefs, migrated) = parse_prefs(data).unwrap();
+        assert!(migrated);
+        assert_eq!(prefs.version, PREFS_VERSION);
+
+        let images = gsettings::image_processing();
+        assert!(images.enabled);
+        assert_eq!(images.max_dimension, 1000);
+        assert_eq!(images.quality, 70);
+        assert!(matches!(images.format, gsettings::ImageFormat::Png));
+        let notifications = gsettings::notifications();
+        assert!(notifications.enabled);
+        assert_eq!(notifications.interval, 15);
+        assert!(!notifications.replies);
+    }
+
+    #[test]
+    fn current_version_is_not_migrated() {
+        let _guard = test_util::isolate();
+        let data = serde_json::to_vec(&Preferences::default()).unwrap();
+        let (prefs, migrated) = parse_prefs(&data).unwrap();
+        assert!(!migrated);
+        assert_eq!(prefs.version, PREFS_VERSION);
+    }
+
+    #[test]
+    fn invalid_account_index_is_reset() {
+        let _guard = test_util::isolate();
+        let data = br#"{"version": 2, "accounts": [], "current_account_index": 4}"#;
+        let (prefs, _) = parse_prefs(data).unwrap();
+        assert_eq!(prefs.current_account_index, 0);
+    }
+
+    #[test]
+    fn unparsable_file_falls_back_to_backup() {
+        let _guard = test_util::isolate();
+        remove_files();
+        let backup = Preferences {
+            accounts: vec![Account {
+                instance_url: "https://lemmy.ml".to_string(),
+                name: "user".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        std::fs::write(backup_path(), serde_json::to_vec(&backup).unwrap()).unwrap();
+        std::fs::write(data_path(), b"{ not json").unwrap();
+
+        let prefs = get_prefs();
+        assert_eq!(prefs.accounts.len(), 1);
+        assert_eq!(prefs.accounts[0].name, "user");
+        assert!(take_load_error().is_some());
+        // the backup has been restored as the new data.json
+        assert!(parse_prefs(&std::fs::read(data_path()).unwrap()).is_ok());
+        remove_files();
+    }
+
+    #[test]
+    fn unparsable_file_without_backup_falls_back_to_defaults() {
+        let _guard = test_util::isolate();
+        remove_files();
+        std::fs::write(data_path(), b"[]").unwrap();
+
+        let prefs = get_prefs();
+        assert!(prefs.accounts.is_empty());
+        assert!(take_load_error().is_some());
+        remove_files();
+    }
+
+    #[test]
+    fn saving_keeps_the_previous_file_as_backup() {
+        let _guard = test_util::isolate();
+        remove_files();
+        let mut prefs = Preferences::default();
+        write_prefs(&prefs).unwrap();
+        prefs.accounts.push(Account::default());
+        write_prefs(&prefs).unwrap();
+
+        let (backup, _) = parse_prefs(&std::fs::read(backup_path()).unwrap()).unwrap();
+        assert!(backup.accounts.is_empty());
+        assert_eq!(get_prefs().accounts.len(), 1);
+        remove_files();
+    }
+}
@@ -0,0 +1,19 @@
+// the tests use their own data directory and keep gsettings in memory,
+// so that they never touch the accounts and preferences of the user
+use std::sync::{Mutex, MutexGuard, Once};
+
+static SETUP: Once = Once::new();
+// the tests share the files of the data directory, hence they run one after another
+static LOCK: Mutex<()> = Mutex::new(());
+
+// has to be called before anything reads the data directory or the settings
+pub fn isolate() -> MutexGuard<'static, ()> {
+    SETUP.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("lemoa-tests-{}", std::process::id()));
+        std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+        std::env::set_var("XDG_CACHE_HOME", dir.join("cache"));
+        std::env::set_var("GSETTINGS_BACKEND", "memory");
+    });
+    // a failed test shouldn't make all following ones fail too
+    LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
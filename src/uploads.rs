@@ -0,0 +1,65 @@
+use crate::api::image::UploadedImage;
+use crate::config::APP_ID;
+use crate::gtk::glib;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::{fs::File, path::PathBuf};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Upload {
+    // the delete token is only valid on the instance the image was uploaded to
+    pub instance_url: String,
+    pub url: String,
+    pub file: String,
+    pub delete_token: String,
+    pub uploaded: chrono::DateTime<chrono::Utc>,
+}
+
+pub fn uploads_path() -> PathBuf {
+    let mut path = glib::user_data_dir();
+    path.push(APP_ID);
+    std::fs::create_dir_all(&path).expect("Could not create directory.");
+    path.push("uploads.json");
+    path
+}
+
+pub fn save_uploads(uploads: &Vec<Upload>) {
+    let file = File::create(uploads_path()).expect("Could not create json file.");
+    serde_json::to_writer(file, uploads).expect("Could not write data to json file");
+}
+
+pub fn get_all_uploads() -> Vec<Upload> {
+    if let Ok(file) = File::open(uploads_path()) {
+        if let Ok(uploads) = serde_json::from_reader(file) {
+            return uploads;
+        }
+    }
+    vec![]
+}
+
+// all uploads to the current instance, the most recent ones first
+pub fn get_uploads() -> Vec<Upload> {
+    let instance_url = settings::get_current_account().instance_url;
+    let mut uploads = get_all_uploads();
+    uploads.retain(|upload| upload.instance_url == instance_url);
+    uploads.sort_by_key(|upload| std::cmp::Reverse(upload.uploaded));
+    uploads
+}
+
+pub fn add_upload(image: &UploadedImage) {
+    let mut uploads = get_all_uploads();
+    uploads.push(Upload {
+        instance_url: settings::get_current_account().instance_url,
+        url: image.url.clone(),
+        file: image.file.clone(),
+        delete_token: image.delete_token.clone(),
+        uploaded: chrono::Utc::now(),
+    });
+    save_uploads(&uploads);
+}
+
+pub fn remove_upload(url: &str) {
+    let mut uploads = get_all_uploads();
+    uploads.retain(|upload| upload.url != url);
+    save_uploads(&uploads);
+}
@@ -1,5 +1,14 @@
 use lemmy_api_common::lemmy_db_schema::newtypes::DbUrl;
+use relm4::gtk::{self, gdk, glib, prelude::*};
 use relm4_components::web_image::WebImageMsg;
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::api::CLIENT;
+
+thread_local! {
+    // images that are shown multiple times, e.g. emojis, are only downloaded once
+    static TEXTURES: RefCell<HashMap<String, gdk::Texture>> = RefCell::new(HashMap::new());
+}
 
 pub fn get_web_image_msg(url: Option<DbUrl>) -> WebImageMsg {
     if let Some(url) = url {
@@ -26,3 +35,40 @@ pub fn format_elapsed_time(time: chrono::DateTime<chrono::Utc>) -> String {
     let current_time = chrono::Utc::now();
     formatter.convert_chrono(time, current_time)
 }
+
+fn show_texture(picture: &gtk::Picture, texture: &gdk::Texture, max_size: i32) {
+    let (width, height) = (texture.width(), texture.height());
+    let scale = (max_size as f64 / width.max(height).max(1) as f64).min(1.0);
+    picture.set_size_request(
+        (width as f64 * scale) as i32,
+        (height as f64 * scale) as i32,
+    );
+    picture.set_paintable(Some(texture));
+}
+
+// downloads the image in the background and shows it scaled down to fit into `max_size`
+pub fn load_picture(picture: &gtk::Picture, url: String, max_size: i32) {
+    if let Some(texture) = TEXTURES.with(|textures| textures.borrow().get(&url).cloned()) {
+        show_texture(picture, &texture, max_size);
+        return;
+    }
+    picture.set_size_request(max_size, max_size);
+
+    let (tx, rx) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    let picture = picture.clone();
+    rx.attach(None, move |(url, bytes): (String, Vec<u8>)| {
+        if let Ok(texture) = gdk::Texture::from_bytes(&glib::Bytes::from_owned(bytes)) {
+            show_texture(&picture, &texture, max_size);
+            TEXTURES.with(|textures| textures.borrow_mut().insert(url, texture));
+        }
+        glib::Continue(false)
+    });
+    std::thread::spawn(
+        move || match CLIENT.get(&url).send().and_then(|res| res.bytes()) {
+            Ok(bytes) => {
+                let _ = tx.send((url, bytes.to_vec()));
+            }
+            Err(err) => println!("{}", err),
+        },
+    );
+}